@@ -17,10 +17,12 @@ use windows::{
 };
 
 // use serde;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json;
 
-use log::{info, error};
+use log::{info, error, warn};
+use crate::dry_run;
+use super::PowercfgQuery::{parse_powercfg_query, query_unified_power_scheme};
 
 /// 对外公开的电源计划结构体，包含 UUID 和友好名称
 #[derive(Debug, Serialize)]
@@ -392,6 +394,13 @@ pub fn write_value_set(
     ac_value: u32,
     dc_value: u32,
 ) -> Result<(), String> {
+    if dry_run::skip(&format!(
+        "写入电源计划 {} 的设置 {}/{}: AC={} DC={}",
+        guid, subgroup_guid, setting_guid, ac_value, dc_value
+    )) {
+        return Ok(());
+    }
+
     let guid_str = guid.replace("-", "");
     let subgroup_guid_str = subgroup_guid.replace("-", "");
     let setting_guid_str = setting_guid.replace("-", "");
@@ -417,7 +426,7 @@ pub fn write_value_set(
 }
 
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PowerSettingValue {
     pub ac_value: u32,
     pub dc_value: u32,
@@ -603,25 +612,60 @@ fn activate_power_scheme(guid: GUID) -> Result<(), String> {
     Ok(())
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum SettingType {
     Enumerated,
     Range,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SettingData {
     pub value: u32,
     pub name: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PossibleSetting {
     pub setting_type: SettingType,
     pub data: Vec<SettingData>,
     pub unit: Option<String>,
 }
 
+impl PossibleSetting {
+    /// 校验某个值是否属于该设置允许的取值（范围值检查上下限，枚举值检查是否为可选值之一）
+    pub fn validate_value(&self, value: u32) -> Result<(), String> {
+        match self.setting_type {
+            SettingType::Range => {
+                let min = self.data.iter().find(|d| d.name == "min").map(|d| d.value);
+                let max = self.data.iter().find(|d| d.name == "max").map(|d| d.value);
+                match (min, max) {
+                    (Some(min), Some(max)) if value < min || value > max => Err(format!(
+                        "值 {} 超出允许范围 {}-{}{}",
+                        value,
+                        min,
+                        max,
+                        self.unit.as_deref().map(|u| format!(" {}", u)).unwrap_or_default()
+                    )),
+                    _ => Ok(()),
+                }
+            }
+            SettingType::Enumerated => {
+                if self.data.is_empty() || self.data.iter().any(|d| d.value == value) {
+                    Ok(())
+                } else {
+                    let options = self
+                        .data
+                        .iter()
+                        .map(|d| format!("{}({})", d.value, d.name))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    Err(format!("值 {} 不是可选值之一: {}", value, options))
+                }
+            }
+        }
+    }
+}
+
 pub fn get_power_setting_range(
     subgroup_guid_str: &str,
     setting_guid_str: &str,
@@ -892,6 +936,9 @@ pub fn write_setting_attributes(
     setting_guid_str: &str,
     attributes: u32,
 ) -> Result<(), String> {
+    if dry_run::skip(&format!("写入设置 {}/{} 的属性: {}", subgroup_guid_str, setting_guid_str, attributes)) {
+        return Ok(());
+    }
     // 移除横线并转换 GUID
     let subgroup_guid_clean = subgroup_guid_str.replace("-", "");
     let setting_guid_clean = setting_guid_str.replace("-", "");
@@ -961,7 +1008,18 @@ pub struct UnifiedPowerScheme {
     pub subgroups: Vec<UnifiedPowerSubgroup>,
 }
 
+/// 读取电源计划的全部设置；Power API 调用失败时改为解析 powercfg /query 的输出
 pub fn get_unified_power_scheme(guid_str_input: &str) -> Result<UnifiedPowerScheme, String> {
+    read_unified_power_scheme(guid_str_input).or_else(|api_error| {
+        warn!("通过 Power API 读取电源计划失败，改用 powercfg /query: {}", api_error);
+        let mut scheme = query_unified_power_scheme(guid_str_input)
+            .map_err(|e| format!("{}；powercfg /query 也失败: {}", api_error, e))?;
+        scheme.scheme.is_active = get_active_power_scheme().is_ok_and(|active| active == scheme.scheme.uuid);
+        Ok(scheme)
+    })
+}
+
+fn read_unified_power_scheme(guid_str_input: &str) -> Result<UnifiedPowerScheme, String> {
     // 先把传入的guid_str_input转换为全大写
     let guid_str = guid_str_input.to_uppercase();
 
@@ -1016,6 +1074,12 @@ pub fn get_unified_power_scheme(guid_str_input: &str) -> Result<UnifiedPowerSche
     })
 }
 
+/// 解析用户粘贴的 powercfg /query 输出，结果与 get_unified_power_scheme_json_by_scheme_guid 的格式相同
+pub fn parse_powercfg_query_json(output: &str) -> Result<String, String> {
+    let scheme = parse_powercfg_query(output)?;
+    serde_json::to_string_pretty(&scheme).map_err(|e| e.to_string())
+}
+
 pub fn get_unified_power_scheme_json_by_scheme_guid(
     guid_str_input: &str,
 ) -> Result<String, String> {
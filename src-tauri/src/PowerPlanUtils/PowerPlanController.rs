@@ -1,4 +1,4 @@
-use super::GetPowerPlans::{get_power_plans, enumerate_power_scheme_subgroups, PowerPlan, PowerSubgroup,get_unified_power_scheme_json_by_scheme_guid,check_if_scheme_is_valid};
+use super::GetPowerPlans::{get_power_plans, enumerate_power_scheme_subgroups, PowerPlan, PowerSubgroup,get_unified_power_scheme_json_by_scheme_guid,check_if_scheme_is_valid,parse_powercfg_query_json};
 use windows::{
     core::GUID,
 };
@@ -20,6 +20,9 @@ impl PowerPlanController {
     pub fn get_power_plans_json_by_scheme_guid(guid_str: &str)->Result<String,String>{
         get_unified_power_scheme_json_by_scheme_guid(guid_str)
     }
+    pub fn parse_powercfg_query_json(output: &str) -> Result<String, String> {
+        parse_powercfg_query_json(output)
+    }
     pub fn check_if_scheme_is_valid(guid_str: &str) -> bool {
         check_if_scheme_is_valid(guid_str)
     }
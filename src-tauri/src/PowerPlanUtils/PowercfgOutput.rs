@@ -0,0 +1,206 @@
+use encoding_rs::{
+    Encoding, BIG5, EUC_KR, GB18030, GBK, IBM866, SHIFT_JIS, UTF_8, WINDOWS_1250, WINDOWS_1251, WINDOWS_1252,
+    WINDOWS_1253, WINDOWS_1254, WINDOWS_1255, WINDOWS_1256, WINDOWS_1257, WINDOWS_1258, WINDOWS_874,
+};
+use std::os::windows::process::CommandExt;
+use std::process::Command;
+
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+// GUID 的文本长度（8-4-4-4-12）
+pub const GUID_TEXT_LEN: usize = 36;
+
+// 西欧系统控制台常用的 OEM 代码页，encoding_rs 不支持，按 0x80..=0xFF 列出对应字符
+const CP437_HIGH: &str = "\
+    ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜ¢£¥₧ƒ\
+    áíóúñÑªº¿⌐¬½¼¡«»░▒▓│┤╡╢╖╕╣║╗╝╜╛┐\
+    └┴┬├─┼╞╟╚╔╩╦╠═╬╧╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀\
+    αßΓπΣσµτΦΘΩδ∞φε∩≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{a0}";
+const CP850_HIGH: &str = "\
+    ÇüéâäàåçêëèïîìÄÅÉæÆôöòûùÿÖÜø£Ø×ƒ\
+    áíóúñÑªº¿®¬½¼¡«»░▒▓│┤ÁÂÀ©╣║╗╝¢¥┐\
+    └┴┬├─┼ãÃ╚╔╩╦╠═╬¤ðÐÊËÈıÍÎÏ┘┌█▄¦Ì▀\
+    ÓßÔÒõÕµþÞÚÛÙýÝ¯´\u{ad}±‗¾¶§÷¸°¨·¹³²■\u{a0}";
+
+/// 在一行文本中查找第一个 GUID，返回其起始位置和 GUID 字符串
+/// 只看 8-4-4-4-12 位十六进制的格式，不依赖 "GUID:" 之类的本地化标签
+pub fn find_guid(line: &str) -> Option<(usize, &str)> {
+    const GROUPS: [usize; 5] = [8, 4, 4, 4, 12];
+    let bytes = line.as_bytes();
+    if bytes.len() < GUID_TEXT_LEN {
+        return None;
+    }
+    (0..=bytes.len() - GUID_TEXT_LEN).find_map(|start| {
+        // 前后紧挨着十六进制字符时说明是更长的一串，不算 GUID
+        if start > 0 && bytes[start - 1].is_ascii_hexdigit() {
+            return None;
+        }
+        if bytes.get(start + GUID_TEXT_LEN).is_some_and(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let mut pos = start;
+        for (index, len) in GROUPS.iter().enumerate() {
+            if index > 0 {
+                if bytes[pos] != b'-' {
+                    return None;
+                }
+                pos += 1;
+            }
+            if !bytes[pos..pos + len].iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            pos += len;
+        }
+        // 匹配到的都是 ASCII 字符，切片一定落在字符边界上
+        Some((start, &line[start..start + GUID_TEXT_LEN]))
+    })
+}
+
+/// 从 powercfg 的输出中取出第一个 GUID（/duplicatescheme、/import 的新计划）
+pub fn extract_guid(output: &str) -> Result<String, String> {
+    output
+        .lines()
+        .find_map(find_guid)
+        .map(|(_, guid)| guid.to_string())
+        .ok_or_else(|| "无法从输出中提取 GUID".to_string())
+}
+
+fn encoding_for_code_page(code_page: u32) -> Option<&'static Encoding> {
+    let encoding = match code_page {
+        65001 => UTF_8,
+        936 => GBK,
+        54936 => GB18030,
+        950 => BIG5,
+        932 => SHIFT_JIS,
+        949 => EUC_KR,
+        866 => IBM866,
+        874 => WINDOWS_874,
+        1250 => WINDOWS_1250,
+        1251 => WINDOWS_1251,
+        1252 => WINDOWS_1252,
+        1253 => WINDOWS_1253,
+        1254 => WINDOWS_1254,
+        1255 => WINDOWS_1255,
+        1256 => WINDOWS_1256,
+        1257 => WINDOWS_1257,
+        1258 => WINDOWS_1258,
+        _ => return None,
+    };
+    Some(encoding)
+}
+
+fn decode_single_byte(bytes: &[u8], high: &str) -> String {
+    let high: Vec<char> = high.chars().collect();
+    bytes
+        .iter()
+        .map(|&b| if b < 0x80 { b as char } else { high[(b - 0x80) as usize] })
+        .collect()
+}
+
+/// 按指定代码页解码命令输出，解码不会失败
+/// 内容是合法 UTF-8 时（例如控制台已切换到 UTF-8）直接按 UTF-8 处理，
+/// 真实的 GBK、Shift_JIS 等文本几乎不可能恰好是合法 UTF-8
+pub fn decode_with_code_page(bytes: &[u8], code_page: u32) -> String {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return text.to_string();
+    }
+    match code_page {
+        437 => return decode_single_byte(bytes, CP437_HIGH),
+        850 => return decode_single_byte(bytes, CP850_HIGH),
+        _ => {}
+    }
+    if let Some(encoding) = encoding_for_code_page(code_page) {
+        let (cow, had_errors) = encoding.decode_without_bom_handling(bytes);
+        if !had_errors {
+            return cow.into_owned();
+        }
+    }
+    // 未知代码页或解码出错，按 Windows-1252 兜底，至少保证 ASCII 部分（GUID）完整
+    WINDOWS_1252.decode_without_bom_handling(bytes).0.into_owned()
+}
+
+// 控制台程序的输出使用 OEM 代码页，而不是 ANSI 代码页
+fn oem_code_page() -> u32 {
+    unsafe { windows_sys::Win32::Globalization::GetOEMCP() }
+}
+
+/// 按当前系统的 OEM 代码页解码 powercfg 的输出
+pub fn decode_output(bytes: &[u8]) -> String {
+    decode_with_code_page(bytes, oem_code_page())
+}
+
+/// 执行 powercfg 并返回解码后的标准输出
+/// 失败时返回错误信息，powercfg 有时把错误写到标准输出，所以标准错误为空时使用标准输出
+pub fn run_powercfg(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("powercfg")
+        .args(args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("执行命令失败: {}", e))?;
+
+    let stdout = decode_output(&output.stdout);
+    if !output.status.success() {
+        let stderr = decode_output(&output.stderr);
+        let message = if stderr.trim().is_empty() { stdout } else { stderr };
+        return Err(message.trim().to_string());
+    }
+    Ok(stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 各语言 Windows 控制台的 OEM 代码页
+    const LOCALES: [(&str, u32); 4] = [("en", 437), ("de", 850), ("ja", 932), ("zh", 936)];
+
+    fn fixture(kind: &str, locale: &str) -> &'static [u8] {
+        macro_rules! fixtures {
+            ($($kind:literal, $locale:literal;)*) => {
+                match (kind, locale) {
+                    $(($kind, $locale) => include_bytes!(concat!(
+                        env!("CARGO_MANIFEST_DIR"), "/fixtures/powercfg/", $kind, "_", $locale, ".txt"
+                    )).as_slice(),)*
+                    _ => panic!("缺少测试数据 {}_{}", kind, locale),
+                }
+            };
+        }
+        fixtures! {
+            "duplicate", "en"; "duplicate", "de"; "duplicate", "ja"; "duplicate", "zh";
+            "import", "en"; "import", "de"; "import", "ja"; "import", "zh";
+            "list", "en"; "list", "de"; "list", "ja"; "list", "zh";
+            "error", "en"; "error", "de"; "error", "ja"; "error", "zh";
+        }
+    }
+
+    #[test]
+    fn test_extract_guid_in_each_locale() {
+        for (locale, code_page) in LOCALES {
+            let duplicate = decode_with_code_page(fixture("duplicate", locale), code_page);
+            assert_eq!(extract_guid(&duplicate).unwrap(), "3f1b2c4d-5e6f-4a7b-8c9d-0e1f2a3b4c5d", "{}", locale);
+            let import = decode_with_code_page(fixture("import", locale), code_page);
+            assert_eq!(extract_guid(&import).unwrap(), "5e2d7a91-0b3c-4d5e-9f60-718293a4b5c6", "{}", locale);
+            let error = decode_with_code_page(fixture("error", locale), code_page);
+            assert!(extract_guid(&error).is_err(), "{}", locale);
+        }
+    }
+
+    #[test]
+    fn test_decode_with_code_page() {
+        let decoded = |kind, locale| {
+            let code_page = LOCALES.iter().find(|(l, _)| *l == locale).unwrap().1;
+            decode_with_code_page(fixture(kind, locale), code_page)
+        };
+        assert!(decoded("list", "de").contains("(Höchstleistung)"));
+        assert!(decoded("error", "de").starts_with("Ungültige Parameter"));
+        assert!(decoded("list", "ja").contains("(高パフォーマンス)"));
+        assert!(decoded("list", "zh").contains("(高性能)"));
+        assert!(decoded("error", "en").starts_with("Invalid Parameters"));
+
+        // 控制台已经是 UTF-8 时，即使系统代码页是 GBK 也能正确解码
+        assert_eq!(decode_with_code_page("节能".as_bytes(), 936), "节能");
+        // 未知代码页不会失败，GUID 部分保持完整
+        let unknown = decode_with_code_page(fixture("list", "de"), 1);
+        assert_eq!(extract_guid(&unknown).unwrap(), "381b4222-f694-41f0-9685-ff5bb260df2e");
+    }
+}
@@ -0,0 +1,259 @@
+use super::GetPowerPlans::{
+    transfer_guidstr_to_guid, PossibleSetting, PowerPlan, PowerSetting, PowerSettingValue, PowerSubgroup,
+    SettingData, SettingType, UnifiedPowerScheme, UnifiedPowerSetting, UnifiedPowerSubgroup,
+};
+use super::PowercfgOutput::{find_guid, run_powercfg, GUID_TEXT_LEN};
+
+// 标题行 GUID 后面括号里的友好名称，兼容全角括号
+fn heading_name(rest: &str) -> String {
+    let rest = rest.trim();
+    let open = rest.find(['(', '（']);
+    let close = rest.rfind([')', '）']);
+    match (open, close) {
+        (Some(open), Some(close)) if close > open => {
+            let open_len = rest[open..].chars().next().map(char::len_utf8).unwrap_or(1);
+            rest[open + open_len..close].trim().to_string()
+        }
+        _ => rest.to_string(),
+    }
+}
+
+// "标签: 值" 中的值，标签因语言而异，只取冒号后的部分
+fn line_value(line: &str) -> Option<&str> {
+    let index = line.find([':', '：'])?;
+    let colon_len = line[index..].chars().next().map(char::len_utf8).unwrap_or(1);
+    Some(line[index + colon_len..].trim())
+}
+
+// 方案、子组或设置的标题行及其后面各行的值
+struct Block<'a> {
+    guid: &'a str,
+    name: String,
+    values: Vec<&'a str>,
+}
+
+fn parse_hex(value: &str) -> Option<Result<u32, String>> {
+    let digits = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X"))?;
+    Some(u32::from_str_radix(digits, 16).map_err(|e| format!("无法解析数值 {}: {}", value, e)))
+}
+
+// 解析设置块中的值：十六进制数依次为 [最小值, 最大值, 增量,] 当前交流值, 当前直流值；
+// 三位十进制数是可选值索引，下一行为其友好名称；范围值的单位跟在增量之后。
+// 没有任何数值的块是子组，返回 None
+fn parse_setting_values(name: &str, values: &[&str]) -> Result<Option<(PowerSettingValue, PossibleSetting)>, String> {
+    let mut numbers = Vec::new();
+    let mut options = Vec::new();
+    let mut pending_index: Option<u32> = None;
+    let mut unit = None;
+
+    for value in values {
+        // 友好名称本身可能是数字，紧跟在索引后的一行一律当作名称
+        if let Some(index) = pending_index.take() {
+            options.push(SettingData {
+                value: index,
+                name: value.to_string(),
+            });
+        } else if let Some(number) = parse_hex(value) {
+            numbers.push(number?);
+        } else if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+            pending_index = Some(value.parse().map_err(|e| format!("无法解析可选值索引 {}: {}", value, e))?);
+        } else if !numbers.is_empty() {
+            unit = Some(value.to_string()).filter(|u| !u.is_empty());
+        }
+        // 其余为标题后的 GUID 别名
+    }
+
+    if numbers.is_empty() && options.is_empty() {
+        return Ok(None);
+    }
+    if numbers.len() < 2 {
+        return Err(format!("设置 {} 缺少当前交流/直流值", name));
+    }
+    let (range, current) = numbers.split_at(numbers.len() - 2);
+    let current_value = PowerSettingValue {
+        ac_value: current[0],
+        dc_value: current[1],
+    };
+    let possible_values = if range.len() >= 2 {
+        // 与 Power API 的结果一致，范围值只保存最小值和最大值
+        PossibleSetting {
+            setting_type: SettingType::Range,
+            data: vec![
+                SettingData {
+                    value: range[0],
+                    name: "min".to_string(),
+                },
+                SettingData {
+                    value: range[1],
+                    name: "max".to_string(),
+                },
+            ],
+            unit,
+        }
+    } else {
+        PossibleSetting {
+            setting_type: SettingType::Enumerated,
+            data: options,
+            unit: None,
+        }
+    };
+    Ok(Some((current_value, possible_values)))
+}
+
+/// 解析 `powercfg /query [方案 GUID]` 的完整输出，得到与 Power API 相同的 UnifiedPowerScheme
+///
+/// 不依赖各语言的标签文字：含 GUID 的行是标题行，第一个为电源方案；
+/// 后面带有当前交流/直流值的标题是设置，其余是子组。
+/// 输出中没有活动状态和设置属性，is_active 为 false，attributes 为 0
+pub fn parse_powercfg_query(output: &str) -> Result<UnifiedPowerScheme, String> {
+    let mut blocks: Vec<Block> = Vec::new();
+    for line in output.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some((start, guid)) = find_guid(line) {
+            blocks.push(Block {
+                guid,
+                name: heading_name(&line[start + GUID_TEXT_LEN..]),
+                values: Vec::new(),
+            });
+        } else if let (Some(block), Some(value)) = (blocks.last_mut(), line_value(line)) {
+            block.values.push(value);
+        }
+    }
+
+    let mut blocks = blocks.into_iter();
+    let scheme_block = blocks.next().ok_or("powercfg 输出中没有找到电源方案")?;
+    let scheme = PowerPlan {
+        uuid: transfer_guidstr_to_guid(scheme_block.guid)?,
+        name: scheme_block.name,
+        is_active: false,
+    };
+
+    let mut subgroups: Vec<UnifiedPowerSubgroup> = Vec::new();
+    for block in blocks {
+        let uuid = transfer_guidstr_to_guid(block.guid)?;
+        match parse_setting_values(&block.name, &block.values)? {
+            None => subgroups.push(UnifiedPowerSubgroup {
+                subgroup: PowerSubgroup { uuid, name: block.name },
+                settings: Vec::new(),
+            }),
+            Some((current_value, possible_values)) => {
+                let subgroup = subgroups
+                    .last_mut()
+                    .ok_or_else(|| format!("设置 {} 不属于任何子组", block.name))?;
+                subgroup.settings.push(UnifiedPowerSetting {
+                    setting: PowerSetting { uuid, name: block.name },
+                    current_value,
+                    possible_values,
+                    attributes: 0,
+                });
+            }
+        }
+    }
+
+    Ok(UnifiedPowerScheme { scheme, subgroups })
+}
+
+/// 通过 powercfg /query 读取电源计划，Power API 调用失败时使用
+pub fn query_unified_power_scheme(guid_str: &str) -> Result<UnifiedPowerScheme, String> {
+    let output = run_powercfg(&["/query", guid_str])?;
+    parse_powercfg_query(&output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUERY_EN: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/powercfg/query_en.txt"));
+    const QUERY_DE: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/powercfg/query_de.txt"));
+    const QUERY_JA: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/powercfg/query_ja.txt"));
+    const QUERY_ZH: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/powercfg/query_zh.txt"));
+
+    fn guid(value: &impl std::fmt::Debug) -> String {
+        format!("{:?}", value).to_lowercase()
+    }
+
+    // 各语言的输出结构相同，只有名称不同
+    fn assert_balanced(scheme: &UnifiedPowerScheme, plan_name: &str, cooling_options: [&str; 2], seconds: &str) {
+        assert_eq!(guid(&scheme.scheme.uuid), "381b4222-f694-41f0-9685-ff5bb260df2e");
+        assert_eq!(scheme.scheme.name, plan_name);
+        assert_eq!(scheme.subgroups.len(), 4);
+
+        let disk = &scheme.subgroups[0];
+        assert_eq!(guid(&disk.subgroup.uuid), "0012ee47-9041-4b5d-9b77-535fba8b1442");
+        let disk_idle = &disk.settings[0];
+        assert_eq!((disk_idle.current_value.ac_value, disk_idle.current_value.dc_value), (1200, 600));
+        assert!(matches!(disk_idle.possible_values.setting_type, SettingType::Range));
+        assert_eq!(disk_idle.possible_values.data[1].value, u32::MAX);
+        assert_eq!(disk_idle.possible_values.unit.as_deref(), Some(seconds));
+
+        // 没有设置的子组
+        assert!(scheme.subgroups[1].settings.is_empty());
+
+        let processor = &scheme.subgroups[2];
+        assert_eq!(processor.settings.len(), 3);
+        let cooling = &processor.settings[1];
+        assert_eq!(guid(&cooling.setting.uuid), "94d3a615-a899-4ac5-ae2b-e4d8f634367f");
+        assert!(matches!(cooling.possible_values.setting_type, SettingType::Enumerated));
+        let names: Vec<&str> = cooling.possible_values.data.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, cooling_options);
+        assert_eq!((cooling.current_value.ac_value, cooling.current_value.dc_value), (1, 0));
+
+        let max_state = &processor.settings[2];
+        assert_eq!(max_state.possible_values.data[1].value, 100);
+        assert_eq!(max_state.possible_values.unit.as_deref(), Some("%"));
+        assert!(max_state.possible_values.validate_value(101).is_err());
+
+        let display = &scheme.subgroups[3].settings[0];
+        assert_eq!((display.current_value.ac_value, display.current_value.dc_value), (600, 300));
+    }
+
+    #[test]
+    fn test_parse_query_in_each_locale() {
+        assert_balanced(&parse_powercfg_query(QUERY_EN).unwrap(), "Balanced", ["Passive", "Active"], "Seconds");
+        assert_balanced(&parse_powercfg_query(QUERY_DE).unwrap(), "Ausbalanciert", ["Passiv", "Aktiv"], "Sekunden");
+        assert_balanced(&parse_powercfg_query(QUERY_JA).unwrap(), "バランス", ["パッシブ", "アクティブ"], "秒");
+        assert_balanced(&parse_powercfg_query(QUERY_ZH).unwrap(), "平衡", ["被动", "主动"], "秒");
+    }
+
+    #[test]
+    fn test_parse_pasted_dump_without_indentation_and_with_crlf() {
+        let pasted: String = QUERY_EN.lines().map(|line| format!("{}\r\n", line.trim())).collect();
+        assert_balanced(&parse_powercfg_query(&pasted).unwrap(), "Balanced", ["Passive", "Active"], "Seconds");
+    }
+
+    #[test]
+    fn test_parse_query_errors() {
+        assert!(parse_powercfg_query("").is_err());
+        assert!(parse_powercfg_query("Invalid Parameters -- try \"/?\" for help").is_err());
+
+        // 设置出现在任何子组之前
+        let orphan = "Power Scheme GUID: 381b4222-f694-41f0-9685-ff5bb260df2e  (Balanced)\n\
+            Power Setting GUID: bc5038f7-23e0-4960-96da-33abaf5935ec  (Maximum processor state)\n\
+            Current AC Power Setting Index: 0x00000064\n\
+            Current DC Power Setting Index: 0x00000064\n";
+        assert!(parse_powercfg_query(orphan).is_err());
+
+        // 缺少直流值
+        let truncated = "Power Scheme GUID: 381b4222-f694-41f0-9685-ff5bb260df2e  (Balanced)\n\
+            Subgroup GUID: 54533251-82be-4824-96c1-47b60b740d00  (Processor power management)\n\
+            Power Setting GUID: 94d3a615-a899-4ac5-ae2b-e4d8f634367f  (System cooling policy)\n\
+            Possible Setting Index: 000\n\
+            Possible Setting Friendly Name: Passive\n\
+            Current AC Power Setting Index: 0x00000001\n";
+        assert!(parse_powercfg_query(truncated).is_err());
+    }
+
+    #[test]
+    fn test_find_guid_ignores_labels() {
+        let line = "GUID des Energieschemas: 381b4222-f694-41f0-9685-ff5bb260df2e  (Ausbalanciert)";
+        let (start, found) = find_guid(line).unwrap();
+        assert_eq!(found, "381b4222-f694-41f0-9685-ff5bb260df2e");
+        assert_eq!(heading_name(&line[start + GUID_TEXT_LEN..]), "Ausbalanciert");
+        assert_eq!(heading_name("  （平衡）"), "平衡");
+        assert!(find_guid("GUID Alias: SCHEME_BALANCED").is_none());
+        assert!(find_guid("0381b4222-f694-41f0-9685-ff5bb260df2e").is_none());
+    }
+}
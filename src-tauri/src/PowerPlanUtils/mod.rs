@@ -1,2 +1,4 @@
 pub mod GetPowerPlans;
-pub mod PowerPlanController;
\ No newline at end of file
+pub mod PowerPlanController;
+pub mod PowercfgOutput;
+pub mod PowercfgQuery;
@@ -0,0 +1,365 @@
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{oneshot, Notify};
+
+use crate::action_journal::{now_millis, JournalTrigger};
+use crate::trigger_action::{self, TriggerAction};
+
+// 全局执行器，监控循环只负责把动作放进队列，不等待执行结束
+pub static ACTION_EXECUTOR: Lazy<ActionExecutor> = Lazy::new(ActionExecutor::new);
+
+// 取消标记，由正在执行的动作在等待和步骤之间检查
+pub struct CancelToken {
+    cancelled: AtomicBool,
+    reason: Mutex<Option<String>>,
+    notify: Notify,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self {
+            cancelled: AtomicBool::new(false),
+            reason: Mutex::new(None),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn cancel(&self, reason: &str) {
+        if let Ok(mut current) = self.reason.lock() {
+            // 只保留第一次取消的原因
+            if current.is_none() {
+                *current = Some(reason.to_string());
+            }
+        }
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn reason(&self) -> String {
+        self.reason
+            .lock()
+            .ok()
+            .and_then(|r| r.clone())
+            .unwrap_or_else(|| "触发动作已取消".to_string())
+    }
+
+    // 已取消时返回取消原因作为错误
+    pub fn check(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            Err(self.reason())
+        } else {
+            Ok(())
+        }
+    }
+
+    // 等待直到被取消
+    pub async fn cancelled(&self) {
+        loop {
+            // 先创建等待对象再检查标记，避免错过检查后到等待前发出的通知
+            let notified = self.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    // 可被取消的等待，被取消时返回取消原因
+    pub async fn sleep(&self, duration: tokio::time::Duration) -> Result<(), String> {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => Ok(()),
+            _ = self.cancelled() => Err(self.reason()),
+        }
+    }
+}
+
+impl Default for CancelToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct QueuedAction {
+    action: TriggerAction,
+    trigger: JournalTrigger,
+    queued_at: u64,
+    // 需要知道执行结果的调用方在这里等待
+    done: Option<oneshot::Sender<Result<(), String>>>,
+}
+
+struct RunningAction {
+    action_id: String,
+    action_name: String,
+    started_at: u64,
+    cancel: Arc<CancelToken>,
+}
+
+// 返回给前端的执行器状态
+#[derive(Serialize, Clone, Debug)]
+pub struct ExecutionInfo {
+    pub action_id: String,
+    pub action_name: String,
+    // 排队中的动作为入队时间，执行中的动作为开始时间
+    pub since: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ExecutorStatus {
+    pub running: Option<ExecutionInfo>,
+    pub queued: Vec<ExecutionInfo>,
+}
+
+#[derive(Default)]
+struct ExecutorState {
+    queue: VecDeque<QueuedAction>,
+    running: Option<RunningAction>,
+}
+
+impl ExecutorState {
+    fn is_pending(&self, action_id: &str) -> bool {
+        self.running.as_ref().is_some_and(|r| r.action_id == action_id)
+            || self.queue.iter().any(|q| q.action.id == action_id)
+    }
+
+    // 同一个动作正在执行或已在队列中时不再重复加入
+    fn enqueue(
+        &mut self,
+        action: TriggerAction,
+        trigger: JournalTrigger,
+        queued_at: u64,
+        done: Option<oneshot::Sender<Result<(), String>>>,
+    ) -> Result<(), String> {
+        if self.is_pending(&action.id) {
+            return Err(format!("触发动作 {} 正在执行或已在队列中", action.name));
+        }
+        self.queue.push_back(QueuedAction {
+            action,
+            trigger,
+            queued_at,
+            done,
+        });
+        Ok(())
+    }
+
+    // 取消排队中的动作返回 true；正在执行的动作由调用方通过取消标记处理
+    fn remove_queued(&mut self, action_id: &str) -> bool {
+        let before = self.queue.len();
+        self.queue.retain(|q| q.action.id != action_id);
+        self.queue.len() != before
+    }
+
+    fn status(&self) -> ExecutorStatus {
+        ExecutorStatus {
+            running: self.running.as_ref().map(|r| ExecutionInfo {
+                action_id: r.action_id.clone(),
+                action_name: r.action_name.clone(),
+                since: r.started_at,
+            }),
+            queued: self
+                .queue
+                .iter()
+                .map(|q| ExecutionInfo {
+                    action_id: q.action.id.clone(),
+                    action_name: q.action.name.clone(),
+                    since: q.queued_at,
+                })
+                .collect(),
+        }
+    }
+}
+
+pub struct ActionExecutor {
+    state: Mutex<ExecutorState>,
+    wakeup: Notify,
+    worker_started: AtomicBool,
+}
+
+impl ActionExecutor {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(ExecutorState::default()),
+            wakeup: Notify::new(),
+            worker_started: AtomicBool::new(false),
+        }
+    }
+
+    // 把动作加入队列，立即返回
+    pub fn submit(&'static self, action: TriggerAction, trigger: JournalTrigger) -> Result<(), String> {
+        self.queue_action(action, trigger, None)
+    }
+
+    // 把动作加入队列，返回的接收端在动作执行结束后收到执行结果
+    // 动作在执行前被移出队列时接收端会收到错误
+    pub fn submit_with_result(
+        &'static self,
+        action: TriggerAction,
+        trigger: JournalTrigger,
+    ) -> Result<oneshot::Receiver<Result<(), String>>, String> {
+        let (done, result) = oneshot::channel();
+        self.queue_action(action, trigger, Some(done))?;
+        Ok(result)
+    }
+
+    fn queue_action(
+        &'static self,
+        action: TriggerAction,
+        trigger: JournalTrigger,
+        done: Option<oneshot::Sender<Result<(), String>>>,
+    ) -> Result<(), String> {
+        self.ensure_worker();
+        let name = action.name.clone();
+        self.state
+            .lock()
+            .map_err(|e| format!("获取执行队列锁失败: {}", e))?
+            .enqueue(action, trigger, now_millis(), done)?;
+        info!("触发动作 {} 已加入执行队列", name);
+        self.wakeup.notify_one();
+        Ok(())
+    }
+
+    // 取消排队中或正在执行的动作，返回是否找到该动作
+    pub fn cancel(&self, action_id: &str) -> Result<bool, String> {
+        let mut state = self.state.lock().map_err(|e| format!("获取执行队列锁失败: {}", e))?;
+        if state.remove_queued(action_id) {
+            info!("已从执行队列中移除触发动作 {}", action_id);
+            return Ok(true);
+        }
+        match &state.running {
+            Some(running) if running.action_id == action_id => {
+                info!("正在取消触发动作 {}", running.action_name);
+                running.cancel.cancel("触发动作已被手动取消");
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    pub fn status(&self) -> Result<ExecutorStatus, String> {
+        Ok(self
+            .state
+            .lock()
+            .map_err(|e| format!("获取执行队列锁失败: {}", e))?
+            .status())
+    }
+
+    fn ensure_worker(&'static self) {
+        if self.worker_started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        tauri::async_runtime::spawn(self.run_worker());
+    }
+
+    // 依次执行队列中的动作，同一时间只执行一个，避免多个动作同时切换电源计划
+    async fn run_worker(&'static self) {
+        info!("触发动作执行器已启动");
+        loop {
+            let next = match self.state.lock() {
+                Ok(mut state) => state.queue.pop_front().map(|queued| {
+                    let cancel = Arc::new(CancelToken::new());
+                    state.running = Some(RunningAction {
+                        action_id: queued.action.id.clone(),
+                        action_name: queued.action.name.clone(),
+                        started_at: now_millis(),
+                        cancel: cancel.clone(),
+                    });
+                    (queued, cancel)
+                }),
+                Err(e) => {
+                    warn!("获取执行队列锁失败: {}", e);
+                    None
+                }
+            };
+
+            let Some((queued, cancel)) = next else {
+                self.wakeup.notified().await;
+                continue;
+            };
+
+            let QueuedAction { action, trigger, done, .. } = queued;
+            let result = trigger_action::execute_trigger_action(&action, trigger, &cancel).await;
+            if let Some(done) = done {
+                let _ = done.send(result);
+            }
+
+            if let Ok(mut state) = self.state.lock() {
+                state.running = None;
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn cancel_trigger_action(action_id: String) -> Result<bool, String> {
+    ACTION_EXECUTOR.cancel(&action_id)
+}
+
+#[tauri::command]
+pub async fn get_action_executor_status() -> Result<ExecutorStatus, String> {
+    ACTION_EXECUTOR.status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(id: &str) -> TriggerAction {
+        TriggerAction {
+            id: id.to_string(),
+            name: id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn trigger() -> JournalTrigger {
+        JournalTrigger::threshold(&[4500], 4.0)
+    }
+
+    #[test]
+    fn test_enqueue_rejects_pending_action() {
+        let mut state = ExecutorState::default();
+        assert!(state.enqueue(action("a"), trigger(), 1, None).is_ok());
+        assert!(state.enqueue(action("b"), trigger(), 2, None).is_ok());
+        assert!(state.enqueue(action("a"), trigger(), 3, None).is_err());
+
+        // 正在执行的动作同样不能重复加入
+        let queued = state.queue.pop_front().unwrap();
+        state.running = Some(RunningAction {
+            action_id: queued.action.id.clone(),
+            action_name: queued.action.name.clone(),
+            started_at: 4,
+            cancel: Arc::new(CancelToken::new()),
+        });
+        assert!(state.enqueue(action("a"), trigger(), 5, None).is_err());
+
+        state.running = None;
+        assert!(state.enqueue(action("a"), trigger(), 6, None).is_ok());
+        let status = state.status();
+        assert_eq!(status.queued.iter().map(|q| q.action_id.as_str()).collect::<Vec<_>>(), vec!["b", "a"]);
+    }
+
+    #[test]
+    fn test_remove_queued_action() {
+        let mut state = ExecutorState::default();
+        state.enqueue(action("a"), trigger(), 1, None).unwrap();
+        assert!(state.remove_queued("a"));
+        assert!(!state.remove_queued("a"));
+        assert!(state.queue.is_empty());
+    }
+
+    #[test]
+    fn test_cancel_token_keeps_first_reason() {
+        let token = CancelToken::new();
+        assert!(token.check().is_ok());
+        token.cancel("超时");
+        token.cancel("手动取消");
+        assert_eq!(token.check().unwrap_err(), "超时");
+    }
+}
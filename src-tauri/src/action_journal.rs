@@ -0,0 +1,545 @@
+use log::{error, info};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::dry_run;
+use crate::versioned_file::exe_dir;
+
+// 日志文件超过该大小后才裁剪，平时追加记录不需要读取整个文件
+const MAX_JOURNAL_BYTES: u64 = 4 * 1024 * 1024;
+
+// 裁剪后保留的最近记录的大小，留出余量，避免刚裁剪完又超过上限
+const TRIMMED_JOURNAL_BYTES: usize = 2 * 1024 * 1024;
+
+// 追加和裁剪日志文件时加锁，避免并发写入交错
+static JOURNAL_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+// 触发动作被执行的原因
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JournalTrigger {
+    // 频率超过阈值，记录超限的核心及其频率（MHz）
+    Threshold {
+        cores: Vec<usize>,
+        frequencies: Vec<u64>,
+        threshold: f64,
+    },
+    // 规则条件满足，记录规则及满足的原因
+    Rule {
+        rule_id: String,
+        rule_name: String,
+        explanation: String,
+    },
+    // 定时任务到期，scheduled_at 为计划执行的本地时间
+    Schedule {
+        schedule_id: String,
+        schedule_name: String,
+        scheduled_at: String,
+    },
+    // 设置保护发现电源设置与快照不一致
+    Drift {
+        plan_guid: String,
+        drifts: Vec<JournalDrift>,
+    },
+}
+
+// 与快照不一致的单个电源设置，由设置保护填写
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct JournalDrift {
+    pub subgroup_guid: String,
+    pub setting_guid: String,
+    #[serde(default)]
+    pub name: String,
+    pub expected_ac: u32,
+    pub expected_dc: u32,
+    pub actual_ac: u32,
+    pub actual_dc: u32,
+}
+
+impl JournalDrift {
+    fn describe(&self) -> String {
+        let label = if self.name.is_empty() { &self.setting_guid } else { &self.name };
+        format!(
+            "{} AC {}→{} DC {}→{}",
+            label, self.actual_ac, self.expected_ac, self.actual_dc, self.expected_dc
+        )
+    }
+}
+
+impl JournalTrigger {
+    // 根据本次采样的频率和阈值生成触发原因
+    pub fn threshold(frequencies: &[u64], threshold: f64) -> Self {
+        let (cores, frequencies) = frequencies
+            .iter()
+            .enumerate()
+            .filter(|(_, &freq)| freq as f64 / 1000.0 > threshold)
+            .map(|(core, &freq)| (core, freq))
+            .unzip();
+        JournalTrigger::Threshold {
+            cores,
+            frequencies,
+            threshold,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            JournalTrigger::Threshold { cores, frequencies, threshold } => {
+                let detail: Vec<String> = cores
+                    .iter()
+                    .zip(frequencies)
+                    .map(|(core, freq)| format!("#{}={}MHz", core, freq))
+                    .collect();
+                format!("threshold {:.2}GHz [{}]", threshold, detail.join(" "))
+            }
+            JournalTrigger::Rule { rule_name, explanation, .. } => {
+                format!("rule {}: {}", rule_name, explanation)
+            }
+            JournalTrigger::Schedule { schedule_name, scheduled_at, .. } => {
+                format!("schedule {} at {}", schedule_name, scheduled_at)
+            }
+            JournalTrigger::Drift { plan_guid, drifts } => {
+                let detail: Vec<String> = drifts.iter().map(|d| d.describe()).collect();
+                format!("drift {} [{}]", plan_guid, detail.join("; "))
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalOutcome {
+    Success,
+    Failed,
+}
+
+// 执行过程中的单个步骤，例如一次电源计划切换
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JournalStep {
+    pub kind: String,
+    // 步骤操作的对象，例如电源计划或电源设置的 GUID
+    pub target: Option<String>,
+    pub attempt: u32,
+    pub started_at: u64,
+    pub duration_ms: u64,
+    pub success: bool,
+    pub error: Option<String>,
+    // 试运行时该步骤本应执行、但被跳过的操作
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub planned: Vec<String>,
+}
+
+// 一次触发动作执行的完整记录
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JournalEntry {
+    pub id: String,
+    pub action_id: String,
+    pub action_name: String,
+    pub worker: String,
+    pub trigger: JournalTrigger,
+    pub started_at: u64,
+    pub duration_ms: u64,
+    pub outcome: JournalOutcome,
+    pub error: Option<String>,
+    pub steps: Vec<JournalStep>,
+    // 是否为试运行，试运行不会修改任何电源设置
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+// 查询条件，时间均为毫秒时间戳
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct JournalFilter {
+    pub action_id: Option<String>,
+    pub outcome: Option<JournalOutcome>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+impl JournalFilter {
+    fn matches(&self, entry: &JournalEntry) -> bool {
+        if let Some(action_id) = &self.action_id {
+            if &entry.action_id != action_id {
+                return false;
+            }
+        }
+        if let Some(outcome) = self.outcome {
+            if entry.outcome != outcome {
+                return false;
+            }
+        }
+        if let Some(since) = self.since {
+            if entry.started_at < since {
+                return false;
+            }
+        }
+        if let Some(until) = self.until {
+            if entry.started_at > until {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// 正在进行的步骤
+pub struct StepTimer {
+    kind: String,
+    target: Option<String>,
+    attempt: u32,
+    started_at: u64,
+    started: Instant,
+}
+
+// 在执行过程中收集步骤，执行结束后生成一条日志记录
+pub struct JournalRecorder {
+    action_id: String,
+    action_name: String,
+    worker: String,
+    trigger: JournalTrigger,
+    started_at: u64,
+    started: Instant,
+    steps: Mutex<Vec<JournalStep>>,
+}
+
+impl JournalRecorder {
+    pub fn new(action_id: &str, action_name: &str, worker: &str, trigger: JournalTrigger) -> Self {
+        Self {
+            action_id: action_id.to_string(),
+            action_name: action_name.to_string(),
+            worker: worker.to_string(),
+            trigger,
+            started_at: now_millis(),
+            started: Instant::now(),
+            steps: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn start_step(&self, kind: &str, target: Option<&str>, attempt: u32) -> StepTimer {
+        StepTimer {
+            kind: kind.to_string(),
+            target: target.map(|t| t.to_string()),
+            attempt,
+            started_at: now_millis(),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn finish_step<T>(&self, timer: StepTimer, result: &Result<T, String>) {
+        let step = JournalStep {
+            kind: timer.kind,
+            target: timer.target,
+            attempt: timer.attempt,
+            started_at: timer.started_at,
+            duration_ms: timer.started.elapsed().as_millis() as u64,
+            success: result.is_ok(),
+            error: result.as_ref().err().cloned(),
+            planned: dry_run::take_planned(),
+        };
+        if let Ok(mut steps) = self.steps.lock() {
+            steps.push(step);
+        }
+    }
+
+    // 已记录的步骤
+    #[cfg(test)]
+    pub fn steps(&self) -> Vec<JournalStep> {
+        self.steps.lock().map(|steps| steps.clone()).unwrap_or_default()
+    }
+
+    // 结束记录并写入日志文件
+    pub fn finish(self, result: &Result<(), String>) -> JournalEntry {
+        let entry = JournalEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            action_id: self.action_id,
+            action_name: self.action_name,
+            worker: self.worker,
+            trigger: self.trigger,
+            started_at: self.started_at,
+            duration_ms: self.started.elapsed().as_millis() as u64,
+            outcome: if result.is_ok() { JournalOutcome::Success } else { JournalOutcome::Failed },
+            error: result.as_ref().err().cloned(),
+            steps: self.steps.into_inner().unwrap_or_default(),
+            dry_run: dry_run::is_enabled(),
+        };
+        if let Err(e) = append_entry(&entry) {
+            error!("写入触发动作执行日志失败: {}", e);
+        }
+        entry
+    }
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+fn get_journal_path() -> PathBuf {
+    exe_dir().join("action_journal.jsonl")
+}
+
+fn append_entry(entry: &JournalEntry) -> Result<(), String> {
+    let line = serde_json::to_string(entry).map_err(|e| format!("序列化执行日志失败: {}", e))?;
+    let path = get_journal_path();
+    let _guard = JOURNAL_LOCK.lock().map_err(|e| format!("获取执行日志锁失败: {}", e))?;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("打开执行日志失败: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("写入执行日志失败: {}", e))?;
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    drop(file);
+
+    if size > MAX_JOURNAL_BYTES {
+        trim_journal(&path, TRIMMED_JOURNAL_BYTES)?;
+    }
+    Ok(())
+}
+
+// 从最新的记录往前保留，总大小不超过 keep_bytes
+fn trim_journal(path: &Path, keep_bytes: usize) -> Result<(), String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("读取执行日志失败: {}", e))?;
+    let mut kept_bytes = 0;
+    let mut kept: Vec<&str> = content
+        .lines()
+        .rev()
+        .filter(|l| !l.trim().is_empty())
+        .take_while(|line| {
+            kept_bytes += line.len() + 1;
+            kept_bytes <= keep_bytes
+        })
+        .collect();
+    kept.reverse();
+
+    let trimmed: String = kept.iter().map(|line| format!("{}\n", line)).collect();
+    fs::write(path, trimmed).map_err(|e| format!("裁剪执行日志失败: {}", e))?;
+    info!("执行日志已裁剪为最近 {} 条", kept.len());
+    Ok(())
+}
+
+// 按条件读取日志，结果按时间从新到旧排列
+pub fn read_entries(filter: &JournalFilter) -> Result<Vec<JournalEntry>, String> {
+    let path = get_journal_path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = {
+        let _guard = JOURNAL_LOCK.lock().map_err(|e| format!("获取执行日志锁失败: {}", e))?;
+        fs::read_to_string(&path).map_err(|e| format!("读取执行日志失败: {}", e))?
+    };
+    Ok(filter_entries(&content, filter))
+}
+
+fn filter_entries(content: &str, filter: &JournalFilter) -> Vec<JournalEntry> {
+    let mut entries: Vec<JournalEntry> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        // 跳过损坏的行，不影响其他记录
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .filter(|entry| filter.matches(entry))
+        .collect();
+    entries.reverse();
+    if let Some(limit) = filter.limit {
+        entries.truncate(limit);
+    }
+    entries
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') || field.contains('\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// 每条执行记录一行，步骤合并到同一列中
+fn entries_to_csv(entries: &[JournalEntry]) -> String {
+    let mut csv = String::from("id,started_at,action_id,action_name,worker,trigger,duration_ms,outcome,error,steps\n");
+    for entry in entries {
+        let steps: Vec<String> = entry
+            .steps
+            .iter()
+            .map(|step| {
+                let planned = if step.planned.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [planned: {}]", step.planned.join(" | "))
+                };
+                format!(
+                    "{}({}) #{} {} {}ms{}{}",
+                    step.kind,
+                    step.target.as_deref().unwrap_or("-"),
+                    step.attempt,
+                    if step.success { "ok" } else { "failed" },
+                    step.duration_ms,
+                    step.error.as_deref().map(|e| format!(": {}", e)).unwrap_or_default(),
+                    planned
+                )
+            })
+            .collect();
+        let fields = [
+            entry.id.clone(),
+            entry.started_at.to_string(),
+            entry.action_id.clone(),
+            entry.action_name.clone(),
+            entry.worker.clone(),
+            entry.trigger.describe(),
+            entry.duration_ms.to_string(),
+            match (entry.outcome, entry.dry_run) {
+                (JournalOutcome::Success, false) => "success".to_string(),
+                (JournalOutcome::Failed, false) => "failed".to_string(),
+                (JournalOutcome::Success, true) => "success (dry run)".to_string(),
+                (JournalOutcome::Failed, true) => "failed (dry run)".to_string(),
+            },
+            entry.error.clone().unwrap_or_default(),
+            steps.join("; "),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| escape_csv(f)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+#[tauri::command]
+pub async fn query_action_journal(filter: Option<JournalFilter>) -> Result<Vec<JournalEntry>, String> {
+    read_entries(&filter.unwrap_or_default())
+}
+
+// 导出为 CSV 文件，返回导出的记录条数
+#[tauri::command]
+pub async fn export_action_journal_csv(path: String, filter: Option<JournalFilter>) -> Result<usize, String> {
+    let entries = read_entries(&filter.unwrap_or_default())?;
+    fs::write(&path, entries_to_csv(&entries)).map_err(|e| format!("导出执行日志失败: {}", e))?;
+    info!("已导出 {} 条触发动作执行日志到 {}", entries.len(), path);
+    Ok(entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_entry(action_id: &str, started_at: u64, outcome: JournalOutcome) -> JournalEntry {
+        JournalEntry {
+            id: format!("{}-{}", action_id, started_at),
+            action_id: action_id.to_string(),
+            action_name: "降频, \"测试\"".to_string(),
+            worker: "simple".to_string(),
+            trigger: JournalTrigger::threshold(&[3000, 4500, 4700], 4.0),
+            started_at,
+            duration_ms: 1200,
+            outcome,
+            error: None,
+            steps: vec![JournalStep {
+                kind: "switch_plan".to_string(),
+                target: Some("PLAN".to_string()),
+                attempt: 1,
+                started_at,
+                duration_ms: 35,
+                success: true,
+                error: None,
+                planned: Vec::new(),
+            }],
+            dry_run: false,
+        }
+    }
+
+    #[test]
+    fn test_threshold_trigger_keeps_exceeded_cores() {
+        let JournalTrigger::Threshold { cores, frequencies, .. } = JournalTrigger::threshold(&[3000, 4500, 4700], 4.0)
+        else {
+            panic!("应为阈值触发");
+        };
+        assert_eq!(cores, vec![1, 2]);
+        assert_eq!(frequencies, vec![4500, 4700]);
+    }
+
+    #[test]
+    fn test_filter_entries() {
+        let lines: Vec<String> = [
+            sample_entry("a", 1000, JournalOutcome::Success),
+            sample_entry("b", 2000, JournalOutcome::Failed),
+            sample_entry("a", 3000, JournalOutcome::Failed),
+        ]
+        .iter()
+        .map(|e| serde_json::to_string(e).unwrap())
+        .collect();
+        let content = format!("{}\nnot json\n{}\n{}\n", lines[0], lines[1], lines[2]);
+
+        let all = filter_entries(&content, &JournalFilter::default());
+        assert_eq!(all.iter().map(|e| e.started_at).collect::<Vec<_>>(), vec![3000, 2000, 1000]);
+
+        let filter = JournalFilter {
+            action_id: Some("a".to_string()),
+            outcome: Some(JournalOutcome::Failed),
+            ..Default::default()
+        };
+        let failed_a = filter_entries(&content, &filter);
+        assert_eq!(failed_a.len(), 1);
+        assert_eq!(failed_a[0].started_at, 3000);
+
+        let filter = JournalFilter {
+            since: Some(1500),
+            limit: Some(1),
+            ..Default::default()
+        };
+        let recent = filter_entries(&content, &filter);
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].started_at, 3000);
+    }
+
+    #[test]
+    fn test_entries_to_csv_escapes_fields() {
+        let csv = entries_to_csv(&[sample_entry("a", 1000, JournalOutcome::Success)]);
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.contains("\"降频, \"\"测试\"\"\""));
+        assert!(row.contains("threshold 4.00GHz [#1=4500MHz #2=4700MHz]"));
+        assert!(row.contains("switch_plan(PLAN) #1 ok 35ms"));
+    }
+
+    #[test]
+    fn test_trim_journal_keeps_newest_lines() {
+        let dir = crate::test_support::temp_dir("action_journal");
+        let path = dir.join("action_journal.jsonl");
+        fs::write(&path, "line-1\nline-2\n\nline-3\nline-4\n").unwrap();
+
+        // 每行连同换行符 7 字节，保留 15 字节只能放下最后两行
+        trim_journal(&path, 15).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "line-3\nline-4\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_drift_trigger_reads_existing_lines() {
+        // 旧记录中每项漂移还带有 plan_guid，解析时忽略
+        let trigger: JournalTrigger = serde_json::from_str(
+            r#"{
+                "type": "drift",
+                "plan_guid": "PLAN",
+                "drifts": [{
+                    "plan_guid": "PLAN",
+                    "subgroup_guid": "SUB",
+                    "setting_guid": "SETTING",
+                    "expected_ac": 100,
+                    "expected_dc": 50,
+                    "actual_ac": 80,
+                    "actual_dc": 50
+                }]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(trigger.describe(), "drift PLAN [SETTING AC 80→100 DC 50→50]");
+    }
+}
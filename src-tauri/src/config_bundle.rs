@@ -0,0 +1,620 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+use crate::dry_run;
+use crate::power_plan::{delete_power_plan, export_power_plan, get_power_plans, import_power_plan};
+use crate::power_settings_preferences_store::PowerSettingsPreferences;
+use crate::settings::Settings;
+use crate::settings_store;
+use crate::trigger_action::{
+    deserialize_trigger_actions_compat, load_trigger_actions, save_trigger_actions, TriggerAction,
+};
+
+// 当前配置包格式版本
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+// 不随配置包导入的本机设置项
+const LOCAL_ONLY_SETTINGS: [&str; 1] = ["accepted_terms_of_service"];
+
+// 配置包中携带的电源计划（.pow 文件内容以 base64 存储）
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BundledPowerPlan {
+    pub guid: String,
+    pub name: String,
+    pub pow_data: String,
+}
+
+// 完整的配置包，单个 JSON 文件
+#[derive(Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub format_version: u32,
+    pub app_version: String,
+    pub exported_at: u64,
+    pub settings: Settings,
+    #[serde(deserialize_with = "deserialize_trigger_actions_compat")]
+    pub trigger_actions: Vec<TriggerAction>,
+    pub liked_power_settings: PowerSettingsPreferences,
+    pub power_plans: Vec<BundledPowerPlan>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleConflictKind {
+    // 本机已存在相同 GUID 的电源计划
+    PlanExists,
+    // 本机已存在相同 ID 但内容不同的触发动作
+    TriggerActionExists,
+    // 设置项与本机当前值不同
+    SettingDiffers,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct BundleConflict {
+    pub kind: BundleConflictKind,
+    pub id: String,
+    pub message: String,
+}
+
+// 导入前的检查报告
+#[derive(Serialize, Clone, Debug)]
+pub struct BundleImportReport {
+    pub valid: bool,
+    pub errors: Vec<String>,
+    pub conflicts: Vec<BundleConflict>,
+    pub plan_count: usize,
+    pub trigger_action_count: usize,
+    pub liked_setting_count: usize,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BundleImportOptions {
+    // 是否应用配置包中的设置
+    pub apply_settings: bool,
+    // 是否覆盖本机已存在的同 ID 触发动作
+    pub overwrite_trigger_actions: bool,
+    // 本机已存在同 GUID 的计划时直接复用，否则重新导入一份副本
+    pub reuse_existing_plans: bool,
+}
+
+// 导入完成后的结果
+#[derive(Serialize, Clone, Debug)]
+pub struct BundleImportResult {
+    pub plan_guid_map: HashMap<String, String>,
+    pub imported_plans: usize,
+    pub imported_trigger_actions: usize,
+    pub skipped_trigger_actions: usize,
+    pub applied_settings: Vec<String>,
+}
+
+fn settings_to_map(settings: &Settings) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    match serde_json::to_value(settings).map_err(|e| format!("序列化设置失败: {}", e))? {
+        serde_json::Value::Object(map) => Ok(map),
+        _ => Err("设置格式无效".to_string()),
+    }
+}
+
+// 计算与本机设置不同的设置项
+fn diff_settings(current: &Settings, incoming: &Settings) -> Result<Vec<(String, serde_json::Value)>, String> {
+    let current = settings_to_map(current)?;
+    let incoming = settings_to_map(incoming)?;
+
+    Ok(incoming
+        .into_iter()
+        .filter(|(key, _)| !LOCAL_ONLY_SETTINGS.contains(&key.as_str()))
+        .filter(|(key, value)| current.get(key) != Some(value))
+        .collect())
+}
+
+fn same_trigger_action(a: &TriggerAction, b: &TriggerAction) -> bool {
+    match (serde_json::to_value(a), serde_json::to_value(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn temp_pow_path() -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("amd-freq-guard-{}.pow", uuid::Uuid::new_v4()))
+}
+
+// 通过 powercfg 导出计划并读取为 base64
+fn export_plan_data(guid: &str) -> Result<String, String> {
+    let path = temp_pow_path();
+    let path_str = path.to_string_lossy().to_string();
+    export_power_plan(guid, &path_str)?;
+    let data = fs::read(&path).map_err(|e| format!("读取导出的电源计划失败: {}", e));
+    let _ = fs::remove_file(&path);
+    Ok(BASE64.encode(data?))
+}
+
+// 将 base64 内容写入临时文件并通过 powercfg 导入，返回新计划的 GUID
+fn import_plan_data(plan: &BundledPowerPlan) -> Result<String, String> {
+    let data = BASE64
+        .decode(&plan.pow_data)
+        .map_err(|e| format!("电源计划 {} 数据无效: {}", plan.name, e))?;
+    let path = temp_pow_path();
+    fs::write(&path, data).map_err(|e| format!("写入临时电源计划文件失败: {}", e))?;
+    let result = import_power_plan(&path.to_string_lossy());
+    let _ = fs::remove_file(&path);
+    result
+}
+
+pub async fn build_config_bundle(app: &AppHandle) -> Result<ConfigBundle, String> {
+    let settings = settings_store::get_settings()?;
+    let trigger_actions = load_trigger_actions(app.clone()).await?;
+    let liked_power_settings = PowerSettingsPreferences::load();
+
+    // 只打包触发动作引用到的电源计划
+    let referenced: HashSet<String> = trigger_actions
+        .iter()
+        .flat_map(|a| a.referenced_plan_guids())
+        .filter(|guid| !guid.is_empty())
+        .map(|guid| guid.to_uppercase())
+        .collect();
+
+    let mut power_plans = Vec::new();
+    for plan in get_power_plans()? {
+        if !referenced.contains(&plan.guid.to_uppercase()) {
+            continue;
+        }
+        info!("打包电源计划: {} ({})", plan.name, plan.guid);
+        power_plans.push(BundledPowerPlan {
+            pow_data: export_plan_data(&plan.guid)?,
+            guid: plan.guid,
+            name: plan.name,
+        });
+    }
+
+    Ok(ConfigBundle {
+        format_version: BUNDLE_FORMAT_VERSION,
+        app_version: app.package_info().version.to_string(),
+        exported_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        settings,
+        trigger_actions,
+        liked_power_settings,
+        power_plans,
+    })
+}
+
+fn read_config_bundle(file_path: &str) -> Result<ConfigBundle, String> {
+    let content = fs::read_to_string(file_path).map_err(|e| format!("读取配置包失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析配置包失败: {}", e))
+}
+
+// 校验配置包并找出与本机配置的冲突
+pub async fn inspect_config_bundle(app: &AppHandle, bundle: &ConfigBundle) -> Result<BundleImportReport, String> {
+    let local_plans: HashSet<String> = get_power_plans()?
+        .into_iter()
+        .map(|p| p.guid.to_uppercase())
+        .collect();
+    let local_actions = load_trigger_actions(app.clone()).await?;
+    let current_settings = settings_store::get_settings()?;
+    check_bundle(bundle, &local_plans, &local_actions, &current_settings)
+}
+
+// 与本机的计划（大写 GUID）、触发动作和设置比较，生成检查报告
+fn check_bundle(
+    bundle: &ConfigBundle,
+    local_plans: &HashSet<String>,
+    local_actions: &[TriggerAction],
+    current_settings: &Settings,
+) -> Result<BundleImportReport, String> {
+    let mut errors = Vec::new();
+    let mut conflicts = Vec::new();
+
+    if bundle.format_version > BUNDLE_FORMAT_VERSION {
+        errors.push(format!(
+            "不支持的配置包版本: {}（当前支持 {}）",
+            bundle.format_version, BUNDLE_FORMAT_VERSION
+        ));
+    }
+
+    let bundled_plans: HashSet<String> = bundle
+        .power_plans
+        .iter()
+        .map(|p| p.guid.to_uppercase())
+        .collect();
+
+    for plan in &bundle.power_plans {
+        if BASE64.decode(&plan.pow_data).is_err() {
+            errors.push(format!("电源计划 {} 数据无效", plan.name));
+        }
+        if local_plans.contains(&plan.guid.to_uppercase()) {
+            conflicts.push(BundleConflict {
+                kind: BundleConflictKind::PlanExists,
+                id: plan.guid.clone(),
+                message: format!("本机已存在电源计划 {} ({})", plan.name, plan.guid),
+            });
+        }
+    }
+
+    // 触发动作引用的计划必须在配置包中或本机已存在
+    let mut seen_ids = HashSet::new();
+    for action in &bundle.trigger_actions {
+        if !seen_ids.insert(action.id.clone()) {
+            errors.push(format!("触发动作 ID 重复: {}", action.id));
+        }
+        for guid in action.referenced_plan_guids() {
+            let guid_upper = guid.to_uppercase();
+            if !bundled_plans.contains(&guid_upper) && !local_plans.contains(&guid_upper) {
+                errors.push(format!("触发动作 {} 引用的电源计划不存在: {}", action.name, guid));
+            }
+        }
+    }
+
+    for action in &bundle.trigger_actions {
+        if let Some(local) = local_actions.iter().find(|a| a.id == action.id) {
+            if !same_trigger_action(local, action) {
+                conflicts.push(BundleConflict {
+                    kind: BundleConflictKind::TriggerActionExists,
+                    id: action.id.clone(),
+                    message: format!("本机已存在不同内容的触发动作: {}", local.name),
+                });
+            }
+        }
+    }
+
+    for (key, value) in diff_settings(current_settings, &bundle.settings)? {
+        conflicts.push(BundleConflict {
+            kind: BundleConflictKind::SettingDiffers,
+            id: key.clone(),
+            message: format!("设置 {} 将变更为 {}", key, value),
+        });
+    }
+
+    Ok(BundleImportReport {
+        valid: errors.is_empty(),
+        errors,
+        conflicts,
+        plan_count: bundle.power_plans.len(),
+        trigger_action_count: bundle.trigger_actions.len(),
+        liked_setting_count: bundle.liked_power_settings.liked_settings.len(),
+    })
+}
+
+pub async fn apply_config_bundle(
+    app: &AppHandle,
+    bundle: ConfigBundle,
+    options: &BundleImportOptions,
+) -> Result<BundleImportResult, String> {
+    let report = inspect_config_bundle(app, &bundle).await?;
+    if !report.valid {
+        return Err(format!("配置包校验失败: {}", report.errors.join("; ")));
+    }
+
+    // 1. 导入电源计划，记录 GUID 变化
+    let local_plans: HashSet<String> = get_power_plans()?
+        .into_iter()
+        .map(|p| p.guid.to_uppercase())
+        .collect();
+    let mut plan_guid_map = HashMap::new();
+    for plan in &bundle.power_plans {
+        let guid_upper = plan.guid.to_uppercase();
+        if options.reuse_existing_plans && local_plans.contains(&guid_upper) {
+            info!("复用本机已存在的电源计划: {} ({})", plan.name, plan.guid);
+            continue;
+        }
+        match import_plan_data(plan) {
+            Ok(new_guid) => {
+                info!("已导入电源计划 {}: {} -> {}", plan.name, plan.guid, new_guid);
+                plan_guid_map.insert(guid_upper, new_guid);
+            }
+            Err(e) => {
+                rollback_imported_plans(&plan_guid_map);
+                return Err(format!("导入电源计划 {} 失败: {}", plan.name, e));
+            }
+        }
+    }
+    let imported_plans = plan_guid_map.len();
+
+    // 2. 合并触发动作和收藏的电源设置，失败时删除刚导入的计划，不留下没有被引用的计划
+    let (imported_trigger_actions, skipped_trigger_actions) =
+        match merge_bundle_data(app, bundle.trigger_actions, bundle.liked_power_settings, &plan_guid_map, options).await {
+            Ok(counts) => counts,
+            Err(e) => {
+                rollback_imported_plans(&plan_guid_map);
+                return Err(e);
+            }
+        };
+
+    // 3. 逐项应用设置，走正常的校验和钩子流程
+    let mut applied_settings = Vec::new();
+    if options.apply_settings {
+        let current_settings = settings_store::get_settings()?;
+        for (key, value) in diff_settings(&current_settings, &bundle.settings)? {
+            if dry_run::skip(&format!("应用设置 {}", key)) {
+                applied_settings.push(key);
+                continue;
+            }
+            match settings_store::update_setting(key.clone(), value).await {
+                Ok(()) => applied_settings.push(key),
+                Err(e) => warn!("应用设置 {} 失败: {}", key, e),
+            }
+        }
+    }
+
+    Ok(BundleImportResult {
+        plan_guid_map,
+        imported_plans,
+        imported_trigger_actions,
+        skipped_trigger_actions,
+        applied_settings,
+    })
+}
+
+// 删除导入到一半的电源计划（值为新 GUID）
+fn rollback_imported_plans(plan_guid_map: &HashMap<String, String>) {
+    for guid in plan_guid_map.values() {
+        match delete_power_plan(guid) {
+            Ok(()) => info!("已删除导入的电源计划 {}", guid),
+            Err(e) => warn!("删除导入的电源计划 {} 失败: {}", guid, e),
+        }
+    }
+}
+
+// 把配置包中的触发动作合并进本机，覆盖或跳过同 ID 的动作，返回 (导入数, 跳过数)
+fn merge_trigger_actions(
+    actions: &mut Vec<TriggerAction>,
+    incoming: Vec<TriggerAction>,
+    plan_guid_map: &HashMap<String, String>,
+    overwrite: bool,
+) -> (usize, usize) {
+    let (mut imported, mut skipped) = (0, 0);
+    for mut action in incoming {
+        action.remap_plan_guids(plan_guid_map);
+        match actions.iter().position(|a| a.id == action.id) {
+            Some(index) if overwrite => {
+                actions[index] = action;
+                imported += 1;
+            }
+            Some(_) => skipped += 1,
+            None => {
+                actions.push(action);
+                imported += 1;
+            }
+        }
+    }
+    (imported, skipped)
+}
+
+// 保存合并后的触发动作和收藏的电源设置；收藏保存失败时恢复原来的触发动作
+async fn merge_bundle_data(
+    app: &AppHandle,
+    trigger_actions: Vec<TriggerAction>,
+    liked_power_settings: PowerSettingsPreferences,
+    plan_guid_map: &HashMap<String, String>,
+    options: &BundleImportOptions,
+) -> Result<(usize, usize), String> {
+    let previous = load_trigger_actions(app.clone()).await?;
+    let mut actions = previous.clone();
+    let counts = merge_trigger_actions(&mut actions, trigger_actions, plan_guid_map, options.overwrite_trigger_actions);
+
+    // 试运行时导入的计划只是占位 GUID，不能写进触发动作和收藏，只统计会导入的内容
+    if dry_run::skip("保存导入的触发动作和收藏的电源设置") {
+        return Ok(counts);
+    }
+    save_trigger_actions(app, &actions)?;
+
+    let mut preferences = PowerSettingsPreferences::load();
+    preferences.liked_settings.extend(liked_power_settings.liked_settings);
+    if let Err(e) = preferences.save() {
+        if let Err(restore_error) = save_trigger_actions(app, &previous) {
+            error!("恢复原来的触发动作失败: {}", restore_error);
+        }
+        return Err(e);
+    }
+    Ok(counts)
+}
+
+#[tauri::command]
+pub async fn export_config_bundle(app: AppHandle, file_path: String) -> Result<(), String> {
+    let bundle = build_config_bundle(&app).await?;
+    let json = serde_json::to_string_pretty(&bundle).map_err(|e| format!("序列化配置包失败: {}", e))?;
+    fs::write(&file_path, json).map_err(|e| format!("保存配置包失败: {}", e))?;
+    info!("配置包已导出: {}", file_path);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn inspect_config_bundle_command(app: AppHandle, file_path: String) -> Result<BundleImportReport, String> {
+    let bundle = read_config_bundle(&file_path)?;
+    inspect_config_bundle(&app, &bundle).await
+}
+
+#[tauri::command]
+pub async fn import_config_bundle(
+    app: AppHandle,
+    file_path: String,
+    options: BundleImportOptions,
+) -> Result<BundleImportResult, String> {
+    let bundle = read_config_bundle(&file_path)?;
+    apply_config_bundle(&app, bundle, &options).await.map_err(|e| {
+        error!("导入配置包失败: {}", e);
+        e
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trigger_action::{SettingSwitchValue, TriggerActionWorker};
+
+    const PLAN_A: &str = "381B4222-F694-41F0-9685-FF5BB260DF2E";
+    const PLAN_B: &str = "8BD00ADD-ABF1-47CF-98B4-0E38E7999415";
+    const PLAN_NEW: &str = "CF285094-3F36-42E5-B53A-D0B098D10D8B";
+
+    fn simple_action(id: &str, temp_plan_guid: &str, target_plan_guid: &str) -> TriggerAction {
+        TriggerAction {
+            id: id.to_string(),
+            name: id.to_string(),
+            worker: TriggerActionWorker::Simple {
+                temp_plan_guid: temp_plan_guid.to_string(),
+                pause_seconds: 1,
+                target_plan_guid: target_plan_guid.to_string(),
+            },
+            ..Default::default()
+        }
+    }
+
+    fn bundle(power_plans: Vec<BundledPowerPlan>, trigger_actions: Vec<TriggerAction>, settings: Settings) -> ConfigBundle {
+        ConfigBundle {
+            format_version: BUNDLE_FORMAT_VERSION,
+            app_version: "test".to_string(),
+            exported_at: 0,
+            settings,
+            trigger_actions,
+            liked_power_settings: PowerSettingsPreferences::default(),
+            power_plans,
+        }
+    }
+
+    fn bundled_plan(guid: &str) -> BundledPowerPlan {
+        BundledPowerPlan {
+            guid: guid.to_string(),
+            name: guid.to_string(),
+            pow_data: BASE64.encode(b"pow"),
+        }
+    }
+
+    #[test]
+    fn test_diff_settings_skips_local_only_settings() {
+        let current = Settings::default();
+        let mut incoming = Settings::default();
+        assert!(diff_settings(&current, &incoming).unwrap().is_empty());
+
+        incoming.refresh_interval = current.refresh_interval + 1000;
+        incoming.accepted_terms_of_service = current.accepted_terms_of_service + 1;
+        let diff = diff_settings(&current, &incoming).unwrap();
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].0, "refresh_interval");
+        assert_eq!(diff[0].1, serde_json::json!(incoming.refresh_interval));
+    }
+
+    #[test]
+    fn test_same_trigger_action_compares_content() {
+        let a = simple_action("a1", PLAN_A, PLAN_B);
+        assert!(same_trigger_action(&a, &a.clone()));
+
+        let mut renamed = a.clone();
+        renamed.name = "其他名称".to_string();
+        assert!(!same_trigger_action(&a, &renamed));
+        assert!(!same_trigger_action(&a, &simple_action("a1", PLAN_B, PLAN_A)));
+    }
+
+    #[test]
+    fn test_remap_plan_guids_for_each_worker() {
+        // 映射表的键为大写 GUID，动作里的小写 GUID 也要替换
+        let guid_map = HashMap::from([(PLAN_A.to_string(), PLAN_NEW.to_string())]);
+
+        let mut simple = simple_action("a1", &PLAN_A.to_lowercase(), PLAN_B);
+        simple.remap_plan_guids(&guid_map);
+        assert_eq!(simple.referenced_plan_guids(), vec![PLAN_NEW, PLAN_B]);
+
+        let mut setting_switch = TriggerAction {
+            worker: TriggerActionWorker::SettingSwitch {
+                plan_guid: PLAN_A.to_string(),
+                values: vec![SettingSwitchValue {
+                    subgroup_guid: String::new(),
+                    setting_guid: String::new(),
+                    ac_value: 0,
+                    dc_value: 0,
+                }],
+                reapply_scheme: false,
+                restore_after_seconds: None,
+            },
+            ..Default::default()
+        };
+        setting_switch.remap_plan_guids(&guid_map);
+        assert_eq!(setting_switch.referenced_plan_guids(), vec![PLAN_NEW]);
+
+        let mut workflow = TriggerAction::switch_plan("w1".to_string(), "切换".to_string(), PLAN_A);
+        workflow.remap_plan_guids(&guid_map);
+        assert_eq!(workflow.referenced_plan_guids(), vec![PLAN_NEW]);
+
+        let mut unmapped = TriggerAction::switch_plan("w2".to_string(), "切换".to_string(), PLAN_B);
+        unmapped.remap_plan_guids(&guid_map);
+        assert_eq!(unmapped.referenced_plan_guids(), vec![PLAN_B]);
+    }
+
+    #[test]
+    fn test_merge_trigger_actions_overwrites_or_skips() {
+        let guid_map = HashMap::from([(PLAN_A.to_string(), PLAN_NEW.to_string())]);
+        let incoming = vec![simple_action("a1", PLAN_A, PLAN_B), simple_action("a2", PLAN_A, PLAN_B)];
+
+        let mut actions = vec![simple_action("a1", PLAN_B, PLAN_B)];
+        assert_eq!(merge_trigger_actions(&mut actions, incoming.clone(), &guid_map, false), (1, 1));
+        assert_eq!(actions[0].referenced_plan_guids(), vec![PLAN_B, PLAN_B]);
+        assert_eq!(actions[1].referenced_plan_guids(), vec![PLAN_NEW, PLAN_B]);
+
+        let mut actions = vec![simple_action("a1", PLAN_B, PLAN_B)];
+        assert_eq!(merge_trigger_actions(&mut actions, incoming, &guid_map, true), (2, 0));
+        assert_eq!(actions[0].referenced_plan_guids(), vec![PLAN_NEW, PLAN_B]);
+    }
+
+    #[test]
+    fn test_check_bundle_reports_conflicts() {
+        let local_plans = HashSet::from([PLAN_A.to_string()]);
+        let local_actions = vec![simple_action("a1", PLAN_A, PLAN_A), simple_action("a2", PLAN_B, PLAN_B)];
+        let mut settings = Settings::default();
+        settings.frequency_threshold += 1.0;
+
+        let bundle = bundle(
+            vec![bundled_plan(&PLAN_A.to_lowercase()), bundled_plan(PLAN_B)],
+            vec![
+                simple_action("a1", PLAN_A, PLAN_B),
+                // 与本机完全相同的动作不算冲突
+                simple_action("a2", PLAN_B, PLAN_B),
+            ],
+            settings,
+        );
+        let report = check_bundle(&bundle, &local_plans, &local_actions, &Settings::default()).unwrap();
+        assert!(report.valid, "{:?}", report.errors);
+        assert_eq!(report.plan_count, 2);
+        assert_eq!(report.trigger_action_count, 2);
+
+        let conflicts: Vec<(&str, &str)> = report
+            .conflicts
+            .iter()
+            .map(|c| {
+                let kind = match c.kind {
+                    BundleConflictKind::PlanExists => "plan",
+                    BundleConflictKind::TriggerActionExists => "action",
+                    BundleConflictKind::SettingDiffers => "setting",
+                };
+                (kind, c.id.as_str())
+            })
+            .collect();
+        let plan_a = PLAN_A.to_lowercase();
+        assert_eq!(
+            conflicts,
+            vec![("plan", plan_a.as_str()), ("action", "a1"), ("setting", "frequency_threshold")]
+        );
+    }
+
+    #[test]
+    fn test_check_bundle_reports_errors() {
+        let mut broken_plan = bundled_plan(PLAN_A);
+        broken_plan.pow_data = "不是 base64".to_string();
+        let mut bundle = bundle(
+            vec![broken_plan],
+            vec![simple_action("a1", PLAN_A, PLAN_A), simple_action("a1", PLAN_A, PLAN_NEW)],
+            Settings::default(),
+        );
+        bundle.format_version = BUNDLE_FORMAT_VERSION + 1;
+
+        let report = check_bundle(&bundle, &HashSet::new(), &[], &Settings::default()).unwrap();
+        assert!(!report.valid);
+        assert_eq!(report.errors.len(), 4, "{:?}", report.errors);
+        assert!(report.errors[0].starts_with("不支持的配置包版本"));
+        assert!(report.errors[1].contains("数据无效"));
+        assert_eq!(report.errors[2], "触发动作 ID 重复: a1");
+        assert!(report.errors[3].ends_with(PLAN_NEW));
+    }
+}
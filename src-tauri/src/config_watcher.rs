@@ -0,0 +1,277 @@
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::{AppHandle, Emitter};
+use tokio::time::{interval as tokio_interval, Duration};
+
+use crate::notification::send_notification;
+use crate::settings_store;
+use crate::trigger_action::{self, is_valid_trigger_action};
+
+// 轮询配置文件修改时间的间隔
+const WATCH_INTERVAL_MS: u64 = 1000;
+
+// 本程序最近一次写入各配置文件的内容，还原被拒绝的外部修改时以它为准
+static APP_WRITES: Lazy<Mutex<HashMap<PathBuf, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 本程序保存配置文件后调用，避免之后拒绝外部修改时把文件还原成保存前的内容
+pub fn record_app_write(path: &Path, content: &str) {
+    if let Ok(mut writes) = APP_WRITES.lock() {
+        writes.insert(path.to_path_buf(), content.to_string());
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum WatchedKind {
+    Settings,
+    TriggerActions,
+}
+
+// 被监视的单个配置文件
+struct WatchedFile {
+    kind: WatchedKind,
+    path: PathBuf,
+    last_modified: Option<SystemTime>,
+    // 修改时间变化后先记下，下一轮仍未变化才处理，避免读到写了一半的文件
+    pending_modified: Option<SystemTime>,
+    // 最近一次通过校验的文件内容，拒绝外部修改时用于还原
+    last_good: Option<String>,
+}
+
+impl WatchedFile {
+    fn new(kind: WatchedKind, path: PathBuf) -> Self {
+        Self {
+            kind,
+            last_modified: modified_time(&path),
+            pending_modified: None,
+            last_good: fs::read_to_string(&path).ok(),
+            path,
+        }
+    }
+
+    // 轮询一次修改时间，变化后保持一轮不变才返回 true
+    fn poll(&mut self) -> bool {
+        let modified = modified_time(&self.path);
+        if modified == self.last_modified {
+            self.pending_modified = None;
+            return false;
+        }
+        if self.pending_modified != modified {
+            self.pending_modified = modified;
+            return false;
+        }
+
+        self.last_modified = modified;
+        self.pending_modified = None;
+        true
+    }
+
+    // 本程序保存过该文件时，以保存的内容作为最近一次有效的内容
+    fn refresh_last_good(&mut self) {
+        if let Some(content) = APP_WRITES.lock().ok().and_then(|mut writes| writes.remove(&self.path)) {
+            self.last_good = Some(content);
+        }
+    }
+
+    fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+pub fn start_config_watcher(app: AppHandle) {
+    let settings_path = match settings_store::settings_file_path() {
+        Ok(path) => path,
+        Err(e) => {
+            error!("启动配置文件监视失败: {}", e);
+            return;
+        }
+    };
+    let actions_path = trigger_action::get_actions_path(&app);
+
+    tauri::async_runtime::spawn(async move {
+        let mut files = [
+            WatchedFile::new(WatchedKind::Settings, settings_path),
+            WatchedFile::new(WatchedKind::TriggerActions, actions_path),
+        ];
+        let mut interval_timer = tokio_interval(Duration::from_millis(WATCH_INTERVAL_MS));
+        interval_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        info!("配置文件监视已启动");
+
+        loop {
+            interval_timer.tick().await;
+            for file in files.iter_mut() {
+                if file.poll() {
+                    check_file(&app, file).await;
+                }
+            }
+        }
+    });
+}
+
+async fn check_file(app: &AppHandle, file: &mut WatchedFile) {
+    let content = match fs::read_to_string(&file.path) {
+        Ok(content) => content,
+        Err(e) => {
+            warn!("读取配置文件 {} 失败: {}", file.file_name(), e);
+            return;
+        }
+    };
+
+    // 内容与上次一致（例如本程序自己保存的），无需处理
+    file.refresh_last_good();
+    if file.last_good.as_deref() == Some(content.as_str()) {
+        return;
+    }
+
+    let result = match file.kind {
+        WatchedKind::Settings => reload_settings(&content),
+        WatchedKind::TriggerActions => reload_trigger_actions(app, &content).await,
+    };
+
+    match result {
+        Ok(()) => {
+            file.last_good = Some(content);
+        }
+        Err(e) => {
+            reject_change(app, file, &content, &e);
+            // 还原后修改时间会再变化，记下以免重复处理
+            file.last_modified = modified_time(&file.path);
+        }
+    }
+}
+
+fn reload_settings(content: &str) -> Result<(), String> {
+    let changed = settings_store::reload_settings_from_content(content)?;
+    if !changed.is_empty() {
+        info!("已重新加载设置文件，变化的设置项: {:?}", changed);
+    }
+    Ok(())
+}
+
+async fn reload_trigger_actions(app: &AppHandle, content: &str) -> Result<(), String> {
+    let (actions, _) = trigger_action::parse_trigger_actions(content)?;
+
+    // 与启用动作时相同的校验
+    for action in actions.iter().filter(|a| a.enabled) {
+        is_valid_trigger_action(action)
+            .await
+            .map_err(|e| format!("触发动作 {} 无效: {}", action.name, e))?;
+    }
+
+    info!("已重新加载触发动作文件，共 {} 个动作", actions.len());
+    let _ = app.emit("trigger-actions-changed", &actions);
+    Ok(())
+}
+
+// 拒绝外部修改：保留一份被拒绝的内容，并还原为最近一次有效的文件
+fn reject_change(app: &AppHandle, file: &WatchedFile, content: &str, reason: &str) {
+    error!("配置文件 {} 的外部修改无效，已拒绝: {}", file.file_name(), reason);
+    restore_file(file, content);
+
+    let _ = send_notification(
+        "配置文件修改无效",
+        &format!("{} 的修改未生效: {}", file.file_name(), reason),
+    );
+    let _ = app.emit(
+        "config-reload-rejected",
+        json!({
+            "file": file.file_name(),
+            "error": reason,
+        }),
+    );
+}
+
+// 把被拒绝的内容另存为 .rejected 文件，再写回最近一次有效的内容
+fn restore_file(file: &WatchedFile, content: &str) {
+    let mut rejected_path = file.path.clone().into_os_string();
+    rejected_path.push(".rejected");
+    if let Err(e) = fs::write(&rejected_path, content) {
+        error!("保存被拒绝的配置文件失败: {}", e);
+    }
+
+    if let Some(last_good) = &file.last_good {
+        if let Err(e) = fs::write(&file.path, last_good) {
+            error!("还原配置文件失败: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration as StdDuration;
+
+    fn temp_file(content: &str) -> PathBuf {
+        let path = crate::test_support::temp_dir("config_watcher").join("settings.json");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    // 修改内容并确保修改时间与之前不同
+    fn touch(path: &Path, content: &str) {
+        let before = modified_time(path);
+        fs::write(path, content).unwrap();
+        let mut modified = SystemTime::now();
+        if Some(modified) == before {
+            modified += StdDuration::from_secs(1);
+        }
+        fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn test_poll_waits_for_stable_modified_time() {
+        let path = temp_file("{}");
+        let mut file = WatchedFile::new(WatchedKind::Settings, path.clone());
+        assert!(!file.poll());
+
+        // 第一次看到变化只记下，下一轮仍未变化才处理
+        touch(&path, r#"{"a":1}"#);
+        assert!(!file.poll());
+        assert!(file.pending_modified.is_some());
+        assert!(file.poll());
+        assert!(!file.poll());
+
+        // 两轮之间又被修改，重新等待
+        touch(&path, r#"{"a":2}"#);
+        assert!(!file.poll());
+        touch(&path, r#"{"a":3}"#);
+        assert!(!file.poll());
+        assert!(file.poll());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_restore_uses_latest_app_write() {
+        let path = temp_file(r#"{"v":1}"#);
+        let mut file = WatchedFile::new(WatchedKind::Settings, path.clone());
+
+        // 程序保存后外部修改被拒绝，应还原为程序保存的内容而不是启动时的内容
+        touch(&path, r#"{"v":2}"#);
+        record_app_write(&path, r#"{"v":2}"#);
+        touch(&path, "invalid");
+        file.refresh_last_good();
+        restore_file(&file, "invalid");
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), r#"{"v":2}"#);
+        let mut rejected_path = path.clone().into_os_string();
+        rejected_path.push(".rejected");
+        assert_eq!(fs::read_to_string(&rejected_path).unwrap(), "invalid");
+
+        // 没有新的写入时保留原来的内容
+        file.refresh_last_good();
+        assert_eq!(file.last_good.as_deref(), Some(r#"{"v":2}"#));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
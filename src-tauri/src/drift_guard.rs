@@ -0,0 +1,479 @@
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Emitter};
+use tokio::time::Duration;
+
+use crate::action_executor::ACTION_EXECUTOR;
+use crate::action_journal::{JournalDrift, JournalTrigger};
+use crate::dry_run;
+use crate::notification::send_notification;
+use crate::power_plan::check_if_scheme_is_valid;
+use crate::trigger_action::{SettingSwitchValue, TriggerAction, TriggerActionWorker};
+use crate::versioned_file::VersionedFile;
+use crate::PowerPlanUtils::GetPowerPlans::{enumerate_power_scheme_settings, get_power_setting_value, transfer_guidstr_to_guid};
+
+// drift_guard.json 的文件版本
+pub const DRIFT_GUARD_SCHEMA_VERSION: u32 = 1;
+
+static DRIFT_GUARD_FILE: VersionedFile = VersionedFile::new("drift_guard.json", "设置保护", DRIFT_GUARD_SCHEMA_VERSION);
+
+// 检查间隔的下限，避免频繁读写电源设置
+const MIN_INTERVAL_SECONDS: u64 = 5;
+
+fn default_interval_seconds() -> u64 {
+    60
+}
+
+// 被保护的设置位置
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GuardedSettingKey {
+    pub plan_guid: String,
+    pub subgroup_guid: String,
+    pub setting_guid: String,
+}
+
+impl GuardedSettingKey {
+    fn same_as(&self, other: &GuardedSettingKey) -> bool {
+        self.plan_guid.eq_ignore_ascii_case(&other.plan_guid)
+            && self.subgroup_guid.eq_ignore_ascii_case(&other.subgroup_guid)
+            && self.setting_guid.eq_ignore_ascii_case(&other.setting_guid)
+    }
+
+    // 读写电源设置的接口遇到无效 GUID 会直接 panic，使用前先检查
+    fn validate(&self) -> Result<(), String> {
+        for guid in [&self.plan_guid, &self.subgroup_guid, &self.setting_guid] {
+            transfer_guidstr_to_guid(guid)?;
+        }
+        Ok(())
+    }
+}
+
+// 快照中的一项设置及其应保持的值
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GuardedSetting {
+    #[serde(flatten)]
+    pub key: GuardedSettingKey,
+    // 拍快照时的设置名称，仅用于显示
+    #[serde(default)]
+    pub name: String,
+    pub ac_value: u32,
+    pub dc_value: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DriftGuardConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_interval_seconds")]
+    pub interval_seconds: u64,
+    #[serde(default)]
+    pub settings: Vec<GuardedSetting>,
+}
+
+impl Default for DriftGuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_seconds: default_interval_seconds(),
+            settings: Vec::new(),
+        }
+    }
+}
+
+impl DriftGuardConfig {
+    pub fn validate(&self, plan_exists: &dyn Fn(&str) -> bool) -> Result<(), String> {
+        if self.interval_seconds < MIN_INTERVAL_SECONDS {
+            return Err(format!("检查间隔不能小于 {} 秒", MIN_INTERVAL_SECONDS));
+        }
+        for (index, setting) in self.settings.iter().enumerate() {
+            let label = if setting.name.is_empty() { &setting.key.setting_guid } else { &setting.name };
+            setting.key.validate().map_err(|e| format!("设置 {} 无效: {}", label, e))?;
+            if !plan_exists(&setting.key.plan_guid) {
+                return Err(format!("设置 {} 所在的电源计划不存在: {}", label, setting.key.plan_guid));
+            }
+            if self.settings[..index].iter().any(|s| s.key.same_as(&setting.key)) {
+                return Err(format!("设置 {} 重复", label));
+            }
+        }
+        Ok(())
+    }
+
+    // 用新的快照值替换同一位置的旧记录，其他设置保持不变
+    pub fn merge_snapshot(&mut self, snapshot: Vec<GuardedSetting>) {
+        for setting in snapshot {
+            match self.settings.iter_mut().find(|s| s.key.same_as(&setting.key)) {
+                Some(existing) => *existing = setting,
+                None => self.settings.push(setting),
+            }
+        }
+    }
+}
+
+// 当前值与快照不一致的设置
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Drift {
+    #[serde(flatten)]
+    pub key: GuardedSettingKey,
+    #[serde(default)]
+    pub name: String,
+    pub expected_ac: u32,
+    pub expected_dc: u32,
+    pub actual_ac: u32,
+    pub actual_dc: u32,
+}
+
+impl Drift {
+    // 执行日志只保存设置的标识和前后的值
+    fn to_journal(&self) -> JournalDrift {
+        JournalDrift {
+            subgroup_guid: self.key.subgroup_guid.clone(),
+            setting_guid: self.key.setting_guid.clone(),
+            name: self.name.clone(),
+            expected_ac: self.expected_ac,
+            expected_dc: self.expected_dc,
+            actual_ac: self.actual_ac,
+            actual_dc: self.actual_dc,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let label = if self.name.is_empty() { &self.key.setting_guid } else { &self.name };
+        format!(
+            "{} AC {}→{} DC {}→{}",
+            label, self.actual_ac, self.expected_ac, self.actual_dc, self.expected_dc
+        )
+    }
+}
+
+// 读取设置当前的 (AC, DC) 值，测试时可替换
+pub type ValueReader = dyn Fn(&GuardedSettingKey) -> Result<(u32, u32), String>;
+
+// 逐项比较当前值与快照，读取失败的设置跳过，下次检查时再试
+pub fn find_drift(settings: &[GuardedSetting], read: &ValueReader) -> Vec<Drift> {
+    settings
+        .iter()
+        .filter_map(|setting| match read(&setting.key) {
+            Ok((ac, dc)) if ac != setting.ac_value || dc != setting.dc_value => Some(Drift {
+                key: setting.key.clone(),
+                name: setting.name.clone(),
+                expected_ac: setting.ac_value,
+                expected_dc: setting.dc_value,
+                actual_ac: ac,
+                actual_dc: dc,
+            }),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("读取设置 {} 的当前值失败: {}", setting.key.setting_guid, e);
+                None
+            }
+        })
+        .collect()
+}
+
+// 按电源计划分组，保持首次出现的顺序，每个计划的修正记为一条执行日志
+pub fn group_by_plan(drifts: &[Drift]) -> Vec<(String, Vec<Drift>)> {
+    let mut groups: Vec<(String, Vec<Drift>)> = Vec::new();
+    for drift in drifts {
+        match groups
+            .iter_mut()
+            .find(|(plan, _)| plan.eq_ignore_ascii_case(&drift.key.plan_guid))
+        {
+            Some((_, group)) => group.push(drift.clone()),
+            None => groups.push((drift.key.plan_guid.clone(), vec![drift.clone()])),
+        }
+    }
+    groups
+}
+
+pub fn parse_drift_guard(content: &str) -> Result<DriftGuardConfig, String> {
+    DRIFT_GUARD_FILE.parse(content)
+}
+
+fn read_config() -> Result<DriftGuardConfig, String> {
+    DRIFT_GUARD_FILE.read()
+}
+
+fn write_config(config: &DriftGuardConfig) -> Result<(), String> {
+    DRIFT_GUARD_FILE.write(json!({
+        "enabled": config.enabled,
+        "interval_seconds": config.interval_seconds,
+        "settings": config.settings,
+    }))
+}
+
+fn read_current_value(key: &GuardedSettingKey) -> Result<(u32, u32), String> {
+    key.validate()?;
+    get_power_setting_value(&key.plan_guid, &key.subgroup_guid, &key.setting_guid).map(|v| (v.ac_value, v.dc_value))
+}
+
+// 写回一个计划中漂移的设置；该计划正在使用时重新激活，使新值立即生效
+fn correction_action(plan_guid: &str, drifts: &[Drift]) -> TriggerAction {
+    TriggerAction {
+        id: format!("drift_guard:{}", plan_guid),
+        name: "电源设置漂移修正".to_string(),
+        enabled: true,
+        worker: TriggerActionWorker::SettingSwitch {
+            plan_guid: plan_guid.to_string(),
+            values: drifts
+                .iter()
+                .map(|drift| SettingSwitchValue {
+                    subgroup_guid: drift.key.subgroup_guid.clone(),
+                    setting_guid: drift.key.setting_guid.clone(),
+                    ac_value: drift.expected_ac,
+                    dc_value: drift.expected_dc,
+                })
+                .collect(),
+            reapply_scheme: true,
+            restore_after_seconds: None,
+        },
+        ..Default::default()
+    }
+}
+
+// 每个计划的修正作为一个动作交给执行队列，不会和正在执行的触发动作同时修改设置
+fn submit_corrections(drifts: &[Drift]) {
+    for (plan_guid, group) in group_by_plan(drifts) {
+        info!("电源计划 {} 中有 {} 项设置发生漂移，正在恢复", plan_guid, group.len());
+        let trigger = JournalTrigger::Drift {
+            plan_guid: plan_guid.clone(),
+            drifts: group.iter().map(Drift::to_journal).collect(),
+        };
+        if let Err(e) = ACTION_EXECUTOR.submit(correction_action(&plan_guid, &group), trigger) {
+            warn!("提交电源计划 {} 的设置修正失败: {}", plan_guid, e);
+        }
+    }
+}
+
+// 读取电源设置较慢，在阻塞线程中检查一次
+async fn check_drift(settings: Vec<GuardedSetting>) -> Result<Vec<Drift>, String> {
+    tokio::task::spawn_blocking(move || find_drift(&settings, &read_current_value))
+        .await
+        .map_err(|e| format!("检查设置漂移失败: {}", e))
+}
+
+fn report_drifts(app: &AppHandle, drifts: &[Drift]) {
+    if let Err(e) = app.emit("drift-corrected", drifts) {
+        warn!("发送设置漂移事件失败: {}", e);
+    }
+    let prefix = if dry_run::is_enabled() { "[试运行] " } else { "" };
+    let detail: Vec<String> = drifts.iter().map(|d| d.describe()).collect();
+    let _ = send_notification(
+        &format!("{}电源设置已恢复", prefix),
+        &format!("{} 项设置被修改，正在恢复为快照中的值: {}", drifts.len(), detail.join("，")),
+    );
+}
+
+// 启动设置保护，配置每次都重新读取，保存后无需通知
+pub fn start_drift_guard(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        info!("电源设置保护已启动");
+        // 同样的漂移只通知一次；试运行时不会真正写回，修正也只提交一次
+        let mut last_reported: Vec<Drift> = Vec::new();
+
+        loop {
+            let config = match read_config() {
+                Ok(config) => config,
+                Err(e) => {
+                    error!("加载设置保护配置失败: {}", e);
+                    DriftGuardConfig::default()
+                }
+            };
+            let interval = Duration::from_secs(config.interval_seconds.max(MIN_INTERVAL_SECONDS));
+
+            if config.enabled && !config.settings.is_empty() {
+                match check_drift(config.settings).await {
+                    Ok(drifts) => {
+                        let changed = drifts != last_reported;
+                        // 修正在执行队列中排在正在执行的触发动作之后，动作临时修改的设置会在它结束后再恢复
+                        if !drifts.is_empty() && (changed || !dry_run::is_enabled()) {
+                            submit_corrections(&drifts);
+                        }
+                        if !drifts.is_empty() && changed {
+                            report_drifts(&app, &drifts);
+                        }
+                        last_reported = drifts;
+                    }
+                    Err(e) => error!("{}", e),
+                }
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn load_drift_guard() -> Result<DriftGuardConfig, String> {
+    read_config()
+}
+
+#[tauri::command]
+pub async fn save_drift_guard(config: DriftGuardConfig) -> Result<(), String> {
+    config.validate(&check_if_scheme_is_valid)?;
+    write_config(&config)
+}
+
+// 读取所选设置的当前值作为快照，加入保护列表并保存
+#[tauri::command]
+pub async fn snapshot_drift_guard_settings(keys: Vec<GuardedSettingKey>) -> Result<DriftGuardConfig, String> {
+    tokio::task::spawn_blocking(move || {
+        let mut snapshot = Vec::new();
+        for key in keys {
+            key.validate()?;
+            if !check_if_scheme_is_valid(&key.plan_guid) {
+                return Err(format!("电源计划不存在: {}", key.plan_guid));
+            }
+            let (ac_value, dc_value) =
+                read_current_value(&key).map_err(|e| format!("读取设置 {} 失败: {}", key.setting_guid, e))?;
+            let name = enumerate_power_scheme_settings(&key.plan_guid, &key.subgroup_guid)
+                .ok()
+                .and_then(|settings| {
+                    settings
+                        .into_iter()
+                        .find(|s| format!("{:?}", s.uuid).eq_ignore_ascii_case(&key.setting_guid))
+                })
+                .map(|s| s.name)
+                .unwrap_or_default();
+            snapshot.push(GuardedSetting {
+                key,
+                name,
+                ac_value,
+                dc_value,
+            });
+        }
+
+        let mut config = read_config()?;
+        config.merge_snapshot(snapshot);
+        write_config(&config)?;
+        info!("设置保护快照已更新，共 {} 项设置", config.settings.len());
+        Ok(config)
+    })
+    .await
+    .map_err(|e| format!("保存设置快照失败: {}", e))?
+}
+
+// 立即检查一次，不受开关和检查间隔限制
+#[tauri::command]
+pub async fn check_drift_now(app: AppHandle) -> Result<Vec<Drift>, String> {
+    let config = read_config()?;
+    let drifts = check_drift(config.settings).await?;
+    if !drifts.is_empty() {
+        submit_corrections(&drifts);
+        report_drifts(&app, &drifts);
+    }
+    Ok(drifts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAN: &str = "381b4222-f694-41f0-9685-ff5bb260df2e";
+    const SUBGROUP: &str = "54533251-82be-4824-96c1-47b60b740d00";
+    const BOOST: &str = "be337238-0d82-4146-a960-4f3749d470c7";
+    const MAX_STATE: &str = "bc5038f7-23e0-4960-96da-33abaf5935ec";
+
+    fn guarded(plan: &str, setting: &str, ac: u32, dc: u32) -> GuardedSetting {
+        GuardedSetting {
+            key: GuardedSettingKey {
+                plan_guid: plan.to_string(),
+                subgroup_guid: SUBGROUP.to_string(),
+                setting_guid: setting.to_string(),
+            },
+            name: String::new(),
+            ac_value: ac,
+            dc_value: dc,
+        }
+    }
+
+    #[test]
+    fn test_find_drift_compares_ac_and_dc() {
+        let settings = vec![guarded(PLAN, BOOST, 2, 0), guarded(PLAN, MAX_STATE, 100, 80)];
+        let read = |key: &GuardedSettingKey| -> Result<(u32, u32), String> {
+            if key.setting_guid == BOOST {
+                Ok((2, 1))
+            } else {
+                Ok((100, 80))
+            }
+        };
+        let drifts = find_drift(&settings, &read);
+        assert_eq!(drifts.len(), 1);
+        assert_eq!(drifts[0].key.setting_guid, BOOST);
+        assert_eq!((drifts[0].actual_ac, drifts[0].actual_dc), (2, 1));
+        assert_eq!((drifts[0].expected_ac, drifts[0].expected_dc), (2, 0));
+
+        // 读取失败的设置不算漂移
+        let failing = |_: &GuardedSettingKey| -> Result<(u32, u32), String> { Err("不存在".to_string()) };
+        assert!(find_drift(&settings, &failing).is_empty());
+    }
+
+    #[test]
+    fn test_group_by_plan_and_merge_snapshot() {
+        let other_plan = "8c5e7fda-e8bf-4a96-9a85-a6e23a8c635c";
+        let settings = vec![
+            guarded(PLAN, BOOST, 2, 0),
+            guarded(other_plan, BOOST, 2, 0),
+            guarded(&PLAN.to_uppercase(), MAX_STATE, 100, 80),
+        ];
+        let drifts = find_drift(&settings, &|_| Ok((0, 0)));
+        let groups = group_by_plan(&drifts);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].1.len(), 2);
+        assert_eq!(groups[1].0, other_plan);
+
+        let mut config = DriftGuardConfig {
+            settings: vec![guarded(PLAN, BOOST, 2, 0)],
+            ..Default::default()
+        };
+        config.merge_snapshot(vec![guarded(&PLAN.to_uppercase(), BOOST, 3, 3), guarded(PLAN, MAX_STATE, 99, 50)]);
+        assert_eq!(config.settings.len(), 2);
+        assert_eq!((config.settings[0].ac_value, config.settings[0].dc_value), (3, 3));
+    }
+
+    #[test]
+    fn test_correction_action_writes_expected_values() {
+        let read = |_: &GuardedSettingKey| -> Result<(u32, u32), String> { Ok((0, 1)) };
+        let drifts = find_drift(&[guarded(PLAN, BOOST, 2, 0)], &read);
+        let action = correction_action(PLAN, &drifts);
+
+        assert_eq!(action.id, format!("drift_guard:{}", PLAN));
+        let TriggerActionWorker::SettingSwitch { plan_guid, values, reapply_scheme, restore_after_seconds } = action.worker
+        else {
+            panic!("修正动作应为设置切换");
+        };
+        assert_eq!(plan_guid, PLAN);
+        assert_eq!(
+            values.iter().map(|v| (v.setting_guid.as_str(), v.ac_value, v.dc_value)).collect::<Vec<_>>(),
+            vec![(BOOST, 2, 0)]
+        );
+        // 只在该计划正在使用时重新激活，修正后不恢复原值
+        assert!(reapply_scheme);
+        assert_eq!(restore_after_seconds, None);
+    }
+
+    #[test]
+    fn test_parse_and_validate_config() {
+        let content = format!(
+            r#"{{"schema_version":1,"enabled":true,"settings":[{{"plan_guid":"{}","subgroup_guid":"{}","setting_guid":"{}","ac_value":2,"dc_value":0}}]}}"#,
+            PLAN, SUBGROUP, BOOST
+        );
+        let config = parse_drift_guard(&content).unwrap();
+        assert!(config.enabled);
+        assert_eq!(config.interval_seconds, 60);
+        assert!(config.validate(&|_| true).is_ok());
+        assert!(config.validate(&|_| false).is_err());
+        assert!(parse_drift_guard(r#"{"schema_version":2}"#).is_err());
+
+        let mut duplicated = config.clone();
+        duplicated.settings.push(guarded(PLAN, BOOST, 1, 1));
+        assert!(duplicated.validate(&|_| true).is_err());
+
+        let invalid = DriftGuardConfig {
+            settings: vec![guarded("not-a-guid", BOOST, 1, 1)],
+            ..Default::default()
+        };
+        assert!(invalid.validate(&|_| true).is_err());
+        assert!(DriftGuardConfig { interval_seconds: 1, ..Default::default() }.validate(&|_| true).is_err());
+    }
+}
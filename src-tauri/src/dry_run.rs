@@ -0,0 +1,115 @@
+use log::info;
+use std::cell::RefCell;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use crate::settings_store;
+
+// 试运行范围：范围内所有修改电源计划的操作只记录、不执行
+#[derive(Clone, Default)]
+struct DryRunScope {
+    enabled: bool,
+    // 范围内被跳过的操作，执行日志按步骤取走
+    planned: Arc<Mutex<Vec<String>>>,
+}
+
+tokio::task_local! {
+    static TASK_SCOPE: DryRunScope;
+}
+
+thread_local! {
+    // spawn_blocking 的线程拿不到 task_local，由 spawn_blocking 包装函数带过去
+    static THREAD_SCOPE: RefCell<Option<DryRunScope>> = const { RefCell::new(None) };
+}
+
+fn current_scope() -> Option<DryRunScope> {
+    TASK_SCOPE
+        .try_with(|scope| scope.clone())
+        .ok()
+        .or_else(|| THREAD_SCOPE.with(|scope| scope.borrow().clone()))
+}
+
+fn scope_enabled() -> bool {
+    current_scope().is_some_and(|scope| scope.enabled)
+}
+
+// 全局试运行开关打开，或当前处于单个动作的试运行范围内
+pub fn is_enabled() -> bool {
+    settings_store::get_dry_run_enabled() || scope_enabled()
+}
+
+// 在试运行范围内执行 future，enabled 为 false 时仍会收集范围信息但不跳过操作
+pub async fn scope<F: Future>(enabled: bool, future: F) -> F::Output {
+    TASK_SCOPE
+        .scope(
+            DryRunScope {
+                enabled,
+                ..Default::default()
+            },
+            future,
+        )
+        .await
+}
+
+// 与 tokio::task::spawn_blocking 相同，但保留当前的试运行范围
+pub fn spawn_blocking<F, T>(f: F) -> tokio::task::JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let scope = current_scope();
+    tokio::task::spawn_blocking(move || {
+        THREAD_SCOPE.with(|current| *current.borrow_mut() = scope);
+        let result = f();
+        THREAD_SCOPE.with(|current| *current.borrow_mut() = None);
+        result
+    })
+}
+
+// 修改类操作执行前调用，试运行时记录操作并返回 true，调用方应直接返回
+pub fn skip(operation: &str) -> bool {
+    if !is_enabled() {
+        return false;
+    }
+    info!("[试运行] 未执行: {}", operation);
+    if let Some(scope) = current_scope() {
+        if let Ok(mut planned) = scope.planned.lock() {
+            planned.push(operation.to_string());
+        }
+    }
+    true
+}
+
+// 取走当前范围内记录的被跳过操作
+pub fn take_planned() -> Vec<String> {
+    current_scope()
+        .and_then(|scope| scope.planned.lock().ok().map(|mut planned| std::mem::take(&mut *planned)))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scope_records_skipped_operations() {
+        assert!(!scope_enabled());
+
+        scope(true, async {
+            assert!(skip("powercfg /setactive a"));
+            // spawn_blocking 中同样处于试运行范围
+            let skipped = spawn_blocking(|| skip("写入电源设置 b")).await.unwrap();
+            assert!(skipped);
+            assert_eq!(take_planned(), vec!["powercfg /setactive a", "写入电源设置 b"]);
+            assert!(take_planned().is_empty());
+        })
+        .await;
+
+        scope(false, async {
+            assert!(!scope_enabled());
+            let skipped = spawn_blocking(|| skip("powercfg /setactive a")).await.unwrap();
+            assert!(!skipped);
+        })
+        .await;
+    }
+}
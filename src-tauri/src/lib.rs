@@ -34,14 +34,45 @@ use windows_sys::Win32::UI::WindowsAndMessaging::{
 // 在文件顶部添加模块声明
 mod power_plan;
 use power_plan::{
-    check_if_scheme_is_valid, get_power_plans, get_power_plans_json_by_scheme_guid,
+    check_if_scheme_is_valid, get_power_plans, get_power_plans_json_by_scheme_guid, parse_powercfg_query_json,
     set_active_plan, PowerPlan,
 };
 
 mod trigger_action;
-pub use trigger_action::{delete_trigger_action, load_trigger_actions, save_trigger_action, set_trigger_action_enabled, get_trigger_action_by_id};
+mod trigger_condition;
+mod workflow;
+mod dry_run;
+mod versioned_file;
+#[cfg(test)]
+mod test_support;
+mod action_journal;
+use action_journal::{export_action_journal_csv, query_action_journal};
+mod action_executor;
+use action_executor::{cancel_trigger_action, get_action_executor_status};
+mod power_source;
+use power_source::{get_current_power_source, start_power_source_watcher};
+mod rule_engine;
+use rule_engine::{explain_rules, load_rules, save_rules};
+mod scheduler;
+mod temperature;
+mod process_rules;
+use process_rules::{get_process_rule_status, load_process_rules, save_process_rules, start_process_watcher};
+use scheduler::{get_schedule_next_runs, load_schedules, save_schedules, start_scheduler};
+pub use trigger_action::{delete_trigger_action, load_trigger_actions, save_trigger_action, set_trigger_action_enabled, get_trigger_action_by_id, simulate_trigger_action};
 
 mod monitor;
+mod replay;
+use replay::replay_frequency_trace;
+mod trace_recorder;
+use trace_recorder::{get_trace_recording_status, start_trace_recording, stop_trace_recording};
+mod drift_guard;
+mod plan_diff;
+use plan_diff::{diff_power_plan_with_snapshot, diff_power_plans};
+mod plan_snapshot;
+use plan_snapshot::{export_plan_snapshot, restore_plan_snapshot};
+mod plan_config;
+use plan_config::{apply_plan_config, preview_plan_config};
+use drift_guard::{check_drift_now, load_drift_guard, save_drift_guard, snapshot_drift_guard_settings, start_drift_guard};
 pub use monitor::MONITOR;
 
 mod settings;
@@ -75,6 +106,12 @@ use PowerPlanUtils::GetPowerPlans::write_value_set;
 mod power_settings_preferences_store;
 use power_settings_preferences_store::{get_liked_power_settings, toggle_power_setting_liked};
 
+mod config_watcher;
+use config_watcher::start_config_watcher;
+
+mod config_bundle;
+use config_bundle::{export_config_bundle, import_config_bundle, inspect_config_bundle_command};
+
 // 创建一个全局状态来存储System实例
 struct SystemState(Mutex<System>);
 
@@ -166,6 +203,11 @@ fn get_power_plans_json_by_scheme_guid_command(guid: &str) -> Result<String, Str
     get_power_plans_json_by_scheme_guid(guid)
 }
 
+#[tauri::command]
+fn parse_powercfg_query_command(output: &str) -> Result<String, String> {
+    parse_powercfg_query_json(output)
+}
+
 #[tauri::command]
 fn set_active_plan_command(guid: String) -> Result<(), String> {
     set_active_plan(&guid)
@@ -427,6 +469,12 @@ pub fn run() {
             move |app_handle, argv, cwd| {
                 info!("检测到新实例启动，参数: {:?}, 工作目录: {:?}", argv, cwd);
 
+                // 新实例带有频率记录参数时由当前实例处理，不激活窗口
+                if matches!(trace_recorder::parse_cli_args(&argv), Ok(Some(_))) {
+                    trace_recorder::handle_cli_args(app_handle, &argv);
+                    return;
+                }
+
                 if let Some(window) = app_handle.get_webview_window("main") {
                     // 确保窗口可见
                     let _ = window.unminimize();
@@ -454,6 +502,18 @@ pub fn run() {
             init_settings_store(app.handle().clone())?;
             // 初始化通知管理器
             init_notification_manager(app.handle().clone())?;
+            // 监视配置文件的外部修改
+            start_config_watcher(app.handle().clone());
+            // 监视交流电源/电池的切换
+            start_power_source_watcher(app.handle().clone());
+            // 按运行中的进程切换电源计划
+            start_process_watcher(app.handle().clone());
+            // 启动定时切换电源计划
+            start_scheduler(app.handle().clone());
+            // 恢复被其他程序改掉的电源设置
+            start_drift_guard(app.handle().clone());
+            // 处理启动参数中的频率记录请求
+            trace_recorder::handle_cli_args(app.handle(), &args);
             
             tauri::async_runtime::spawn(async move {
             // 检查服务条款版本
@@ -511,8 +571,10 @@ pub fn run() {
             });
 
             let show_i = MenuItem::with_id(app, "show", "显示", true, None::<&str>)?;
+            let record_trace_i = MenuItem::with_id(app, "record_trace", "记录频率（30 分钟）", true, None::<&str>)?;
+            let stop_trace_i = MenuItem::with_id(app, "stop_trace", "停止记录频率", true, None::<&str>)?;
             let quit_i = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&show_i, &quit_i])?;
+            let menu = Menu::with_items(app, &[&show_i, &record_trace_i, &stop_trace_i, &quit_i])?;
 
             let _tray = TrayIconBuilder::new()
                 .icon(app.default_window_icon().unwrap().clone())
@@ -547,6 +609,14 @@ pub fn run() {
                         }
                         let _ = win.set_focus();
                     }
+                    "record_trace" => {
+                        if let Err(e) = trace_recorder::start_recording(app, trace_recorder::TraceOptions::default()) {
+                            error!("开始记录频率失败: {}", e);
+                        }
+                    }
+                    "stop_trace" => {
+                        let _ = trace_recorder::stop_recording(app);
+                    }
                     "quit" => {
                         app.exit(0);
                     }
@@ -594,6 +664,7 @@ pub fn run() {
             check_update,
             get_constants,
             get_power_plans_json_by_scheme_guid_command,
+            parse_powercfg_query_command,
             toggle_power_setting_liked,
             get_liked_power_settings,
             write_value_set_command,
@@ -606,6 +677,38 @@ pub fn run() {
             get_setting,
             set_trigger_action_master_switch,
             monitor::get_monitor_state,
+            export_config_bundle,
+            inspect_config_bundle_command,
+            import_config_bundle,
+            query_action_journal,
+            export_action_journal_csv,
+            cancel_trigger_action,
+            get_action_executor_status,
+            load_rules,
+            save_rules,
+            explain_rules,
+            load_schedules,
+            save_schedules,
+            get_schedule_next_runs,
+            get_current_power_source,
+            load_process_rules,
+            save_process_rules,
+            get_process_rule_status,
+            simulate_trigger_action,
+            replay_frequency_trace,
+            start_trace_recording,
+            stop_trace_recording,
+            get_trace_recording_status,
+            load_drift_guard,
+            save_drift_guard,
+            snapshot_drift_guard_settings,
+            check_drift_now,
+            diff_power_plans,
+            diff_power_plan_with_snapshot,
+            export_plan_snapshot,
+            restore_plan_snapshot,
+            preview_plan_config,
+            apply_plan_config,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
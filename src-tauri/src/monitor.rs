@@ -1,7 +1,8 @@
+use crate::trigger_condition::{core_load, FrequencySample};
 use crate::notification::{send_notification, send_notification_with_handle};
-use crate::power_plan::set_active_plan;
+use crate::power_plan::{self, set_active_plan};
 use crate::settings::Settings;
-use crate::trigger_action::{load_trigger_actions, TriggerAction};
+use crate::trigger_action::TriggerAction;
 use calcmhz;
 use log::{error, info, warn};
 use serde::Serialize;
@@ -20,12 +21,23 @@ use crate::settings_store;
 use tokio::task::JoinHandle;
 use std::sync::atomic::{AtomicU64, Ordering};
 use crate::trigger_action;
+use crate::rule_engine;
+use crate::power_source::{self, PowerSource};
+use crate::temperature;
+use crate::process_rules;
+use crate::trace_recorder::{self, TraceRecord};
+use crate::action_journal::now_millis;
 #[derive(Clone, Serialize)]
 pub struct MonitorState {
     pub frequencies: Vec<u64>,
     pub is_refreshing: bool,
     pub indicator_status: String,
     pub last_update_count: u64,
+    pub power_source: Option<PowerSource>,
+    // CPU 温度（摄氏度），没有可用的传感器时为空
+    pub temperature: Option<f32>,
+    // 每个逻辑核心的占用率（%），与 SysInfo 模式下的 frequencies 一一对应
+    pub loads: Vec<f32>,
 }
 
 impl Default for MonitorState {
@@ -35,6 +47,9 @@ impl Default for MonitorState {
             is_refreshing: false,
             indicator_status: "normal".to_string(),
             last_update_count: 0,
+            power_source: None,
+            temperature: None,
+            loads: Vec::new(),
         }
     }
 }
@@ -49,6 +64,7 @@ pub struct Monitor {
     mode_auto_switched: Arc<Mutex<bool>>,
     monitor_task: Arc<Mutex<Option<JoinHandle<()>>>>,
     timer_version: Arc<AtomicU64>,
+    hook_handles: Arc<std::sync::Mutex<Vec<settings_store::SettingHookHandle>>>,
 }
 
 impl Monitor {
@@ -62,19 +78,21 @@ impl Monitor {
             mode_auto_switched: Arc::new(Mutex::new(false)),
             monitor_task: Arc::new(Mutex::new(None)),
             timer_version: Arc::new(AtomicU64::new(0)),
+            hook_handles: Arc::new(std::sync::Mutex::new(Vec::new())),
         }
     }
 
     pub fn set_window(&mut self, window: WebviewWindow) {
         self.window = Some(window.clone());
 
-        // 注册设置钩子
+        // 注册设置钩子，新句柄替换旧句柄时旧钩子自动注销，多次调用不会重复注册
+        let mut handles = Vec::new();
         let monitor = self.clone();
         
         // 监听频率检测开关
-        if let Ok(()) = settings_store::add_setting_hook("frequency_detection_enabled", move |_, value| {
-            info!("钩子-频率检测开关变化: {}", value);
-            let enabled = value.as_bool().unwrap_or(true);
+        match settings_store::add_setting_hook("frequency_detection_enabled", move |change| {
+            info!("钩子-频率检测开关变化: {} -> {}", change.old_value, change.new_value);
+            let enabled = change.new_value.as_bool().unwrap_or(true);
             let monitor = monitor.clone();
             
             // 直接启动，不等待
@@ -86,37 +104,68 @@ impl Monitor {
                 });
             }
         }) {
-            info!("已注册频率检测开关钩子");
+            Ok(handle) => {
+                handles.push(handle);
+                info!("已注册频率检测开关钩子");
+            }
+            Err(e) => error!("注册频率检测开关钩子失败: {}", e),
         }
 
         // 监听刷新间隔变化
         let monitor = self.clone();
-        if let Ok(()) = settings_store::add_setting_hook("refresh_interval", move |_, value| {
-            if let Some(interval) = value.as_u64() {
-                let monitor = monitor.clone();
-                tauri::async_runtime::spawn(async move {
-                    info!("钩子-刷新间隔变化: {}", interval);
+        match settings_store::add_async_setting_hook("refresh_interval", move |change| {
+            let monitor = monitor.clone();
+            async move {
+                if change.new_value.as_u64().is_some() {
+                    info!("钩子-刷新间隔变化: {} -> {}", change.old_value, change.new_value);
                     // 直接重启监控
                     monitor.start();
-                });
+                }
             }
         }) {
-            info!("已注册刷新间隔钩子");
+            Ok(handle) => {
+                handles.push(handle);
+                info!("已注册刷新间隔钩子");
+            }
+            Err(e) => error!("注册刷新间隔钩子失败: {}", e),
         }
 
         //监听频率模式变化
         let monitor = self.clone();
-        if let Ok(()) = settings_store::add_setting_hook("frequency_mode", move |_, value| {
-            if let Some(mode_str) = value.as_str() {
-                let mode = mode_str.to_string(); // 转换为拥有所有权的字符串
-                let monitor = monitor.clone();
-                tauri::async_runtime::spawn(async move {
-                    info!("钩子-频率模式变化: {}", mode);
+        match settings_store::add_async_setting_hook("frequency_mode", move |change| {
+            let monitor = monitor.clone();
+            async move {
+                if change.new_value.as_str().is_some() {
+                    info!("钩子-频率模式变化: {} -> {}", change.old_value, change.new_value);
                     monitor.start();
-                });
+                }
+            }
+        }) {
+            Ok(handle) => {
+                handles.push(handle);
+                info!("已注册频率模式钩子");
+            }
+            Err(e) => error!("注册频率模式钩子失败: {}", e),
+        }
+
+        // 供电方式的覆盖设置变化时按新的刷新间隔重启
+        let monitor = self.clone();
+        match settings_store::add_async_setting_hook("power_source_profiles", move |change| {
+            let monitor = monitor.clone();
+            async move {
+                info!("钩子-供电方式设置变化: {} -> {}", change.old_value, change.new_value);
+                monitor.start();
             }
         }) {
-            info!("已注册频率模式钩子");
+            Ok(handle) => {
+                handles.push(handle);
+                info!("已注册供电方式设置钩子");
+            }
+            Err(e) => error!("注册供电方式设置钩子失败: {}", e),
+        }
+
+        if let Ok(mut hook_handles) = self.hook_handles.lock() {
+            *hook_handles = handles;
         }
 
         // 在设置窗口时初始化监控器的设置
@@ -202,15 +251,16 @@ impl Monitor {
 
         // 不使用 await，直接 spawn 新任务
         tauri::async_runtime::spawn(async move {
-            let mut interval_timer = tokio_interval(Duration::from_millis(settings_store::get_refresh_interval()));
+            // 刷新间隔按当前供电方式取值，供电方式变化导致间隔变化时重启监控
+            let started_interval = settings_store::get_effective_refresh_interval();
+            let mut interval_timer = tokio_interval(Duration::from_millis(started_interval));
             interval_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
-            info!("新的监控器启动，版本号: {}，刷新间隔: {}ms", current_version, settings_store::get_refresh_interval());
+            info!("新的监控器启动，版本号: {}，刷新间隔: {}ms", current_version, started_interval);
             
             let current_version = version.load(Ordering::SeqCst);
 
-            let mut last_frequencies: Vec<u64> = Vec::new();
-            let mut unchanged_count = 0;
+            let mut auto_switch = AutoSwitchTracker::default();
 
             let mut skip_interval_for_first_time = true;
 
@@ -229,12 +279,17 @@ impl Monitor {
                 // let frequency_mode = settings_guard.frequency_mode.clone();
                 let frequency_mode = settings_store::get_frequency_mode();
                 // let frequency_threshold = settings_guard.frequency_threshold;
-                let frequency_threshold = settings_store::get_frequency_threshold();
+                let frequency_threshold = settings_store::get_effective_frequency_threshold();
                 let trigger_action_enabled = settings_store::get_trigger_action_enabled().unwrap_or(false);
                 // let auto_switch_enabled = settings_guard.auto_switch_enabled;
                 let auto_switch_enabled = settings_store::get_auto_switch_enabled();
                 // let refresh_interval = settings_guard.refresh_interval;
-                let refresh_interval = settings_store::get_refresh_interval();
+                let refresh_interval = settings_store::get_effective_refresh_interval();
+                if refresh_interval != started_interval {
+                    info!("供电方式变化，刷新间隔 {}ms -> {}ms，重启监控", started_interval, refresh_interval);
+                    monitor.start();
+                    break;
+                }
                 // drop(settings_guard);
 
                 // 检查是否需要更新定时器间隔
@@ -244,8 +299,9 @@ impl Monitor {
                 //     info!("更新刷新间隔为: {}ms", refresh_interval);
                 // }
 
-                // 获取频率数据
+                // 获取频率数据和各核心占用率
                 let frequencies = Self::get_frequencies(&frequency_mode).await;
+                let loads = Self::get_loads();
 
                 //如果版本不对，那就不用往下了
                 if (version.load(Ordering::SeqCst) != current_version) || (settings_store::get_frequency_detection_enabled() == false) {
@@ -255,22 +311,10 @@ impl Monitor {
 
                 // 只在启用自动切换时进行频率变化检测
                 if auto_switch_enabled && frequency_mode == "1" {
-                    // 检查频率是否有变化
-                    let has_changed = if !frequencies.is_empty() 
-                        && !last_frequencies.is_empty() 
-                        && frequencies.len() == last_frequencies.len() 
-                    {
-                        frequencies.iter().zip(last_frequencies.iter()).any(|(a, b)| a != b)
-                    } else {
-                        false
-                    };
-
-                    if has_changed {
-                        unchanged_count = 0;
-                    } else {
-                        unchanged_count += 1;
-                        // 获取阈值用于日志
-                        let threshold = settings_store::get_auto_switch_threshold();
+                    let threshold = settings_store::get_auto_switch_threshold();
+                    let decision = auto_switch.observe(&frequencies, threshold);
+                    let unchanged_count = decision.unchanged_count;
+                    if unchanged_count > 0 {
                         info!("频率未更新，计数: {}/{}", unchanged_count, threshold);
                     }
 
@@ -282,12 +326,11 @@ impl Monitor {
                     }
 
                     // 检查是否需要切换模式
-                    let threshold = settings_store::get_auto_switch_threshold();
-                    if unchanged_count >= threshold {
+                    if decision.switch_mode {
                         monitor.set_mode_auto_switched(true).await;
                         info!("触发自动切换到 CalcMhz 模式");
                         if let Some(window) = &window {
-                            send_notification_with_handle(&window.app_handle(), "CPU频率检测模式自动变更", "由于在Sysinfo模式下频率长时间未更新，我们认为这是有问题的，自动切换到 CalcMhz 模式");
+                            send_notification_with_handle(&window.app_handle(), AUTO_SWITCH_TITLE, AUTO_SWITCH_MESSAGE);
                             let _ = window.emit(
                                 "mode-switched",
                                 json!({
@@ -299,88 +342,108 @@ impl Monitor {
                         }
                         drop(state);
 
+                        // 切换模式前的这次采样同样要记录，否则记录中会缺少触发切换的那一段
+                        Self::record_trace_sample(
+                            &frequency_mode,
+                            &frequencies,
+                            &loads,
+                            frequency_threshold,
+                            false,
+                            None,
+                            power_source::current_power_source(),
+                        )
+                        .await;
+
                         // 更新设置
                         let mut settings = settings.lock().await;
                         settings.frequency_mode = "2".to_string();
                         drop(settings);
-                        unchanged_count = 0;
                         continue;
                     }
-                } else {
+                } else if auto_switch.reset(&frequencies) {
                     // 如果自动切换被禁用，立即重置计数器和状态
-                    if unchanged_count > 0 {
-                        unchanged_count = 0;
-                        let mut state = state.lock().await;
-                        state.last_update_count = 0;
-                        if let Some(window) = &window {
-                            let _ = window.emit("monitor-state-updated", &*state);
-                        }
+                    let mut state = state.lock().await;
+                    state.last_update_count = 0;
+                    if let Some(window) = &window {
+                        let _ = window.emit("monitor-state-updated", &*state);
                     }
                 }
 
-                // 更新 last_frequencies
-                last_frequencies = frequencies.clone();
+                // 读取温度可能较慢（Windows 上需要查询 WMI），放到阻塞线程中
+                let cpu_temperature = async_runtime::spawn_blocking(temperature::read_cpu_temperature)
+                    .await
+                    .unwrap_or(None);
 
                 // 更新状态
                 let mut state = state.lock().await;
                 state.frequencies = frequencies.clone();
+                state.power_source = power_source::current_power_source();
+                state.temperature = cpu_temperature;
+                state.loads = loads.clone();
+                // 复制出状态后立即释放锁，派发动作和评估规则期间其他任务仍能读取状态
+                let snapshot = state.clone();
+                drop(state);
+                rule_engine::record_sample(&frequencies, cpu_temperature);
                 
                 // 检查是否需要执行触发动作
                 if let Some(window) = &window {
-                    let settings = settings.lock().await;
-                    
-                    // 只有在总开关打开时才检查频率并执行动作
+                    // 只有在总开关打开时才按各动作自己的触发条件派发
                     if trigger_action_enabled {
-                        for (i, &freq) in frequencies.iter().enumerate() {
-                            let freq_ghz = freq as f64 / 1000.0;
-                            if freq_ghz > frequency_threshold {
-                                // 检查是否需要发送通知（防抖）
-                                let current_time = SystemTime::now()
-                                    .duration_since(UNIX_EPOCH)
-                                    .unwrap()
-                                    .as_secs();
-                                
-                                let mut last_alert = last_alert_time.lock().await;
-                                if current_time - *last_alert >= settings.alert_debounce_seconds {
-                                    *last_alert = current_time;
-                                    
-                                    // 执行触发动作
-                                    if let Ok(actions) = load_trigger_actions(window.app_handle().clone()).await {
-                                        if let Some(active_action) = actions.iter().find(|a| a.enabled) {
-                                            info!("发现活动的触发动作，准备执行: {}", active_action.name);
-                                            trigger_action::execute_trigger_action(
-                                                active_action,
-                                            ).await;
-                                        } else {
-                                            warn!("未找到已启用的触发动作");
-                                        }
-                                    } else {
-                                        error!("加载触发动作失败");
-                                    }
-                                }
-                                break; // 找到一个超过阈值的就足够了
+                        // 进程规则要求屏蔽触发动作时（如编译、游戏期间）不派发，规则引擎和定时任务切换计划不受影响
+                        if process_rules::actions_suppressed() {
+                            info!("匹配的进程正在运行，跳过触发动作");
+                        } else {
+                            let sample = FrequencySample {
+                                frequencies: frequencies.clone(),
+                                frequency_mode: frequency_mode.clone(),
+                                global_threshold: frequency_threshold,
+                                global_cooldown_seconds: settings_store::get_alert_debounce_seconds(),
+                                power_source: snapshot.power_source,
+                                temperature: cpu_temperature,
+                                loads: loads.clone(),
+                            };
+                            if let Err(e) = trigger_action::dispatch_trigger_actions(window.app_handle(), &sample).await {
+                                error!("派发触发动作失败: {}", e);
                             }
                         }
                     }
-                    drop(settings);
+
+                    // 规则由各自的 enabled 控制，不受触发动作总开关影响
+                    rule_engine::evaluate_rules(window.app_handle()).await;
 
                     // 发送状态更新到前端
-                    let _ = window.emit("monitor-state-updated", &*state);
+                    let _ = window.emit("monitor-state-updated", &snapshot);
                 }
                 
-                // 检查频率阈值和触发动作
-                if let Some(window) = &window {
-                    Self::check_frequency_threshold(
-                        &frequencies,
-                        frequency_threshold,
-                        trigger_action_enabled,
-                        window,
-                        last_alert_time.clone(),
-                        // settings.lock().await.alert_debounce_seconds,
-                        settings_store::get_alert_debounce_seconds()
-                    )
-                    .await;
-                }
+                // 检查频率阈值并发送提醒
+                let alert_sent = match &window {
+                    Some(window) => {
+                        Self::check_frequency_threshold(
+                            &frequencies,
+                            frequency_threshold,
+                            cpu_temperature,
+                            &loads,
+                            window,
+                            last_alert_time.clone(),
+                            // settings.lock().await.alert_debounce_seconds,
+                            settings_store::get_alert_debounce_seconds()
+                        )
+                        .await
+                    }
+                    None => false,
+                };
+
+                // 正在记录频率时追加本次采样
+                Self::record_trace_sample(
+                    &frequency_mode,
+                    &frequencies,
+                    &loads,
+                    frequency_threshold,
+                    alert_sent,
+                    cpu_temperature,
+                    snapshot.power_source,
+                )
+                .await;
                 
             }
             
@@ -415,7 +478,7 @@ impl Monitor {
         info!("停止监控器");
     }
 
-    async fn get_frequencies(frequency_mode: &str) -> Vec<u64> {
+    pub(crate) async fn get_frequencies(frequency_mode: &str) -> Vec<u64> {
         if frequency_mode == "1" {
             // SysInfo 模式
             let mut sys = System::new();
@@ -430,43 +493,87 @@ impl Monitor {
         }
     }
 
+    // 各核心占用率，占用率要比较两次刷新之间的差值，所以复用同一个 System
+    // 第一次调用时还没有上一次的数据，返回的占用率为 0
+    pub(crate) fn get_loads() -> Vec<f32> {
+        match LOAD_SYSTEM.lock() {
+            Ok(mut sys) => {
+                sys.refresh_cpu_usage();
+                sys.cpus().iter().map(|cpu| cpu.cpu_usage()).collect()
+            }
+            Err(_) => Vec::new(),
+        }
+    }
+
+    // 正在记录频率时追加一次采样，当前计划使用缓存，避免每个周期都枚举电源计划
+    async fn record_trace_sample(
+        frequency_mode: &str,
+        frequencies: &[u64],
+        loads: &[f32],
+        frequency_threshold: f64,
+        alert_sent: bool,
+        temperature: Option<f32>,
+        power_source: Option<PowerSource>,
+    ) {
+        if !trace_recorder::is_recording() {
+            return;
+        }
+        let active_plan = async_runtime::spawn_blocking(power_plan::cached_active_plan_guid)
+            .await
+            .unwrap_or(None);
+        trace_recorder::record(&TraceRecord {
+            timestamp_ms: now_millis(),
+            frequency_source: trace_recorder::frequency_source(frequency_mode).to_string(),
+            active_plan,
+            alert_state: indicator_status(exceeded_cores(frequencies, frequency_threshold).len(), frequencies.len())
+                .to_string(),
+            alert_sent,
+            temperature,
+            power_source,
+            frequencies: frequencies.to_vec(),
+            loads: loads.to_vec(),
+        });
+    }
+
     async fn check_frequency_threshold(
         frequencies: &[u64],
         threshold: f64,
-        trigger_action_enabled: bool,
+        temperature: Option<f32>,
+        loads: &[f32],
         window: &WebviewWindow,
         last_alert_time: Arc<Mutex<u64>>,
         debounce_seconds: u64,
-    ) {
-        let mut exceeded_count = 0;
-        let mut exceeded_cores = Vec::new();
-
-        for (index, freq) in frequencies.iter().enumerate() {
-            let freq_ghz = *freq as f64 / 1000.0;
-            if freq_ghz > threshold {
-                exceeded_count += 1;
+    ) -> bool {
+        let mut alert_sent = false;
+        let exceeded = exceeded_cores(frequencies, threshold);
+        let exceeded_count = exceeded.len();
+        let exceeded_cores: Vec<serde_json::Value> = exceeded
+            .iter()
+            .map(|&index| {
+                let freq_ghz = frequencies[index] as f64 / 1000.0;
                 warn!("CPU核心 {} 频率超限: {:.2} GHz", index, freq_ghz);
-
-                exceeded_cores.push(json!({
+                json!({
                     "core": index,
                     "frequency": freq_ghz,
-                    "threshold": threshold
-                }));
-            }
-        }
-
-        // 如果有核心超过阈值，检查防抖
-        if exceeded_count > 0 {
+                    "threshold": threshold,
+                    "load": core_load(frequencies, loads, index)
+                })
+            })
+            .collect();
+
+        // 如果有核心超过阈值，检查防抖；匹配的进程要求屏蔽提醒时只更新指示器
+        if exceeded_count > 0 && process_rules::alerts_suppressed() {
+            info!("匹配的进程正在运行，不发送频率提醒");
+        } else if exceeded_count > 0 {
             let current_time = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap_or_default()
                 .as_secs();
 
             let mut last_time = last_alert_time.lock().await;
-            if current_time - *last_time >= debounce_seconds {
-                // 更新上次报警时间
-                *last_time = current_time;
+            if alert_due(&mut last_time, current_time, debounce_seconds) {
                 drop(last_time); // 提前释放锁，避免死锁
+                alert_sent = true;
 
                 // 发送通知
                 let _ = window.emit(
@@ -475,46 +582,22 @@ impl Monitor {
                         "total_cores": frequencies.len(),
                         "exceeded_count": exceeded_count,
                         "exceeded_cores": exceeded_cores,
-                        "threshold": threshold
+                        "threshold": threshold,
+                        "temperature": temperature
                     }),
                 );
 
-                // 发送系统通知
-                if exceeded_count == frequencies.len() {
-                    send_notification("CPU 频率警告", &format!("所有核心频率均超过 {:.1} GHz", threshold));
-                } else {
-                    send_notification("CPU 频率警告", &format!("{} 个核心频率超过 {:.1} GHz", exceeded_count, threshold));
-                }
-
-                // 如果启用了触发动作，立即执行
-                if trigger_action_enabled {
-                    if let Ok(actions) = trigger_action::load_trigger_actions(window.app_handle().clone()).await {
-                        if let Some(active_action) = actions.iter().find(|a| a.enabled) {
-                            info!("发现活动的触发动作，准备执行: {}", active_action.name);
-                            trigger_action::execute_trigger_action(
-                                active_action,
-                            ).await;
-                        } else {
-                            warn!("未找到已启用的触发动作");
-                        }
-                    } else {
-                        error!("加载触发动作失败");
-                    }
-                }
+                // 发送系统通知，有温度数据时一并显示
+                let _ = send_notification(
+                    ALERT_TITLE,
+                    &alert_message(exceeded_count, frequencies.len(), threshold, temperature),
+                );
             }
         }
 
         // 更新指示器状态
-        let _ = window.emit(
-            "indicator-status-changed",
-            if exceeded_count == frequencies.len() {
-                "danger"
-            } else if exceeded_count > 0 {
-                "warning"
-            } else {
-                "normal"
-            },
-        );
+        let _ = window.emit("indicator-status-changed", indicator_status(exceeded_count, frequencies.len()));
+        alert_sent
     }
 
     // pub async fn update_frequency_mode(&self, mode: String) {
@@ -571,7 +654,7 @@ impl Monitor {
         if let Ok(actions) =
             trigger_action::load_trigger_actions(window.app_handle().clone()).await
         {
-            actions.iter().any(|a| a.enabled)
+            !trigger_action::eligible_actions(&actions).is_empty()
         } else {
             false
         }
@@ -638,11 +721,108 @@ impl Monitor {
     }
 }
 
+// 监控循环中与时间、窗口无关的判断逻辑，实时监控和离线回放（replay.rs）共用
+
+// 频率提醒的通知标题
+pub const ALERT_TITLE: &str = "CPU 频率警告";
+
+// 自动切换频率获取模式时的通知
+pub const AUTO_SWITCH_TITLE: &str = "CPU频率检测模式自动变更";
+pub const AUTO_SWITCH_MESSAGE: &str = "由于在Sysinfo模式下频率长时间未更新，我们认为这是有问题的，自动切换到 CalcMhz 模式";
+
+// SysInfo 模式下频率长时间不变时切换到 CalcMhz 模式的计数
+#[derive(Default, Debug)]
+pub struct AutoSwitchTracker {
+    last_frequencies: Vec<u64>,
+    unchanged_count: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct AutoSwitchDecision {
+    pub unchanged_count: u64,
+    // 达到阈值，应切换到 CalcMhz 模式（计数已清零）
+    pub switch_mode: bool,
+}
+
+impl AutoSwitchTracker {
+    // 自动切换启用时每次采样调用一次
+    pub fn observe(&mut self, frequencies: &[u64], threshold: u64) -> AutoSwitchDecision {
+        // 没有上一次的数据或核心数变化时视为未更新
+        let has_changed = !frequencies.is_empty()
+            && !self.last_frequencies.is_empty()
+            && frequencies.len() == self.last_frequencies.len()
+            && frequencies.iter().zip(&self.last_frequencies).any(|(a, b)| a != b);
+        if has_changed {
+            self.unchanged_count = 0;
+        } else {
+            self.unchanged_count += 1;
+        }
+
+        let unchanged_count = self.unchanged_count;
+        if unchanged_count >= threshold {
+            self.unchanged_count = 0;
+            return AutoSwitchDecision { unchanged_count, switch_mode: true };
+        }
+        self.last_frequencies = frequencies.to_vec();
+        AutoSwitchDecision { unchanged_count, switch_mode: false }
+    }
+
+    // 自动切换未启用时调用，返回计数是否从非零被清零
+    pub fn reset(&mut self, frequencies: &[u64]) -> bool {
+        self.last_frequencies = frequencies.to_vec();
+        std::mem::take(&mut self.unchanged_count) > 0
+    }
+}
+
+// 频率高于阈值（GHz）的核心序号
+pub fn exceeded_cores(frequencies: &[u64], threshold: f64) -> Vec<usize> {
+    frequencies
+        .iter()
+        .enumerate()
+        .filter(|(_, &freq)| freq as f64 / 1000.0 > threshold)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+// 指示器状态：全部超限为 danger，部分超限为 warning
+pub fn indicator_status(exceeded_count: usize, total_cores: usize) -> &'static str {
+    if exceeded_count == total_cores {
+        "danger"
+    } else if exceeded_count > 0 {
+        "warning"
+    } else {
+        "normal"
+    }
+}
+
+// 距上次提醒已超过防抖时间时更新提醒时间并返回 true，时间单位为秒
+pub fn alert_due(last_alert_time: &mut u64, now: u64, debounce_seconds: u64) -> bool {
+    if now.saturating_sub(*last_alert_time) < debounce_seconds {
+        return false;
+    }
+    *last_alert_time = now;
+    true
+}
+
+// 频率提醒的通知内容，有温度数据时一并显示
+pub fn alert_message(exceeded_count: usize, total_cores: usize, threshold: f64, temperature: Option<f32>) -> String {
+    let temperature_text = temperature
+        .map(|t| format!("，CPU 温度 {:.0}°C", t))
+        .unwrap_or_default();
+    if exceeded_count == total_cores {
+        format!("所有核心频率均超过 {:.1} GHz{}", threshold, temperature_text)
+    } else {
+        format!("{} 个核心频率超过 {:.1} GHz{}", exceeded_count, threshold, temperature_text)
+    }
+}
+
 // 创建一个全局监控实例
 lazy_static::lazy_static! {
     pub static ref MONITOR: Monitor = Monitor::new();
+    static ref LOAD_SYSTEM: std::sync::Mutex<System> = std::sync::Mutex::new(System::new());
 }
 
+
 // 添加一个 tauri 命令
 #[tauri::command]
 pub async fn get_monitor_state() -> Result<MonitorState, String> {
@@ -0,0 +1,620 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::plan_diff::{ComparablePlan, ComparableSetting};
+use crate::power_plan::{
+    delete_power_plan, duplicate_power_plan, get_power_plans, rename_power_plan, set_active_plan, PowerPlan,
+};
+use crate::PowerPlanUtils::GetPowerPlans::{get_unified_power_scheme, write_value_set, SettingType};
+
+const PROCESSOR_SUBGROUP: &str = "54533251-82be-4824-96c1-47b60b740d00";
+
+// 系统自带的电源计划，based_on 可以直接写这些名称
+const PLAN_ALIASES: &[(&str, &str)] = &[
+    ("balanced", "381b4222-f694-41f0-9685-ff5bb260df2e"),
+    ("high_performance", "8c5e7fda-e8bf-4a96-9a85-a6e23a8c635c"),
+    ("power_saver", "a1841308-3541-4fab-bc81-f71556f20b4a"),
+];
+
+// 常用设置的固定名称：系统语言不同时友好名称也不同，配置文件里用这些名称不受影响
+struct SettingAlias {
+    name: &'static str,
+    subgroup_guid: &'static str,
+    setting_guid: &'static str,
+    values: &'static [(&'static str, u32)],
+}
+
+const SETTING_ALIASES: &[SettingAlias] = &[
+    SettingAlias {
+        name: "boost_mode",
+        subgroup_guid: PROCESSOR_SUBGROUP,
+        setting_guid: "be337238-0d82-4146-a960-4f3749d470c7",
+        values: &[
+            ("disabled", 0),
+            ("enabled", 1),
+            ("aggressive", 2),
+            ("efficient_enabled", 3),
+            ("efficient_aggressive", 4),
+            ("aggressive_at_guaranteed", 5),
+            ("efficient_aggressive_at_guaranteed", 6),
+        ],
+    },
+    SettingAlias {
+        name: "boost_policy",
+        subgroup_guid: PROCESSOR_SUBGROUP,
+        setting_guid: "45bcc044-d885-43e2-8605-ee0ec6e96b59",
+        values: &[],
+    },
+    SettingAlias {
+        name: "max_processor_state",
+        subgroup_guid: PROCESSOR_SUBGROUP,
+        setting_guid: "bc5038f7-23e0-4960-96da-33abaf5935ec",
+        values: &[],
+    },
+    SettingAlias {
+        name: "min_processor_state",
+        subgroup_guid: PROCESSOR_SUBGROUP,
+        setting_guid: "893dee8e-2bef-41e0-89c6-b55d0929964c",
+        values: &[],
+    },
+    SettingAlias {
+        name: "cooling_policy",
+        subgroup_guid: PROCESSOR_SUBGROUP,
+        setting_guid: "94d3a615-a899-4ac5-ae2b-e4d8f634367f",
+        values: &[("passive", 0), ("active", 1)],
+    },
+];
+
+// 设置值：可以写数值，也可以写可选项名称（内置别名或系统显示的名称）
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum ValueSpec {
+    Index(u32),
+    Label(String),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SplitValue {
+    #[serde(default)]
+    pub ac: Option<ValueSpec>,
+    #[serde(default)]
+    pub dc: Option<ValueSpec>,
+}
+
+// 直接写值表示 AC/DC 相同，写成 { ac = .., dc = .. } 时未填写的一侧保持不变
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum SettingSpec {
+    Both(ValueSpec),
+    Split(SplitValue),
+}
+
+impl SettingSpec {
+    fn sides(&self) -> (Option<&ValueSpec>, Option<&ValueSpec>) {
+        match self {
+            SettingSpec::Both(value) => (Some(value), Some(value)),
+            SettingSpec::Split(split) => (split.ac.as_ref(), split.dc.as_ref()),
+        }
+    }
+}
+
+// 配置文件中描述的一个电源计划
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct DesiredPlan {
+    pub name: String,
+    // 已知计划 GUID 时优先按 GUID 查找，找不到再按名称查找
+    #[serde(default)]
+    pub guid: Option<String>,
+    // 计划不存在时复制哪个计划，可以是内置别名、GUID 或计划名称
+    #[serde(default)]
+    pub based_on: Option<String>,
+    // 计划的旧名称，找到后改名而不是新建
+    #[serde(default)]
+    pub rename_from: Vec<String>,
+    // 键为内置设置名称、"子组GUID/设置GUID"、设置 GUID 或系统显示的设置名称
+    #[serde(default)]
+    pub settings: BTreeMap<String, SettingSpec>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct PlanConfigFile {
+    #[serde(default, rename = "plan")]
+    plans: Vec<DesiredPlan>,
+}
+
+pub fn parse_plan_config(content: &str) -> Result<Vec<DesiredPlan>, String> {
+    let file: PlanConfigFile = toml::from_str(content).map_err(|e| format!("解析电源计划配置失败: {}", e))?;
+    for (index, plan) in file.plans.iter().enumerate() {
+        if plan.name.trim().is_empty() {
+            return Err(format!("第 {} 个电源计划缺少名称", index + 1));
+        }
+        if file.plans[..index].iter().any(|p| p.name.eq_ignore_ascii_case(&plan.name)) {
+            return Err(format!("电源计划名称重复: {}", plan.name));
+        }
+    }
+    Ok(file.plans)
+}
+
+// 要对电源计划本身做的操作
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PlanOperation {
+    Keep { plan_guid: String },
+    Rename { plan_guid: String, from: String },
+    Create { base_guid: String },
+}
+
+fn find_by_name<'a>(plans: &'a [PowerPlan], name: &str) -> Result<Option<&'a PowerPlan>, String> {
+    let mut matches = plans.iter().filter(|p| p.name.trim().eq_ignore_ascii_case(name.trim()));
+    let found = matches.next();
+    if matches.next().is_some() {
+        return Err(format!("存在多个名为 {} 的电源计划，请在配置中填写 guid", name));
+    }
+    Ok(found)
+}
+
+fn resolve_base(based_on: &str, plans: &[PowerPlan]) -> Result<String, String> {
+    let guid = PLAN_ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(based_on))
+        .map(|(_, guid)| *guid)
+        .unwrap_or(based_on);
+    if let Some(plan) = plans.iter().find(|p| p.guid.eq_ignore_ascii_case(guid)) {
+        return Ok(plan.guid.clone());
+    }
+    find_by_name(plans, based_on)?
+        .map(|p| p.guid.clone())
+        .ok_or_else(|| format!("找不到作为基础的电源计划: {}", based_on))
+}
+
+// 根据当前已有的计划决定保留、改名还是新建
+pub fn resolve_plan(desired: &DesiredPlan, plans: &[PowerPlan]) -> Result<PlanOperation, String> {
+    let by_guid = desired
+        .guid
+        .as_ref()
+        .and_then(|guid| plans.iter().find(|p| p.guid.eq_ignore_ascii_case(guid)));
+    let existing = match by_guid {
+        Some(plan) => Some(plan),
+        None => find_by_name(plans, &desired.name)?,
+    };
+    if let Some(plan) = existing {
+        return Ok(if plan.name.trim() == desired.name.trim() {
+            PlanOperation::Keep { plan_guid: plan.guid.clone() }
+        } else {
+            PlanOperation::Rename {
+                plan_guid: plan.guid.clone(),
+                from: plan.name.clone(),
+            }
+        });
+    }
+
+    for old_name in &desired.rename_from {
+        if let Some(plan) = find_by_name(plans, old_name)? {
+            return Ok(PlanOperation::Rename {
+                plan_guid: plan.guid.clone(),
+                from: plan.name.clone(),
+            });
+        }
+    }
+
+    let based_on = desired
+        .based_on
+        .as_deref()
+        .ok_or_else(|| format!("电源计划 {} 不存在，需要填写 based_on 才能创建", desired.name))?;
+    Ok(PlanOperation::Create {
+        base_guid: resolve_base(based_on, plans)?,
+    })
+}
+
+// 需要写入的一项设置，from_* 为当前值
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SettingChange {
+    pub key: String,
+    pub subgroup_guid: String,
+    pub setting_guid: String,
+    pub setting_name: String,
+    pub from_ac: u32,
+    pub from_dc: u32,
+    pub ac_value: u32,
+    pub dc_value: u32,
+}
+
+fn find_setting<'a>(key: &str, current: &'a ComparablePlan) -> Result<(&'a ComparableSetting, Option<&'static SettingAlias>), String> {
+    let alias = SETTING_ALIASES.iter().find(|a| a.name.eq_ignore_ascii_case(key));
+    let (subgroup_guid, setting_guid) = match alias {
+        Some(alias) => (Some(alias.subgroup_guid), alias.setting_guid),
+        None => match key.split_once('/') {
+            Some((subgroup, setting)) => (Some(subgroup.trim()), setting.trim()),
+            None => (None, key.trim()),
+        },
+    };
+
+    let by_guid = current.settings.iter().find(|s| {
+        s.setting_guid.eq_ignore_ascii_case(setting_guid)
+            && subgroup_guid.is_none_or(|subgroup| s.subgroup_guid.eq_ignore_ascii_case(subgroup))
+    });
+    if let Some(setting) = by_guid {
+        return Ok((setting, alias));
+    }
+    if alias.is_some() || subgroup_guid.is_some() {
+        return Err(format!("电源计划中没有设置 {}", key));
+    }
+
+    // 最后按系统显示的名称查找
+    let mut by_name = current.settings.iter().filter(|s| s.setting_name.trim().eq_ignore_ascii_case(key.trim()));
+    let found = by_name.next().ok_or_else(|| format!("电源计划中没有设置 {}", key))?;
+    if by_name.next().is_some() {
+        return Err(format!("有多个名为 {} 的设置，请改用 子组GUID/设置GUID", key));
+    }
+    Ok((found, None))
+}
+
+fn resolve_value(
+    key: &str,
+    spec: &ValueSpec,
+    setting: &ComparableSetting,
+    alias: Option<&SettingAlias>,
+) -> Result<u32, String> {
+    let value = match spec {
+        ValueSpec::Index(value) => *value,
+        ValueSpec::Label(label) => {
+            let from_alias = alias.and_then(|a| {
+                a.values
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(label.trim()))
+                    .map(|(_, value)| *value)
+            });
+            let from_possible = || {
+                setting.possible_values.as_ref().and_then(|possible| match possible.setting_type {
+                    SettingType::Enumerated => possible
+                        .data
+                        .iter()
+                        .find(|d| d.name.trim().eq_ignore_ascii_case(label.trim()))
+                        .map(|d| d.value),
+                    SettingType::Range => None,
+                })
+            };
+            from_alias
+                .or_else(from_possible)
+                .ok_or_else(|| format!("设置 {} 没有名为 {} 的可选值", key, label))?
+        }
+    };
+    if let Some(possible) = &setting.possible_values {
+        possible.validate_value(value).map_err(|e| format!("设置 {}: {}", key, e))?;
+    }
+    Ok(value)
+}
+
+// 与当前值比较，只返回需要写入的设置，重复执行时结果为空
+pub fn compute_setting_changes(desired: &DesiredPlan, current: &ComparablePlan) -> Result<Vec<SettingChange>, String> {
+    let mut changes = Vec::new();
+    for (key, spec) in &desired.settings {
+        let (setting, alias) = find_setting(key, current)?;
+        let (ac, dc) = spec.sides();
+        let ac_value = match ac {
+            Some(value) => resolve_value(key, value, setting, alias)?,
+            None => setting.ac_value,
+        };
+        let dc_value = match dc {
+            Some(value) => resolve_value(key, value, setting, alias)?,
+            None => setting.dc_value,
+        };
+        if ac_value == setting.ac_value && dc_value == setting.dc_value {
+            continue;
+        }
+        changes.push(SettingChange {
+            key: key.clone(),
+            subgroup_guid: setting.subgroup_guid.clone(),
+            setting_guid: setting.setting_guid.clone(),
+            setting_name: setting.setting_name.clone(),
+            from_ac: setting.ac_value,
+            from_dc: setting.dc_value,
+            ac_value,
+            dc_value,
+        });
+    }
+    Ok(changes)
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PlanApplyResult {
+    pub name: String,
+    pub operation: PlanOperation,
+    // 新建的计划在预览时还没有 GUID
+    pub plan_guid: Option<String>,
+    pub changes: Vec<SettingChange>,
+}
+
+impl PlanApplyResult {
+    pub fn is_noop(&self) -> bool {
+        matches!(self.operation, PlanOperation::Keep { .. }) && self.changes.is_empty()
+    }
+}
+
+fn load_comparable(guid: &str) -> Result<ComparablePlan, String> {
+    get_unified_power_scheme(guid)
+        .map(|scheme| ComparablePlan::from_unified(&scheme))
+        .map_err(|e| format!("读取电源计划 {} 失败: {}", guid, e))
+}
+
+// 计算每个计划要做的操作，不修改系统；新建的计划与基础计划的设置相同，按基础计划比较
+fn preview(desired: &[DesiredPlan]) -> Result<Vec<PlanApplyResult>, String> {
+    let plans = get_power_plans()?;
+    desired
+        .iter()
+        .map(|plan| {
+            let operation = resolve_plan(plan, &plans)?;
+            let (plan_guid, compare_guid) = match &operation {
+                PlanOperation::Keep { plan_guid } | PlanOperation::Rename { plan_guid, .. } => {
+                    (Some(plan_guid.clone()), plan_guid.clone())
+                }
+                PlanOperation::Create { base_guid } => (None, base_guid.clone()),
+            };
+            let changes = compute_setting_changes(plan, &load_comparable(&compare_guid)?)
+                .map_err(|e| format!("电源计划 {}: {}", plan.name, e))?;
+            Ok(PlanApplyResult {
+                name: plan.name.clone(),
+                operation,
+                plan_guid,
+                changes,
+            })
+        })
+        .collect()
+}
+
+// 执行一个计划的修改；新建的计划中途失败时删除，不留下只完成一半的副本
+fn execute(result: &mut PlanApplyResult) -> Result<(), String> {
+    let outcome = execute_steps(result);
+    if let (Err(_), PlanOperation::Create { .. }, Some(guid)) = (&outcome, &result.operation, &result.plan_guid) {
+        match delete_power_plan(guid) {
+            Ok(()) => info!("已删除未完成的电源计划 {}（{}）", result.name, guid),
+            Err(e) => warn!("删除未完成的电源计划 {}（{}）失败: {}", result.name, guid, e),
+        }
+        result.plan_guid = None;
+    }
+    outcome
+}
+
+fn execute_steps(result: &mut PlanApplyResult) -> Result<(), String> {
+    let plan_guid = match &result.operation {
+        PlanOperation::Keep { plan_guid } => plan_guid.clone(),
+        PlanOperation::Rename { plan_guid, from } => {
+            rename_power_plan(plan_guid, &result.name)?;
+            info!("电源计划 {} 已改名为 {}", from, result.name);
+            plan_guid.clone()
+        }
+        PlanOperation::Create { base_guid } => {
+            let new_guid = duplicate_power_plan(base_guid)?;
+            // 先记下新计划，后续步骤失败时据此删除
+            result.plan_guid = Some(new_guid.clone());
+            rename_power_plan(&new_guid, &result.name)?;
+            info!("已基于 {} 创建电源计划 {}（{}）", base_guid, result.name, new_guid);
+            new_guid
+        }
+    };
+    result.plan_guid = Some(plan_guid.clone());
+
+    for change in &result.changes {
+        write_value_set(&plan_guid, &change.subgroup_guid, &change.setting_guid, change.ac_value, change.dc_value)
+            .map_err(|e| format!("写入电源计划 {} 的设置 {} 失败: {}", result.name, change.key, e))?;
+    }
+
+    // 修改的是正在使用的计划时重新激活，使新值立即生效
+    if !result.changes.is_empty()
+        && get_power_plans()?
+            .iter()
+            .any(|p| p.is_active && p.guid.eq_ignore_ascii_case(&plan_guid))
+    {
+        set_active_plan(&plan_guid)?;
+    }
+    Ok(())
+}
+
+fn read_plan_config(path: &str) -> Result<Vec<DesiredPlan>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("读取电源计划配置失败: {}", e))?;
+    parse_plan_config(&content)
+}
+
+// 预览配置文件会对系统做的修改
+#[tauri::command]
+pub async fn preview_plan_config(path: String) -> Result<Vec<PlanApplyResult>, String> {
+    let desired = read_plan_config(&path)?;
+    tokio::task::spawn_blocking(move || preview(&desired))
+        .await
+        .map_err(|e| format!("预览电源计划配置失败: {}", e))?
+}
+
+// 按配置文件创建、改名电源计划并写入不同的设置；先检查整个文件，有错误时不做任何修改
+#[tauri::command]
+pub async fn apply_plan_config(path: String) -> Result<Vec<PlanApplyResult>, String> {
+    let desired = read_plan_config(&path)?;
+    tokio::task::spawn_blocking(move || {
+        let mut results = preview(&desired)?;
+        // 某个计划失败时不再修改后面的计划，并说明哪些计划已经改过
+        let mut changed = Vec::new();
+        for result in results.iter_mut().filter(|r| !r.is_noop()) {
+            if let Err(e) = execute(result) {
+                let changed_names = if changed.is_empty() { "无".to_string() } else { changed.join("、") };
+                return Err(format!(
+                    "应用电源计划 {} 失败: {}。已修改的计划: {}，其余计划未修改",
+                    result.name, e, changed_names
+                ));
+            }
+            changed.push(result.name.clone());
+        }
+        info!(
+            "电源计划配置已应用: {} 个计划，{} 个有修改",
+            results.len(),
+            results.iter().filter(|r| !r.is_noop()).count()
+        );
+        Ok(results)
+    })
+    .await
+    .map_err(|e| format!("应用电源计划配置失败: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PowerPlanUtils::GetPowerPlans::{PossibleSetting, SettingData};
+
+    const BALANCED: &str = "381B4222-F694-41F0-9685-FF5BB260DF2E";
+    const CUSTOM: &str = "0A5B0E2C-5D8F-4E51-9C22-2F2C3B1D9E01";
+
+    const CONFIG: &str = r#"
+        [[plan]]
+        name = "Guarded"
+        based_on = "balanced"
+        rename_from = ["Old Guarded"]
+
+        [plan.settings]
+        boost_mode = "disabled"
+        max_processor_state = { ac = 99 }
+
+        [[plan]]
+        name = "平衡"
+        guid = "381b4222-f694-41f0-9685-ff5bb260df2e"
+
+        [plan.settings]
+        "处理器性能提升模式" = "高性能"
+    "#;
+
+    fn power_plan(guid: &str, name: &str) -> PowerPlan {
+        PowerPlan {
+            guid: guid.to_string(),
+            name: name.to_string(),
+            is_active: false,
+        }
+    }
+
+    fn live_settings(boost: u32, max_state: u32) -> ComparablePlan {
+        let setting = |guid: &str, name: &str, value: u32, possible: PossibleSetting| ComparableSetting {
+            subgroup_guid: PROCESSOR_SUBGROUP.to_uppercase(),
+            subgroup_name: "处理器电源管理".to_string(),
+            setting_guid: guid.to_uppercase(),
+            setting_name: name.to_string(),
+            ac_value: value,
+            dc_value: value,
+            possible_values: Some(possible),
+        };
+        let boost_values = PossibleSetting {
+            setting_type: SettingType::Enumerated,
+            data: vec![
+                SettingData { value: 0, name: "已禁用".to_string() },
+                SettingData { value: 1, name: "已启用".to_string() },
+                SettingData { value: 2, name: "高性能".to_string() },
+            ],
+            unit: None,
+        };
+        let percent = PossibleSetting {
+            setting_type: SettingType::Range,
+            data: vec![
+                SettingData { value: 0, name: "min".to_string() },
+                SettingData { value: 100, name: "max".to_string() },
+            ],
+            unit: Some("%".to_string()),
+        };
+        ComparablePlan {
+            guid: BALANCED.to_string(),
+            name: "平衡".to_string(),
+            settings: vec![
+                setting("be337238-0d82-4146-a960-4f3749d470c7", "处理器性能提升模式", boost, boost_values),
+                setting("bc5038f7-23e0-4960-96da-33abaf5935ec", "最大处理器状态", max_state, percent),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let plans = parse_plan_config(CONFIG).unwrap();
+        assert_eq!(plans.len(), 2);
+        assert_eq!(plans[0].settings.len(), 2);
+        assert!(matches!(plans[0].settings["boost_mode"], SettingSpec::Both(ValueSpec::Label(_))));
+
+        assert!(parse_plan_config("[[plan]]\nname = \"a\"\n[[plan]]\nname = \"A\"").is_err());
+        assert!(parse_plan_config("[[plan]]\nname = \"a\"\nbasedon = \"balanced\"").is_err());
+        assert!(parse_plan_config("[[plan]]\nname = \"a\"\nsettings = { boost_mode = { ac = 1, ad = 2 } }").is_err());
+    }
+
+    #[test]
+    fn test_resolve_plan_operations() {
+        let plans = parse_plan_config(CONFIG).unwrap();
+        let live = vec![power_plan(BALANCED, "平衡")];
+        assert_eq!(
+            resolve_plan(&plans[0], &live).unwrap(),
+            PlanOperation::Create { base_guid: BALANCED.to_string() }
+        );
+        assert_eq!(
+            resolve_plan(&plans[1], &live).unwrap(),
+            PlanOperation::Keep { plan_guid: BALANCED.to_string() }
+        );
+
+        let renamed = vec![power_plan(BALANCED, "平衡"), power_plan(CUSTOM, "Old Guarded")];
+        assert_eq!(
+            resolve_plan(&plans[0], &renamed).unwrap(),
+            PlanOperation::Rename {
+                plan_guid: CUSTOM.to_string(),
+                from: "Old Guarded".to_string()
+            }
+        );
+
+        let existing = vec![power_plan(BALANCED, "平衡"), power_plan(CUSTOM, "guarded")];
+        assert!(matches!(resolve_plan(&plans[0], &existing).unwrap(), PlanOperation::Rename { .. }));
+
+        let ambiguous = vec![power_plan(BALANCED, "Guarded"), power_plan(CUSTOM, "Guarded")];
+        assert!(resolve_plan(&plans[0], &ambiguous).is_err());
+
+        let mut no_base = plans[0].clone();
+        no_base.based_on = None;
+        assert!(resolve_plan(&no_base, &live).is_err());
+    }
+
+    #[test]
+    fn test_setting_changes_are_idempotent() {
+        let plans = parse_plan_config(CONFIG).unwrap();
+        let mut current = live_settings(2, 100);
+
+        let changes = compute_setting_changes(&plans[0], &current).unwrap();
+        assert_eq!(changes.len(), 2);
+        let boost = changes.iter().find(|c| c.key == "boost_mode").unwrap();
+        assert_eq!((boost.ac_value, boost.dc_value), (0, 0));
+        let max_state = changes.iter().find(|c| c.key == "max_processor_state").unwrap();
+        // 只指定了 AC，DC 保持不变
+        assert_eq!((max_state.ac_value, max_state.dc_value), (99, 100));
+
+        for change in &changes {
+            let setting = current
+                .settings
+                .iter_mut()
+                .find(|s| s.setting_guid == change.setting_guid)
+                .unwrap();
+            setting.ac_value = change.ac_value;
+            setting.dc_value = change.dc_value;
+        }
+        assert!(compute_setting_changes(&plans[0], &current).unwrap().is_empty());
+
+        // 按系统显示的名称和可选项名称写
+        let by_name = compute_setting_changes(&plans[1], &live_settings(0, 100)).unwrap();
+        assert_eq!(by_name[0].ac_value, 2);
+    }
+
+    #[test]
+    fn test_invalid_settings_are_rejected() {
+        let current = live_settings(2, 100);
+        let plan = |settings: &str| {
+            parse_plan_config(&format!("[[plan]]\nname = \"a\"\n[plan.settings]\n{}", settings))
+                .unwrap()
+                .remove(0)
+        };
+
+        assert!(compute_setting_changes(&plan("max_processor_state = 120"), &current).is_err());
+        assert!(compute_setting_changes(&plan("boost_mode = \"turbo\""), &current).is_err());
+        assert!(compute_setting_changes(&plan("cooling_policy = \"active\""), &current).is_err());
+        assert!(compute_setting_changes(&plan("\"不存在的设置\" = 1"), &current).is_err());
+    }
+}
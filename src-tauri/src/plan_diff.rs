@@ -0,0 +1,435 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::plan_snapshot::{plan_snapshot_from_value, PlanSnapshot};
+use crate::PowerPlanUtils::GetPowerPlans::{
+    get_unified_power_scheme, PossibleSetting, PowerSettingValue, SettingType, UnifiedPowerScheme,
+};
+
+// 展开成一维列表的电源计划，便于按 子组/设置 GUID 对齐比较
+#[derive(Debug, Clone)]
+pub struct ComparablePlan {
+    pub guid: String,
+    pub name: String,
+    pub settings: Vec<ComparableSetting>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ComparableSetting {
+    pub subgroup_guid: String,
+    pub subgroup_name: String,
+    pub setting_guid: String,
+    pub setting_name: String,
+    pub ac_value: u32,
+    pub dc_value: u32,
+    // 旧的快照可能没有保存可选值
+    pub possible_values: Option<PossibleSetting>,
+}
+
+impl ComparableSetting {
+    fn same_position(&self, other: &ComparableSetting) -> bool {
+        self.subgroup_guid.eq_ignore_ascii_case(&other.subgroup_guid)
+            && self.setting_guid.eq_ignore_ascii_case(&other.setting_guid)
+    }
+}
+
+impl ComparablePlan {
+    pub fn from_unified(scheme: &UnifiedPowerScheme) -> Self {
+        let settings = scheme
+            .subgroups
+            .iter()
+            .flat_map(|subgroup| {
+                subgroup.settings.iter().map(move |setting| ComparableSetting {
+                    subgroup_guid: format!("{:?}", subgroup.subgroup.uuid),
+                    subgroup_name: subgroup.subgroup.name.clone(),
+                    setting_guid: format!("{:?}", setting.setting.uuid),
+                    setting_name: setting.setting.name.clone(),
+                    ac_value: setting.current_value.ac_value,
+                    dc_value: setting.current_value.dc_value,
+                    possible_values: Some(setting.possible_values.clone()),
+                })
+            })
+            .collect();
+        Self {
+            guid: format!("{:?}", scheme.scheme.uuid),
+            name: scheme.scheme.name.clone(),
+            settings,
+        }
+    }
+
+    pub fn from_snapshot(snapshot: &PlanSnapshot) -> Self {
+        let settings = snapshot
+            .subgroups
+            .iter()
+            .flat_map(|subgroup| {
+                subgroup.settings.iter().map(move |setting| ComparableSetting {
+                    subgroup_guid: subgroup.guid.clone(),
+                    subgroup_name: subgroup.name.clone(),
+                    setting_guid: setting.guid.clone(),
+                    setting_name: setting.name.clone(),
+                    ac_value: setting.ac_value,
+                    dc_value: setting.dc_value,
+                    possible_values: None,
+                })
+            })
+            .collect();
+        Self {
+            guid: snapshot.plan_guid.clone(),
+            name: snapshot.plan_name.clone(),
+            settings,
+        }
+    }
+
+    // 读取保存的 JSON：顶层带 schema_version 的是电源计划快照，
+    // 否则按计划编辑器的格式读取（与 get_power_plans_json_by_scheme_guid_command 的输出结构相同）
+    pub fn from_saved_json(content: &str) -> Result<Self, String> {
+        let value: serde_json::Value =
+            serde_json::from_str(content).map_err(|e| format!("解析电源计划快照失败: {}", e))?;
+        if value.get("schema_version").is_some() {
+            return plan_snapshot_from_value(value).map(|snapshot| Self::from_snapshot(&snapshot));
+        }
+        let saved: SavedScheme = serde_json::from_value(value).map_err(|e| format!("解析电源计划快照失败: {}", e))?;
+        let settings = saved
+            .subgroups
+            .into_iter()
+            .flat_map(|subgroup| {
+                let SavedNamed { uuid: subgroup_guid, name: subgroup_name } = subgroup.subgroup;
+                subgroup.settings.into_iter().map(move |setting| ComparableSetting {
+                    subgroup_guid: subgroup_guid.clone(),
+                    subgroup_name: subgroup_name.clone(),
+                    setting_guid: setting.setting.uuid,
+                    setting_name: setting.setting.name,
+                    ac_value: setting.current_value.ac_value,
+                    dc_value: setting.current_value.dc_value,
+                    possible_values: setting.possible_values,
+                })
+            })
+            .collect();
+        Ok(Self {
+            guid: saved.scheme.uuid,
+            name: saved.scheme.name,
+            settings,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct SavedNamed {
+    uuid: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct SavedSetting {
+    setting: SavedNamed,
+    current_value: PowerSettingValue,
+    #[serde(default)]
+    possible_values: Option<PossibleSetting>,
+}
+
+#[derive(Deserialize)]
+struct SavedSubgroup {
+    subgroup: SavedNamed,
+    #[serde(default)]
+    settings: Vec<SavedSetting>,
+}
+
+#[derive(Deserialize)]
+struct SavedScheme {
+    scheme: SavedNamed,
+    #[serde(default)]
+    subgroups: Vec<SavedSubgroup>,
+}
+
+// 某一侧的取值及其显示文本
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DiffValue {
+    pub ac_value: u32,
+    pub ac_label: String,
+    pub dc_value: u32,
+    pub dc_label: String,
+}
+
+// 一项不同的设置，某一侧没有该设置时对应的值为空
+#[derive(Serialize, Debug, Clone)]
+pub struct SettingDiff {
+    pub subgroup_guid: String,
+    pub subgroup_name: String,
+    pub setting_guid: String,
+    pub setting_name: String,
+    pub unit: Option<String>,
+    pub left: Option<DiffValue>,
+    pub right: Option<DiffValue>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PlanDiff {
+    pub left_guid: String,
+    pub left_name: String,
+    pub right_guid: String,
+    pub right_name: String,
+    pub differences: Vec<SettingDiff>,
+}
+
+// 枚举值显示可选项名称，范围值显示数值和单位
+pub fn value_label(possible: Option<&PossibleSetting>, value: u32) -> String {
+    match possible {
+        Some(possible) => match possible.setting_type {
+            SettingType::Enumerated => possible
+                .data
+                .iter()
+                .find(|d| d.value == value)
+                .map(|d| d.name.clone())
+                .unwrap_or_else(|| value.to_string()),
+            SettingType::Range => match possible.unit.as_deref().filter(|u| !u.is_empty()) {
+                Some(unit) => format!("{} {}", value, unit),
+                None => value.to_string(),
+            },
+        },
+        None => value.to_string(),
+    }
+}
+
+fn diff_value(setting: &ComparableSetting, possible: Option<&PossibleSetting>) -> DiffValue {
+    DiffValue {
+        ac_value: setting.ac_value,
+        ac_label: value_label(possible, setting.ac_value),
+        dc_value: setting.dc_value,
+        dc_label: value_label(possible, setting.dc_value),
+    }
+}
+
+fn setting_diff(
+    left: Option<&ComparableSetting>,
+    right: Option<&ComparableSetting>,
+) -> Option<SettingDiff> {
+    let base = left.or(right)?;
+    // 可选值与计划无关，任取一侧即可
+    let possible = left
+        .and_then(|s| s.possible_values.as_ref())
+        .or_else(|| right.and_then(|s| s.possible_values.as_ref()));
+    // 快照可能没有保存名称，取另一侧的名称
+    let name_of = |pick: fn(&ComparableSetting) -> &String| {
+        [left, right]
+            .into_iter()
+            .flatten()
+            .map(pick)
+            .find(|name| !name.is_empty())
+            .cloned()
+            .unwrap_or_default()
+    };
+    Some(SettingDiff {
+        subgroup_guid: base.subgroup_guid.clone(),
+        subgroup_name: name_of(|s| &s.subgroup_name),
+        setting_guid: base.setting_guid.clone(),
+        setting_name: name_of(|s| &s.setting_name),
+        unit: possible.and_then(|p| p.unit.clone()).filter(|u| !u.is_empty()),
+        left: left.map(|s| diff_value(s, possible)),
+        right: right.map(|s| diff_value(s, possible)),
+    })
+}
+
+// 按左侧的顺序列出 AC 或 DC 值不同的设置，只在右侧存在的设置排在最后
+pub fn diff_plans(left: &ComparablePlan, right: &ComparablePlan) -> PlanDiff {
+    let mut differences: Vec<SettingDiff> = left
+        .settings
+        .iter()
+        .filter_map(|l| {
+            let r = right.settings.iter().find(|r| r.same_position(l));
+            match r {
+                Some(r) if r.ac_value == l.ac_value && r.dc_value == l.dc_value => None,
+                _ => setting_diff(Some(l), r),
+            }
+        })
+        .collect();
+    differences.extend(
+        right
+            .settings
+            .iter()
+            .filter(|r| !left.settings.iter().any(|l| l.same_position(r)))
+            .filter_map(|r| setting_diff(None, Some(r))),
+    );
+
+    PlanDiff {
+        left_guid: left.guid.clone(),
+        left_name: left.name.clone(),
+        right_guid: right.guid.clone(),
+        right_name: right.name.clone(),
+        differences,
+    }
+}
+
+fn load_live_plan(guid: &str) -> Result<ComparablePlan, String> {
+    get_unified_power_scheme(guid)
+        .map(|scheme| ComparablePlan::from_unified(&scheme))
+        .map_err(|e| format!("读取电源计划 {} 失败: {}", guid, e))
+}
+
+// 比较两个电源计划
+#[tauri::command]
+pub async fn diff_power_plans(left_guid: String, right_guid: String) -> Result<PlanDiff, String> {
+    tokio::task::spawn_blocking(move || Ok(diff_plans(&load_live_plan(&left_guid)?, &load_live_plan(&right_guid)?)))
+        .await
+        .map_err(|e| format!("比较电源计划失败: {}", e))?
+}
+
+// 比较当前电源计划与保存的快照（电源计划快照或计划编辑器导出的 JSON），快照在左侧
+#[tauri::command]
+pub async fn diff_power_plan_with_snapshot(guid: String, snapshot_path: String) -> Result<PlanDiff, String> {
+    let content = fs::read_to_string(&snapshot_path).map_err(|e| format!("读取电源计划快照失败: {}", e))?;
+    let snapshot = ComparablePlan::from_saved_json(&content)?;
+    tokio::task::spawn_blocking(move || Ok(diff_plans(&snapshot, &load_live_plan(&guid)?)))
+        .await
+        .map_err(|e| format!("比较电源计划失败: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PowerPlanUtils::GetPowerPlans::SettingData;
+
+    const PROCESSOR: &str = "54533251-82be-4824-96c1-47b60b740d00";
+    const BOOST: &str = "be337238-0d82-4146-a960-4f3749d470c7";
+    const MAX_STATE: &str = "bc5038f7-23e0-4960-96da-33abaf5935ec";
+    const MIN_STATE: &str = "893dee8e-2bef-41e0-89c6-b55d0929964c";
+
+    fn boost_values() -> PossibleSetting {
+        PossibleSetting {
+            setting_type: SettingType::Enumerated,
+            data: vec![
+                SettingData { value: 0, name: "已禁用".to_string() },
+                SettingData { value: 2, name: "高性能".to_string() },
+            ],
+            unit: None,
+        }
+    }
+
+    fn percent_values() -> PossibleSetting {
+        PossibleSetting {
+            setting_type: SettingType::Range,
+            data: vec![
+                SettingData { value: 0, name: "min".to_string() },
+                SettingData { value: 100, name: "max".to_string() },
+            ],
+            unit: Some("%".to_string()),
+        }
+    }
+
+    fn setting(guid: &str, name: &str, ac: u32, dc: u32, possible: Option<PossibleSetting>) -> ComparableSetting {
+        ComparableSetting {
+            subgroup_guid: PROCESSOR.to_string(),
+            subgroup_name: "处理器电源管理".to_string(),
+            setting_guid: guid.to_string(),
+            setting_name: name.to_string(),
+            ac_value: ac,
+            dc_value: dc,
+            possible_values: possible,
+        }
+    }
+
+    fn plan(name: &str, settings: Vec<ComparableSetting>) -> ComparablePlan {
+        ComparablePlan {
+            guid: format!("{}-guid", name),
+            name: name.to_string(),
+            settings,
+        }
+    }
+
+    #[test]
+    fn test_diff_lists_changed_and_missing_settings_with_labels() {
+        let left = plan(
+            "a",
+            vec![
+                setting(BOOST, "处理器性能提升模式", 2, 0, Some(boost_values())),
+                setting(MAX_STATE, "最大处理器状态", 100, 80, Some(percent_values())),
+                setting(MIN_STATE, "最小处理器状态", 5, 5, Some(percent_values())),
+            ],
+        );
+        let right = plan(
+            "b",
+            vec![
+                setting(&BOOST.to_uppercase(), "处理器性能提升模式", 0, 0, Some(boost_values())),
+                setting(MAX_STATE, "最大处理器状态", 100, 80, Some(percent_values())),
+            ],
+        );
+
+        let diff = diff_plans(&left, &right);
+        assert_eq!(diff.differences.len(), 2);
+
+        let boost = &diff.differences[0];
+        assert_eq!(boost.setting_guid, BOOST);
+        assert_eq!(boost.left.as_ref().unwrap().ac_label, "高性能");
+        assert_eq!(boost.right.as_ref().unwrap().ac_label, "已禁用");
+        assert!(boost.unit.is_none());
+
+        let min_state = &diff.differences[1];
+        assert_eq!(min_state.unit.as_deref(), Some("%"));
+        assert_eq!(min_state.left.as_ref().unwrap().dc_label, "5 %");
+        assert!(min_state.right.is_none());
+
+        // 只在右侧存在的设置排在最后
+        let reversed = diff_plans(&right, &left);
+        assert_eq!(reversed.differences.len(), 2);
+        assert!(reversed.differences[1].left.is_none());
+        assert!(diff_plans(&left, &left).differences.is_empty());
+    }
+
+    #[test]
+    fn test_value_label_falls_back_to_number() {
+        assert_eq!(value_label(Some(&boost_values()), 3), "3");
+        assert_eq!(value_label(None, 42), "42");
+    }
+
+    #[test]
+    fn test_saved_json_uses_editor_format() {
+        let content = format!(
+            r#"{{
+                "scheme": {{ "uuid": "381b4222-f694-41f0-9685-ff5bb260df2e", "name": "平衡", "is_active": true }},
+                "subgroups": [{{
+                    "subgroup": {{ "uuid": "{}", "name": "处理器电源管理" }},
+                    "settings": [{{
+                        "setting": {{ "uuid": "{}", "name": "处理器性能提升模式" }},
+                        "current_value": {{ "ac_value": 2, "dc_value": 0 }},
+                        "possible_values": {{
+                            "setting_type": "Enumerated",
+                            "data": [{{ "value": 0, "name": "已禁用" }}, {{ "value": 2, "name": "高性能" }}],
+                            "unit": null
+                        }},
+                        "attributes": 0
+                    }}]
+                }}]
+            }}"#,
+            PROCESSOR, BOOST
+        );
+        let saved = ComparablePlan::from_saved_json(&content).unwrap();
+        assert_eq!(saved.name, "平衡");
+        assert_eq!(saved.settings.len(), 1);
+        assert_eq!(saved.settings[0].subgroup_name, "处理器电源管理");
+
+        let live = plan("live", vec![setting(BOOST, "处理器性能提升模式", 0, 0, None)]);
+        let diff = diff_plans(&saved, &live);
+        assert_eq!(diff.differences[0].right.as_ref().unwrap().ac_label, "已禁用");
+        assert!(ComparablePlan::from_saved_json("{}").is_err());
+
+        let snapshot = format!(
+            r#"{{"schema_version":1,"plan_guid":"x","plan_name":"快照","subgroups":[{{"guid":"{}","settings":[{{"guid":"{}","ac_value":2,"dc_value":0}}]}}]}}"#,
+            PROCESSOR, BOOST
+        );
+        let saved = ComparablePlan::from_saved_json(&snapshot).unwrap();
+        assert_eq!(saved.name, "快照");
+        let live = plan("live", vec![setting(BOOST, "处理器性能提升模式", 0, 0, Some(boost_values()))]);
+        // 快照不保存可选值，标签取自当前计划
+        let diff = diff_plans(&saved, &live);
+        assert_eq!(diff.differences[0].left.as_ref().unwrap().ac_label, "高性能");
+        assert_eq!(diff.differences[0].setting_name, "处理器性能提升模式");
+    }
+
+    #[test]
+    fn test_saved_json_detects_snapshot_by_top_level_key() {
+        // 名称里出现 schema_version 的编辑器 JSON 不能被当成快照
+        let content = r#"{"scheme":{"uuid":"x","name":"\"schema_version\""},"subgroups":[]}"#;
+        let saved = ComparablePlan::from_saved_json(content).unwrap();
+        assert_eq!(saved.name, "\"schema_version\"");
+        assert!(saved.settings.is_empty());
+    }
+}
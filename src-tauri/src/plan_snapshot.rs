@@ -0,0 +1,398 @@
+use chrono::Local;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::plan_diff::ComparablePlan;
+use crate::power_plan::{delete_power_plan, duplicate_power_plan, get_power_plans, rename_power_plan, set_active_plan};
+use crate::PowerPlanUtils::GetPowerPlans::{get_unified_power_scheme, write_value_set, UnifiedPowerScheme};
+
+// 电源计划快照文件的版本
+pub const PLAN_SNAPSHOT_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SnapshotSetting {
+    pub guid: String,
+    // 名称只用于阅读，恢复时按 GUID 匹配
+    #[serde(default)]
+    pub name: String,
+    pub ac_value: u32,
+    pub dc_value: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SnapshotSubgroup {
+    pub guid: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub settings: Vec<SnapshotSetting>,
+}
+
+// 可读、可放进 git 比较的电源计划快照，只保存 GUID 和 AC/DC 值
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlanSnapshot {
+    pub schema_version: u32,
+    pub plan_guid: String,
+    pub plan_name: String,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub subgroups: Vec<SnapshotSubgroup>,
+}
+
+impl PlanSnapshot {
+    pub fn from_unified(scheme: &UnifiedPowerScheme) -> Self {
+        Self {
+            schema_version: PLAN_SNAPSHOT_SCHEMA_VERSION,
+            plan_guid: format!("{:?}", scheme.scheme.uuid),
+            plan_name: scheme.scheme.name.clone(),
+            created_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            subgroups: scheme
+                .subgroups
+                .iter()
+                .map(|subgroup| SnapshotSubgroup {
+                    guid: format!("{:?}", subgroup.subgroup.uuid),
+                    name: subgroup.subgroup.name.clone(),
+                    settings: subgroup
+                        .settings
+                        .iter()
+                        .map(|setting| SnapshotSetting {
+                            guid: format!("{:?}", setting.setting.uuid),
+                            name: setting.setting.name.clone(),
+                            ac_value: setting.current_value.ac_value,
+                            dc_value: setting.current_value.dc_value,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+pub fn parse_plan_snapshot(content: &str) -> Result<PlanSnapshot, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(content).map_err(|e| format!("解析电源计划快照失败: {}", e))?;
+    plan_snapshot_from_value(value)
+}
+
+// 检查版本并转换已经解析好的快照
+pub fn plan_snapshot_from_value(value: serde_json::Value) -> Result<PlanSnapshot, String> {
+    let version = value
+        .get("schema_version")
+        .and_then(|v| v.as_u64())
+        .ok_or("电源计划快照缺少 schema_version 字段")?;
+    if version != PLAN_SNAPSHOT_SCHEMA_VERSION as u64 {
+        return Err(format!(
+            "不支持的电源计划快照版本: {}（当前支持 {}）",
+            version, PLAN_SNAPSHOT_SCHEMA_VERSION
+        ));
+    }
+    serde_json::from_value(value).map_err(|e| format!("解析电源计划快照失败: {}", e))
+}
+
+// 恢复到哪个计划
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RestoreTarget {
+    // 覆盖已有的计划
+    Existing { plan_guid: String },
+    // 复制 base_guid 得到新计划后再写入，name 为空时使用快照中的计划名称
+    Duplicate {
+        base_guid: String,
+        #[serde(default)]
+        name: Option<String>,
+    },
+}
+
+// 需要写入的一项设置
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SnapshotWrite {
+    pub subgroup_guid: String,
+    pub setting_guid: String,
+    pub name: String,
+    pub ac_value: u32,
+    pub dc_value: u32,
+}
+
+// 快照中有、目标机器上没有（或取值不被接受）的设置
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SkippedSetting {
+    pub subgroup_guid: String,
+    pub setting_guid: String,
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Default)]
+pub struct RestorePlan {
+    pub writes: Vec<SnapshotWrite>,
+    pub unchanged: usize,
+    pub skipped: Vec<SkippedSetting>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RestoreReport {
+    pub plan_guid: String,
+    pub written: Vec<SnapshotWrite>,
+    pub unchanged: usize,
+    pub skipped: Vec<SkippedSetting>,
+    pub failed: Vec<SkippedSetting>,
+    // 设置已写入但重新激活计划失败时的原因，写入的值要等下次激活计划才生效
+    pub reactivate_error: Option<String>,
+}
+
+// 对比快照和目标计划，只写入不同的值；目标上不存在的设置和超出可选范围的值单独列出
+pub fn plan_restore(snapshot: &PlanSnapshot, target: &ComparablePlan) -> RestorePlan {
+    let mut plan = RestorePlan::default();
+    for subgroup in &snapshot.subgroups {
+        for setting in &subgroup.settings {
+            let skipped = |reason: String| SkippedSetting {
+                subgroup_guid: subgroup.guid.clone(),
+                setting_guid: setting.guid.clone(),
+                name: setting.name.clone(),
+                reason,
+            };
+            let Some(current) = target.settings.iter().find(|s| {
+                s.subgroup_guid.eq_ignore_ascii_case(&subgroup.guid) && s.setting_guid.eq_ignore_ascii_case(&setting.guid)
+            }) else {
+                plan.skipped.push(skipped("目标计划中不存在该设置".to_string()));
+                continue;
+            };
+
+            if current.ac_value == setting.ac_value && current.dc_value == setting.dc_value {
+                plan.unchanged += 1;
+                continue;
+            }
+            if let Some(possible) = &current.possible_values {
+                let invalid = [setting.ac_value, setting.dc_value]
+                    .into_iter()
+                    .find_map(|value| possible.validate_value(value).err());
+                if let Some(e) = invalid {
+                    plan.skipped.push(skipped(e));
+                    continue;
+                }
+            }
+            plan.writes.push(SnapshotWrite {
+                subgroup_guid: current.subgroup_guid.clone(),
+                setting_guid: current.setting_guid.clone(),
+                name: if setting.name.is_empty() { current.setting_name.clone() } else { setting.name.clone() },
+                ac_value: setting.ac_value,
+                dc_value: setting.dc_value,
+            });
+        }
+    }
+    plan
+}
+
+// 返回要写入的计划和用来比较当前值的计划
+// 新复制的计划与基础计划的设置相同，按基础计划比较，试运行时复制出的计划并不存在
+fn resolve_target(snapshot: &PlanSnapshot, target: &RestoreTarget) -> Result<(String, String), String> {
+    match target {
+        RestoreTarget::Existing { plan_guid } => Ok((plan_guid.clone(), plan_guid.clone())),
+        RestoreTarget::Duplicate { base_guid, name } => {
+            let new_guid = duplicate_power_plan(base_guid)?;
+            let name = name.as_deref().filter(|n| !n.trim().is_empty()).unwrap_or(&snapshot.plan_name);
+            if let Err(e) = rename_power_plan(&new_guid, name) {
+                discard_duplicate(&new_guid);
+                return Err(e);
+            }
+            info!("已复制电源计划 {} 为 {}（{}）", base_guid, name, new_guid);
+            Ok((new_guid, base_guid.clone()))
+        }
+    }
+}
+
+// 复制出的计划在写入前出错时删除，不留下没有恢复内容的副本
+fn discard_duplicate(guid: &str) {
+    match delete_power_plan(guid) {
+        Ok(()) => info!("已删除未完成的电源计划 {}", guid),
+        Err(e) => warn!("删除未完成的电源计划 {} 失败: {}", guid, e),
+    }
+}
+
+pub fn restore_snapshot(snapshot: &PlanSnapshot, target: &RestoreTarget) -> Result<RestoreReport, String> {
+    let (plan_guid, compare_guid) = resolve_target(snapshot, target)?;
+    let scheme = match get_unified_power_scheme(&compare_guid) {
+        Ok(scheme) => scheme,
+        Err(e) => {
+            if let RestoreTarget::Duplicate { .. } = target {
+                discard_duplicate(&plan_guid);
+            }
+            return Err(format!("读取电源计划 {} 失败: {}", compare_guid, e));
+        }
+    };
+    let restore = plan_restore(snapshot, &ComparablePlan::from_unified(&scheme));
+
+    let mut written = Vec::new();
+    let mut failed = Vec::new();
+    for write in restore.writes {
+        match write_value_set(&plan_guid, &write.subgroup_guid, &write.setting_guid, write.ac_value, write.dc_value) {
+            Ok(()) => written.push(write),
+            Err(e) => {
+                error!("恢复设置 {} 失败: {}", write.name, e);
+                failed.push(SkippedSetting {
+                    subgroup_guid: write.subgroup_guid,
+                    setting_guid: write.setting_guid,
+                    name: write.name,
+                    reason: e,
+                });
+            }
+        }
+    }
+
+    // 写入的是正在使用的计划时重新激活，使新值立即生效；设置已经写入，失败时只记录在报告中
+    let reactivate_error = if written.is_empty() {
+        None
+    } else {
+        match get_power_plans() {
+            Ok(plans) if plans.iter().any(|p| p.is_active && p.guid.eq_ignore_ascii_case(&plan_guid)) => {
+                set_active_plan(&plan_guid).err()
+            }
+            Ok(_) => None,
+            Err(e) => Some(e),
+        }
+    };
+    if let Some(e) = &reactivate_error {
+        error!("重新激活电源计划 {} 失败: {}", plan_guid, e);
+    }
+
+    info!(
+        "电源计划快照已恢复到 {}: 写入 {} 项，未变化 {} 项，跳过 {} 项，失败 {} 项",
+        plan_guid,
+        written.len(),
+        restore.unchanged,
+        restore.skipped.len(),
+        failed.len()
+    );
+    Ok(RestoreReport {
+        plan_guid,
+        written,
+        unchanged: restore.unchanged,
+        skipped: restore.skipped,
+        failed,
+        reactivate_error,
+    })
+}
+
+#[tauri::command]
+pub async fn export_plan_snapshot(guid: String, file_path: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        let scheme = get_unified_power_scheme(&guid)?;
+        let snapshot = PlanSnapshot::from_unified(&scheme);
+        let content = serde_json::to_string_pretty(&snapshot).map_err(|e| format!("序列化电源计划快照失败: {}", e))?;
+        fs::write(&file_path, content).map_err(|e| format!("保存电源计划快照失败: {}", e))?;
+        info!("已导出电源计划 {} 的快照到 {}", snapshot.plan_name, file_path);
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("导出电源计划快照失败: {}", e))?
+}
+
+#[tauri::command]
+pub async fn restore_plan_snapshot(file_path: String, target: RestoreTarget) -> Result<RestoreReport, String> {
+    let content = fs::read_to_string(&file_path).map_err(|e| format!("读取电源计划快照失败: {}", e))?;
+    let snapshot = parse_plan_snapshot(&content)?;
+    tokio::task::spawn_blocking(move || restore_snapshot(&snapshot, &target))
+        .await
+        .map_err(|e| format!("恢复电源计划快照失败: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan_diff::ComparableSetting;
+    use crate::PowerPlanUtils::GetPowerPlans::{PossibleSetting, SettingData, SettingType};
+
+    const PROCESSOR: &str = "54533251-82BE-4824-96C1-47B60B740D00";
+    const BOOST: &str = "BE337238-0D82-4146-A960-4F3749D470C7";
+    const MAX_STATE: &str = "BC5038F7-23E0-4960-96DA-33ABAF5935EC";
+    const VENDOR_ONLY: &str = "0CC5B647-C1DF-4637-891A-DEC35C318583";
+
+    fn snapshot_json() -> String {
+        format!(
+            r#"{{
+                "schema_version": 1,
+                "plan_guid": "381B4222-F694-41F0-9685-FF5BB260DF2E",
+                "plan_name": "平衡",
+                "created_at": "2026-10-18 09:00:00",
+                "subgroups": [{{
+                    "guid": "{}",
+                    "name": "处理器电源管理",
+                    "settings": [
+                        {{ "guid": "{}", "name": "处理器性能提升模式", "ac_value": 0, "dc_value": 0 }},
+                        {{ "guid": "{}", "name": "最大处理器状态", "ac_value": 99, "dc_value": 80 }},
+                        {{ "guid": "{}", "name": "厂商设置", "ac_value": 1, "dc_value": 1 }}
+                    ]
+                }}]
+            }}"#,
+            PROCESSOR.to_lowercase(),
+            BOOST.to_lowercase(),
+            MAX_STATE,
+            VENDOR_ONLY
+        )
+    }
+
+    fn target(max_state_possible: PossibleSetting) -> ComparablePlan {
+        let setting = |guid: &str, ac: u32, dc: u32, possible: Option<PossibleSetting>| ComparableSetting {
+            subgroup_guid: PROCESSOR.to_string(),
+            subgroup_name: "处理器电源管理".to_string(),
+            setting_guid: guid.to_string(),
+            setting_name: String::new(),
+            ac_value: ac,
+            dc_value: dc,
+            possible_values: possible,
+        };
+        ComparablePlan {
+            guid: "target".to_string(),
+            name: "目标".to_string(),
+            settings: vec![setting(BOOST, 2, 2, None), setting(MAX_STATE, 99, 80, Some(max_state_possible))],
+        }
+    }
+
+    fn percent(max: u32) -> PossibleSetting {
+        PossibleSetting {
+            setting_type: SettingType::Range,
+            data: vec![
+                SettingData { value: 0, name: "min".to_string() },
+                SettingData { value: max, name: "max".to_string() },
+            ],
+            unit: Some("%".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_restore_writes_only_changed_and_reports_missing() {
+        let snapshot = parse_plan_snapshot(&snapshot_json()).unwrap();
+        let restore = plan_restore(&snapshot, &target(percent(100)));
+
+        assert_eq!(restore.writes.len(), 1);
+        assert_eq!(restore.writes[0].setting_guid, BOOST);
+        assert_eq!((restore.writes[0].ac_value, restore.writes[0].dc_value), (0, 0));
+        assert_eq!(restore.unchanged, 1);
+        assert_eq!(restore.skipped.len(), 1);
+        assert_eq!(restore.skipped[0].setting_guid, VENDOR_ONLY);
+    }
+
+    #[test]
+    fn test_restore_skips_values_outside_target_range() {
+        let mut snapshot = parse_plan_snapshot(&snapshot_json()).unwrap();
+        snapshot.subgroups[0].settings[1].ac_value = 100;
+        let restore = plan_restore(&snapshot, &target(percent(99)));
+        assert_eq!(restore.writes.len(), 1);
+        assert_eq!(restore.skipped.len(), 2);
+        assert!(restore.skipped[0].reason.contains("超出允许范围"));
+    }
+
+    #[test]
+    fn test_snapshot_round_trip_and_version() {
+        let snapshot = parse_plan_snapshot(&snapshot_json()).unwrap();
+        let content = serde_json::to_string_pretty(&snapshot).unwrap();
+        let parsed = parse_plan_snapshot(&content).unwrap();
+        assert_eq!(parsed.subgroups[0].settings.len(), 3);
+        assert_eq!(parsed.plan_name, "平衡");
+
+        assert!(parse_plan_snapshot(r#"{"schema_version":2,"plan_guid":"x","plan_name":"y"}"#).is_err());
+        assert!(parse_plan_snapshot(r#"{"plan_guid":"x","plan_name":"y"}"#).is_err());
+    }
+}
@@ -1,13 +1,18 @@
-use encoding_rs::GBK;
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 // use uuid::Uuid;
-use std::os::windows::process::CommandExt;
 
 use crate::PowerPlanUtils::PowerPlanController::PowerPlanController;
+use crate::PowerPlanUtils::PowercfgOutput::{extract_guid, find_guid, run_powercfg, GUID_TEXT_LEN};
+use crate::dry_run;
 
+// 缓存的当前计划多久后重新读取，在系统设置里切换计划时最多延迟这么久才能看到
+const ACTIVE_PLAN_CACHE_TTL: Duration = Duration::from_secs(5);
 
-const CREATE_NO_WINDOW: u32 = 0x08000000;
+// 最近一次读取到的当前计划及读取时间，程序自己切换计划后清空
+static ACTIVE_PLAN_CACHE: Lazy<Mutex<Option<(Instant, Option<String>)>>> = Lazy::new(|| Mutex::new(None));
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PowerPlan {
@@ -60,80 +65,68 @@ pub fn get_power_plans() -> Result<Vec<PowerPlan>, String> {
     Ok(plans)
 }
 
+// 当前正在使用的电源计划，读取失败时返回 None
+pub fn active_plan_guid() -> Option<String> {
+    get_power_plans()
+        .ok()
+        .and_then(|plans| plans.into_iter().find(|p| p.is_active).map(|p| p.guid))
+}
+
+// 带缓存的当前计划，供监控循环、规则引擎这类每个周期都要读取的地方使用
+// 枚举电源计划需要调用系统接口，缓存过期或程序切换过计划后才重新读取
+pub fn cached_active_plan_guid() -> Option<String> {
+    if let Ok(cache) = ACTIVE_PLAN_CACHE.lock() {
+        if let Some((read_at, guid)) = cache.as_ref() {
+            if read_at.elapsed() < ACTIVE_PLAN_CACHE_TTL {
+                return guid.clone();
+            }
+        }
+    }
+    let guid = active_plan_guid();
+    if let Ok(mut cache) = ACTIVE_PLAN_CACHE.lock() {
+        *cache = Some((Instant::now(), guid.clone()));
+    }
+    guid
+}
+
 pub fn check_if_scheme_is_valid(guid: &str) -> bool {
     PowerPlanController::check_if_scheme_is_valid(guid)
 }
 pub fn get_power_plans_json_by_scheme_guid(guid: &str)->Result<String,String>{
     PowerPlanController::get_power_plans_json_by_scheme_guid(guid)
 }
+// 把 powercfg /query 的输出转换为与上面相同的 JSON
+pub fn parse_powercfg_query_json(output: &str) -> Result<String, String> {
+    PowerPlanController::parse_powercfg_query_json(output)
+}
 
 pub fn set_active_plan(guid: &str) -> Result<(), String> {
+    if dry_run::skip(&format!("powercfg /setactive {}", guid)) {
+        return Ok(());
+    }
     //这种不需要处理输出的，直接使用powercfg设置
-    let output = Command::new("powercfg")
-        .args(["/setactive", guid])
-        .creation_flags(CREATE_NO_WINDOW)
-        .output()
-        .map_err(|e| format!("设置活动计划失败: {}", e))?;
-
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("设置活动计划失败: {}", error));
+    run_powercfg(&["/setactive", guid]).map_err(|e| format!("设置活动计划失败: {}", e))?;
+    // 下次读取当前计划时重新从系统获取
+    if let Ok(mut cache) = ACTIVE_PLAN_CACHE.lock() {
+        *cache = None;
     }
-
     Ok(())
 }
 
+// 按 GUID 的格式识别计划行，表头和 "GUID:" 之类的标签在各语言下都不同
 fn parse_power_plans(output: &str) -> Result<Vec<PowerPlan>, String> {
-    let mut plans = Vec::new();
-    let mut found_header = false;
-
-    // println!("开始解析行:");
-    for line in output.lines() {
-        // println!("处理行: {}", line);
-
-        // 跳过空行
-        if line.trim().is_empty() {
-            // println!("跳过空行");
-            continue;
-        }
-
-        // 检查是否找到头部分隔线
-        if line.contains("-----------------------------------") {
-            // println!("找到分隔线");
-            found_header = true;
-            continue;
-        }
-
-        // 解析计划信息
-        if found_header && line.contains("GUID:") {
-            // println!("解析GUID行");
-            if let Some(plan) = parse_plan_line(line) {
-                // println!("成功解析计划: {} ({})", plan.name, plan.guid);
-                plans.push(plan);
-            }
-        }
-    }
-
-    Ok(plans)
+    Ok(output.lines().filter_map(parse_plan_line).collect())
 }
 
 fn parse_plan_line(line: &str) -> Option<PowerPlan> {
     let line = line.trim();
 
     // 查找 GUID 部分
-    let guid_start = line.find("GUID:")? + 5;
-    let remaining = &line[guid_start..];
-
-    // 使用多个空格作为分隔符来分割 GUID 和名称部分
-    let parts: Vec<&str> = remaining.split("  ").filter(|s| !s.is_empty()).collect();
-    if parts.is_empty() {
-        return None;
-    }
-
-    let guid = parts[0].trim().to_string();
+    let (guid_start, guid) = find_guid(line)?;
+    let guid = guid.to_string();
 
     // 获取名称部分（可能包含星号）
-    let name_part = parts.get(1).map(|s| *s).unwrap_or("").trim();
+    let name_part = line[guid_start + GUID_TEXT_LEN..].trim();
     let is_active = name_part.ends_with('*');
 
     // 处理名称，移除括号和星号
@@ -157,40 +150,22 @@ fn parse_plan_line(line: &str) -> Option<PowerPlan> {
     })
 }
 
+// 试运行时复制、导入不会真正创建计划，返回一个占位 GUID 让调用方照常继续
+// 之后对该 GUID 的写入、改名等操作同样只会被记录
+fn dry_run_placeholder_guid() -> String {
+    uuid::Uuid::new_v4().to_string().to_uppercase()
+}
+
 // 复制电源计划
 pub fn duplicate_power_plan(guid: &str) -> Result<String, String> {
-    let output = Command::new("powercfg")
-        .args(["/duplicatescheme", guid])
-        .creation_flags(CREATE_NO_WINDOW)
-        .output()
-        .map_err(|e| format!("执行命令失败: {}", e))?;
-
-    if !output.status.success() {
-        let (cow, _encoding_used, had_errors) = GBK.decode(&output.stderr);
-        if had_errors {
-            return Err("GBK 解码失败".to_string());
-        }
-        return Err(cow.into_owned());
+    let placeholder = dry_run_placeholder_guid();
+    if dry_run::skip(&format!("powercfg /duplicatescheme {}（新计划以 {} 代替）", guid, placeholder)) {
+        return Ok(placeholder);
     }
+    let output = run_powercfg(&["/duplicatescheme", guid])?;
 
     // 从输出中提取新的 GUID
-    let (cow, _encoding_used, had_errors) = GBK.decode(&output.stdout);
-    if had_errors {
-        return Err("GBK 解码失败".to_string());
-    }
-    let output_str = cow.into_owned();
-
-    // 尝试从输出中提取 GUID
-    if let Some(guid) = output_str
-        .lines()
-        .find(|line| line.contains("GUID:"))
-        .and_then(|line| line.split("GUID:").nth(1))
-        .map(|s| s.trim().to_string())
-    {
-        Ok(guid)
-    } else {
-        Err("无法从输出中提取 GUID".to_string())
-    }
+    extract_guid(&output)
 }
 
 // 删除电源计划
@@ -200,39 +175,21 @@ pub fn delete_power_plan(guid: &str) -> Result<(), String> {
     if plans.iter().any(|plan| plan.guid == guid && plan.is_active) {
         return Err("不能删除当前活动的电源计划".to_string());
     }
-
-    let output = Command::new("powercfg")
-        .args(["/delete", guid])
-        .creation_flags(CREATE_NO_WINDOW)
-        .output()
-        .map_err(|e| format!("执行命令失败: {}", e))?;
-
-    if !output.status.success() {
-        let (cow, _encoding_used, had_errors) = GBK.decode(&output.stderr);
-        if had_errors {
-            return Err("GBK 解码失败".to_string());
-        }
-        return Err(cow.into_owned());
+    if dry_run::skip(&format!("powercfg /delete {}", guid)) {
+        return Ok(());
     }
 
+    run_powercfg(&["/delete", guid])?;
+
     Ok(())
 }
 
 // 更改计划名称
 pub fn rename_power_plan(guid: &str, new_name: &str) -> Result<(), String> {
-    let output = Command::new("powercfg")
-        .args(["/changename", guid, new_name])
-        .creation_flags(CREATE_NO_WINDOW)
-        .output()
-        .map_err(|e| format!("执行命令失败: {}", e))?;
-
-    if !output.status.success() {
-        let (cow, _encoding_used, had_errors) = GBK.decode(&output.stderr);
-        if had_errors {
-            return Err("GBK 解码失败".to_string());
-        }
-        return Err(cow.into_owned());
+    if dry_run::skip(&format!("powercfg /changename {} {}", guid, new_name)) {
+        return Ok(());
     }
+    run_powercfg(&["/changename", guid, new_name])?;
 
     Ok(())
 }
@@ -257,57 +214,21 @@ pub async fn rename_power_plan_command(guid: String, new_name: String) -> Result
 
 // 导出电源计划
 pub fn export_power_plan(guid: &str, file_path: &str) -> Result<(), String> {
-    let output = Command::new("powercfg")
-        .args(["/export", file_path, guid])
-        .creation_flags(CREATE_NO_WINDOW)
-        .output()
-        .map_err(|e| format!("执行导出命令失败: {}", e))?;
-
-    if !output.status.success() {
-        let (cow, _encoding_used, had_errors) = GBK.decode(&output.stderr);
-        if had_errors {
-            return Err("GBK 解码失败".to_string());
-        }
-        return Err(cow.into_owned());
-    }
+    run_powercfg(&["/export", file_path, guid])?;
 
     Ok(())
 }
 
 // 导入电源计划
 pub fn import_power_plan(file_path: &str) -> Result<String, String> {
-    let output = Command::new("powercfg")
-        .args(["/import", file_path])
-        .creation_flags(CREATE_NO_WINDOW)
-        .output()
-        .map_err(|e| format!("执行导入命令失败: {}", e))?;
-
-    if !output.status.success() {
-        let (cow, _encoding_used, had_errors) = GBK.decode(&output.stderr);
-        if had_errors {
-            return Err("GBK 解码失败".to_string());
-        }
-        return Err(cow.into_owned());
+    let placeholder = dry_run_placeholder_guid();
+    if dry_run::skip(&format!("powercfg /import {}（新计划以 {} 代替）", file_path, placeholder)) {
+        return Ok(placeholder);
     }
+    let output = run_powercfg(&["/import", file_path])?;
 
     // 从输出中提取新的 GUID
-    let (cow, _encoding_used, had_errors) = GBK.decode(&output.stdout);
-    if had_errors {
-        return Err("GBK 解码失败".to_string());
-    }
-    let output_str = cow.into_owned();
-
-    // 尝试从输出中提取 GUID
-    if let Some(guid) = output_str
-        .lines()
-        .find(|line| line.contains("GUID:"))
-        .and_then(|line| line.split("GUID:").nth(1))
-        .map(|s| s.trim().to_string())
-    {
-        Ok(guid)
-    } else {
-        Err("无法从输出中提取 GUID".to_string())
-    }
+    extract_guid(&output)
 }
 
 #[tauri::command]
@@ -339,19 +260,23 @@ mod tests {
         assert_eq!(plans[1].is_active, true);
         assert_eq!(plans[1].name, "我的自定义计划 1");
     }
-}
-
-// 修改执行命令的函数
-fn execute_powercfg(args: &[&str]) -> Result<String, String> {
-    let output = std::process::Command::new("powercfg")
-        .args(args)
-        .creation_flags(CREATE_NO_WINDOW) // 添加这个标志来隐藏窗口
-        .output()
-        .map_err(|e| format!("执行命令失败: {}", e))?;
 
-    if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
-    } else {
-        Err(String::from_utf8_lossy(&output.stderr).to_string())
+    #[test]
+    fn test_parse_power_plans_in_each_locale() {
+        use crate::PowerPlanUtils::PowercfgOutput::decode_with_code_page;
+
+        // 原始字节按各语言控制台的 OEM 代码页保存
+        let outputs: [(&[u8], u32, [&str; 3]); 4] = [
+            (include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/powercfg/list_en.txt")), 437, ["Balanced", "High performance", "Power saver"]),
+            (include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/powercfg/list_de.txt")), 850, ["Ausbalanciert", "Höchstleistung", "Energiesparmodus"]),
+            (include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/powercfg/list_ja.txt")), 932, ["バランス", "高パフォーマンス", "省電力"]),
+            (include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/powercfg/list_zh.txt")), 936, ["平衡", "高性能", "节能"]),
+        ];
+        for (bytes, code_page, names) in outputs {
+            let plans = parse_power_plans(&decode_with_code_page(bytes, code_page)).unwrap();
+            assert_eq!(plans.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), names);
+            assert_eq!(plans[0].guid, "381b4222-f694-41f0-9685-ff5bb260df2e");
+            assert!(plans[0].is_active && !plans[1].is_active && !plans[2].is_active);
+        }
     }
 }
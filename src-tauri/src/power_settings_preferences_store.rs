@@ -35,7 +35,7 @@ impl PowerSettingsPreferences {
         }
     }
 
-    fn save(&self) -> Result<(), String> {
+    pub(crate) fn save(&self) -> Result<(), String> {
         let path = Self::get_preferences_path();
         let json = serde_json::to_string_pretty(&self).map_err(|e| e.to_string())?;
         fs::write(path, json).map_err(|e| e.to_string())
@@ -0,0 +1,259 @@
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+use tokio::time::{interval as tokio_interval, Duration};
+
+// 检查供电方式的间隔
+const WATCH_INTERVAL_SECONDS: u64 = 3;
+
+// 最近一次读取到的供电方式，由 start_power_source_watcher 更新
+static CURRENT_POWER_SOURCE: Lazy<Mutex<Option<PowerSource>>> = Lazy::new(|| Mutex::new(None));
+
+// 当前供电方式
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerSource {
+    Ac,
+    Battery,
+}
+
+impl PowerSource {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            PowerSource::Ac => "交流电源",
+            PowerSource::Battery => "电池",
+        }
+    }
+}
+
+// 某种供电方式下覆盖的监控设置，为空则使用全局设置
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PowerSourceProfile {
+    #[serde(default)]
+    pub frequency_threshold: Option<f64>,
+    #[serde(default)]
+    pub refresh_interval: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct PowerSourceProfiles {
+    #[serde(default)]
+    pub ac: PowerSourceProfile,
+    #[serde(default)]
+    pub battery: PowerSourceProfile,
+}
+
+impl PowerSourceProfiles {
+    pub fn validate(&self) -> Result<(), String> {
+        for (source, profile) in [(PowerSource::Ac, &self.ac), (PowerSource::Battery, &self.battery)] {
+            if profile.frequency_threshold.is_some_and(|t| t <= 0.0) {
+                return Err(format!("{}下的频率阈值必须大于0", source.display_name()));
+            }
+            if profile.refresh_interval == Some(0) {
+                return Err(format!("{}下的刷新间隔必须大于0", source.display_name()));
+            }
+        }
+        Ok(())
+    }
+
+    fn profile(&self, source: Option<PowerSource>) -> Option<&PowerSourceProfile> {
+        match source? {
+            PowerSource::Ac => Some(&self.ac),
+            PowerSource::Battery => Some(&self.battery),
+        }
+    }
+
+    // 供电方式未知或未覆盖时使用全局值
+    pub fn frequency_threshold(&self, source: Option<PowerSource>, global: f64) -> f64 {
+        self.profile(source)
+            .and_then(|p| p.frequency_threshold)
+            .unwrap_or(global)
+    }
+
+    pub fn refresh_interval(&self, source: Option<PowerSource>, global: u64) -> u64 {
+        self.profile(source).and_then(|p| p.refresh_interval).unwrap_or(global)
+    }
+}
+
+// 从 /sys/class/power_supply 这样的目录判断供电方式
+// 有在线的外接电源（Mains/USB）即为交流电源；只有电池时按电池的充放电状态判断
+pub fn read_sysfs_power_source(root: &Path) -> Option<PowerSource> {
+    let read = |dir: &Path, name: &str| fs::read_to_string(dir.join(name)).ok().map(|v| v.trim().to_string());
+
+    let mut has_external_supply = false;
+    let mut battery_status = None;
+    for entry in fs::read_dir(root).ok()?.flatten() {
+        let dir = entry.path();
+        match read(&dir, "type").as_deref() {
+            Some("Mains") | Some("USB") => {
+                has_external_supply = true;
+                if read(&dir, "online").as_deref() == Some("1") {
+                    return Some(PowerSource::Ac);
+                }
+            }
+            Some("Battery") => {
+                // 部分设备会在 power_supply 下暴露外设（如鼠标）的电池，只看系统电池
+                if read(&dir, "scope").as_deref() == Some("Device") {
+                    continue;
+                }
+                if battery_status.is_none() || read(&dir, "status").as_deref() == Some("Discharging") {
+                    battery_status = read(&dir, "status");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match battery_status.as_deref() {
+        Some("Discharging") => Some(PowerSource::Battery),
+        Some("Charging") | Some("Full") | Some("Not charging") => Some(PowerSource::Ac),
+        // 有外接电源但不在线，且存在电池
+        Some(_) if has_external_supply => Some(PowerSource::Battery),
+        _ => None,
+    }
+}
+
+#[cfg(windows)]
+fn read_platform_power_source() -> Option<PowerSource> {
+    use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status = SYSTEM_POWER_STATUS::default();
+    unsafe { GetSystemPowerStatus(&mut status) }.ok()?;
+    match status.ACLineStatus {
+        0 => Some(PowerSource::Battery),
+        1 => Some(PowerSource::Ac),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_platform_power_source() -> Option<PowerSource> {
+    read_sysfs_power_source(Path::new("/sys/class/power_supply"))
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn read_platform_power_source() -> Option<PowerSource> {
+    None
+}
+
+// 直接读取当前供电方式，无法判断时返回 None（例如没有电池的台式机）
+pub fn get_power_source() -> Option<PowerSource> {
+    read_platform_power_source()
+}
+
+// 读取最近一次检查到的供电方式，监控循环每个周期都会调用，避免重复读取系统状态
+pub fn current_power_source() -> Option<PowerSource> {
+    CURRENT_POWER_SOURCE.lock().ok().and_then(|current| *current)
+}
+
+// 定期检查供电方式，变化时发送 power-source-changed 事件
+pub fn start_power_source_watcher(app: AppHandle) {
+    let initial = get_power_source();
+    if let Ok(mut current) = CURRENT_POWER_SOURCE.lock() {
+        *current = initial;
+    }
+    info!("当前供电方式: {}", initial.map(|s| s.display_name()).unwrap_or("未知"));
+
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio_interval(Duration::from_secs(WATCH_INTERVAL_SECONDS));
+        loop {
+            interval.tick().await;
+            let source = get_power_source();
+            let previous = match CURRENT_POWER_SOURCE.lock() {
+                Ok(mut current) => std::mem::replace(&mut *current, source),
+                Err(e) => {
+                    warn!("获取供电方式锁失败: {}", e);
+                    continue;
+                }
+            };
+            if previous == source {
+                continue;
+            }
+            info!(
+                "供电方式变化: {} -> {}",
+                previous.map(|s| s.display_name()).unwrap_or("未知"),
+                source.map(|s| s.display_name()).unwrap_or("未知")
+            );
+            let _ = app.emit(
+                "power-source-changed",
+                json!({
+                    "source": source,
+                    "previous": previous,
+                }),
+            );
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_current_power_source() -> Result<Option<PowerSource>, String> {
+    Ok(current_power_source())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_tree;
+
+    fn fixture(supplies: &[(&str, &[(&str, &str)])]) -> std::path::PathBuf {
+        temp_tree("power_supply", supplies)
+    }
+
+    #[test]
+    fn test_sysfs_mains_online() {
+        let root = fixture(&[
+            ("AC", &[("type", "Mains"), ("online", "1")]),
+            ("BAT0", &[("type", "Battery"), ("status", "Charging")]),
+        ]);
+        assert_eq!(read_sysfs_power_source(&root), Some(PowerSource::Ac));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_sysfs_on_battery() {
+        let root = fixture(&[
+            ("ADP1", &[("type", "Mains"), ("online", "0")]),
+            ("BAT0", &[("type", "Battery"), ("status", "Discharging")]),
+            ("hidpp_battery_0", &[("type", "Battery"), ("scope", "Device"), ("status", "Charging")]),
+        ]);
+        assert_eq!(read_sysfs_power_source(&root), Some(PowerSource::Battery));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_sysfs_without_supplies() {
+        let root = fixture(&[]);
+        assert_eq!(read_sysfs_power_source(&root), None);
+        assert_eq!(read_sysfs_power_source(&root.join("missing")), None);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_profiles_fall_back_to_global() {
+        let profiles = PowerSourceProfiles {
+            battery: PowerSourceProfile {
+                frequency_threshold: Some(2.0),
+                refresh_interval: Some(5000),
+            },
+            ..Default::default()
+        };
+        assert_eq!(profiles.frequency_threshold(Some(PowerSource::Battery), 3.0), 2.0);
+        assert_eq!(profiles.frequency_threshold(Some(PowerSource::Ac), 3.0), 3.0);
+        assert_eq!(profiles.refresh_interval(None, 1000), 1000);
+        assert_eq!(profiles.refresh_interval(Some(PowerSource::Battery), 1000), 5000);
+        assert!(PowerSourceProfiles {
+            ac: PowerSourceProfile {
+                refresh_interval: Some(0),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+}
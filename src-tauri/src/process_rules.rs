@@ -0,0 +1,463 @@
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use tauri::{AppHandle, Emitter};
+use tokio::time::{interval as tokio_interval, Duration};
+
+use crate::notification::send_notification;
+use crate::power_plan::{active_plan_guid, check_if_scheme_is_valid, set_active_plan};
+use crate::versioned_file::VersionedFile;
+
+// process_rules.json 的文件版本
+pub const PROCESS_RULES_SCHEMA_VERSION: u32 = 1;
+
+static PROCESS_RULES_FILE: VersionedFile =
+    VersionedFile::new("process_rules.json", "进程规则", PROCESS_RULES_SCHEMA_VERSION);
+
+// 检查进程列表的间隔
+const WATCH_INTERVAL_SECONDS: u64 = 3;
+
+// 匹配的进程运行期间是否屏蔽频率提醒和触发动作，监控循环每个周期读取
+static SUPPRESS_ALERTS: AtomicBool = AtomicBool::new(false);
+static SUPPRESS_ACTIONS: AtomicBool = AtomicBool::new(false);
+
+static PROCESS_RULE_STATUS: Lazy<Mutex<ProcessRuleStatus>> = Lazy::new(|| Mutex::new(ProcessRuleStatus::default()));
+
+// 复用同一个 System，只补充读取新进程的路径
+static PROCESS_SYSTEM: Lazy<Mutex<System>> = Lazy::new(|| Mutex::new(System::new()));
+
+// 按进程切换电源计划和屏蔽提醒的规则
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProcessRule {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+    // 可执行文件名或路径，支持 * 和 ? 通配符，不区分大小写；包含路径分隔符时匹配完整路径
+    pub patterns: Vec<String>,
+    // 进程运行期间切换到的电源计划，为空则不切换
+    #[serde(default)]
+    pub plan_guid: Option<String>,
+    #[serde(default)]
+    pub suppress_alerts: bool,
+    #[serde(default)]
+    pub suppress_actions: bool,
+}
+
+// 进程列表中的一项
+#[derive(Clone, Debug)]
+pub struct ProcessInfo {
+    pub name: String,
+    pub exe: Option<String>,
+}
+
+// 当前匹配的结果
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ProcessRuleStatus {
+    pub active_rules: Vec<String>,
+    // 第一条匹配且设置了电源计划的规则决定要切换的计划
+    pub plan_guid: Option<String>,
+    pub suppress_alerts: bool,
+    pub suppress_actions: bool,
+}
+
+// 简单的通配符匹配，* 匹配任意多个字符，? 匹配一个字符
+fn wildcard_match(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            // 回到上一个 *，让它多匹配一个字符
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn normalize(value: &str) -> Vec<char> {
+    value.trim().to_lowercase().replace('\\', "/").chars().collect()
+}
+
+impl ProcessRule {
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        self.patterns.iter().any(|pattern| {
+            let pattern_chars = normalize(pattern);
+            if pattern_chars.contains(&'/') {
+                process
+                    .exe
+                    .as_deref()
+                    .is_some_and(|exe| wildcard_match(&pattern_chars, &normalize(exe)))
+            } else {
+                wildcard_match(&pattern_chars, &normalize(&process.name))
+            }
+        })
+    }
+
+    pub fn validate(&self, plan_exists: &dyn Fn(&str) -> bool) -> Result<(), String> {
+        if self.patterns.iter().all(|p| p.trim().is_empty()) {
+            return Err("至少需要一个进程名或路径".to_string());
+        }
+        if let Some(plan_guid) = &self.plan_guid {
+            if !plan_exists(plan_guid) {
+                return Err(format!("电源计划不存在: {}", plan_guid));
+            }
+        }
+        if self.plan_guid.is_none() && !self.suppress_alerts && !self.suppress_actions {
+            return Err("没有设置要切换的电源计划，也没有屏蔽提醒或触发动作".to_string());
+        }
+        Ok(())
+    }
+}
+
+pub fn validate_process_rules(rules: &[ProcessRule], plan_exists: &dyn Fn(&str) -> bool) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for rule in rules {
+        if !seen.insert(rule.id.as_str()) {
+            return Err(format!("进程规则 {} 的 id 重复", rule.name));
+        }
+        rule.validate(plan_exists)
+            .map_err(|e| format!("进程规则 {} 无效: {}", rule.name, e))?;
+    }
+    Ok(())
+}
+
+// 根据进程列表计算当前生效的规则
+pub fn evaluate_process_rules(rules: &[ProcessRule], processes: &[ProcessInfo]) -> ProcessRuleStatus {
+    let mut status = ProcessRuleStatus::default();
+    for rule in rules.iter().filter(|r| r.enabled) {
+        if !processes.iter().any(|p| rule.matches(p)) {
+            continue;
+        }
+        status.active_rules.push(rule.id.clone());
+        if status.plan_guid.is_none() {
+            status.plan_guid = rule.plan_guid.clone();
+        }
+        status.suppress_alerts |= rule.suppress_alerts;
+        status.suppress_actions |= rule.suppress_actions;
+    }
+    status
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlanChange {
+    Switch(String),
+    Restore(String),
+}
+
+// 记录切换前的电源计划，进程退出后还原
+#[derive(Default, Debug)]
+pub struct PlanSwitchState {
+    restore_plan: Option<String>,
+    applied_plan: Option<String>,
+}
+
+impl PlanSwitchState {
+    // desired 为规则要求的计划，active 为当前实际生效的计划
+    // 返回 Switch 时还没有记为已切换，切换成功后由调用方调用 switched，失败则下个周期重试
+    pub fn transition(&mut self, desired: Option<&str>, active: Option<&str>) -> Option<PlanChange> {
+        match desired {
+            Some(desired) => {
+                if self.applied_plan.as_deref() == Some(desired) {
+                    return None;
+                }
+                if active.is_some_and(|a| a.eq_ignore_ascii_case(desired)) {
+                    self.switched(desired, active);
+                    return None;
+                }
+                Some(PlanChange::Switch(desired.to_string()))
+            }
+            None => {
+                let applied = self.applied_plan.take()?;
+                let restore = self.restore_plan.take()?;
+                // 用户在期间手动切换过计划时，不覆盖用户的选择
+                if !active.is_some_and(|a| a.eq_ignore_ascii_case(&applied)) {
+                    return None;
+                }
+                if restore.eq_ignore_ascii_case(&applied) {
+                    return None;
+                }
+                Some(PlanChange::Restore(restore))
+            }
+        }
+    }
+
+    // 记录已切换到 plan，active 为切换前的计划
+    pub fn switched(&mut self, plan: &str, active: Option<&str>) {
+        // 规则之间切换时保留最初的计划
+        if self.applied_plan.is_none() {
+            self.restore_plan = active.map(|a| a.to_string());
+        }
+        self.applied_plan = Some(plan.to_string());
+    }
+}
+
+pub fn alerts_suppressed() -> bool {
+    SUPPRESS_ALERTS.load(Ordering::SeqCst)
+}
+
+pub fn actions_suppressed() -> bool {
+    SUPPRESS_ACTIONS.load(Ordering::SeqCst)
+}
+
+pub fn parse_process_rules(content: &str) -> Result<Vec<ProcessRule>, String> {
+    PROCESS_RULES_FILE.parse_field(content, "rules")
+}
+
+fn read_process_rules_file() -> Result<Vec<ProcessRule>, String> {
+    PROCESS_RULES_FILE.read_field("rules")
+}
+
+// 刷新所有进程较慢，需要在阻塞线程中调用
+fn collect_processes() -> Vec<ProcessInfo> {
+    let Ok(mut sys) = PROCESS_SYSTEM.lock() else {
+        return Vec::new();
+    };
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_exe(UpdateKind::OnlyIfNotSet),
+    );
+    sys.processes()
+        .values()
+        .map(|p| ProcessInfo {
+            name: p.name().to_string_lossy().to_string(),
+            exe: p.exe().map(|exe| exe.to_string_lossy().to_string()),
+        })
+        .collect()
+}
+
+// 切换失败时只在第一次失败时通知，之后每个周期重试但不重复打扰
+fn apply_plan_change(change: &PlanChange, status: &ProcessRuleStatus, notify_failure: bool) -> Result<(), String> {
+    let (guid, title) = match change {
+        PlanChange::Switch(guid) => (guid, "检测到匹配的进程"),
+        PlanChange::Restore(guid) => (guid, "匹配的进程已退出"),
+    };
+    match set_active_plan(guid) {
+        Ok(_) => {
+            info!("{}，切换电源计划到 {}（规则: {:?}）", title, guid, status.active_rules);
+            let _ = send_notification(title, &format!("已切换电源计划到 {}", guid));
+            Ok(())
+        }
+        Err(e) => {
+            error!("按进程规则切换电源计划失败: {}", e);
+            if notify_failure {
+                let _ = send_notification("切换电源计划失败", &e);
+            }
+            Err(e)
+        }
+    }
+}
+
+// 定期检查进程列表，按规则切换电源计划并更新屏蔽状态
+pub fn start_process_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut plan_state = PlanSwitchState::default();
+        // 最近一次切换失败的计划，用于避免重复通知
+        let mut failed_change: Option<PlanChange> = None;
+        let mut interval = tokio_interval(Duration::from_secs(WATCH_INTERVAL_SECONDS));
+
+        loop {
+            interval.tick().await;
+            let rules = match read_process_rules_file() {
+                Ok(rules) => rules,
+                Err(e) => {
+                    warn!("加载进程规则失败: {}", e);
+                    continue;
+                }
+            };
+
+            // 没有启用的规则时不读取进程列表，但仍需还原之前切换的计划
+            let status = if rules.iter().any(|r| r.enabled) {
+                let processes = tauri::async_runtime::spawn_blocking(collect_processes)
+                    .await
+                    .unwrap_or_default();
+                evaluate_process_rules(&rules, &processes)
+            } else {
+                ProcessRuleStatus::default()
+            };
+
+            SUPPRESS_ALERTS.store(status.suppress_alerts, Ordering::SeqCst);
+            SUPPRESS_ACTIONS.store(status.suppress_actions, Ordering::SeqCst);
+
+            if status.plan_guid.is_some() || plan_state.applied_plan.is_some() {
+                let active = tauri::async_runtime::spawn_blocking(active_plan_guid)
+                    .await
+                    .unwrap_or(None);
+                match plan_state.transition(status.plan_guid.as_deref(), active.as_deref()) {
+                    Some(change) => {
+                        let notify_failure = failed_change.as_ref() != Some(&change);
+                        let task = {
+                            let (change, status) = (change.clone(), status.clone());
+                            tauri::async_runtime::spawn_blocking(move || apply_plan_change(&change, &status, notify_failure))
+                        };
+                        match task.await.unwrap_or_else(|e| Err(format!("切换电源计划的任务失败: {}", e))) {
+                            Ok(()) => {
+                                if let PlanChange::Switch(plan) = &change {
+                                    plan_state.switched(plan, active.as_deref());
+                                }
+                                failed_change = None;
+                            }
+                            Err(_) => failed_change = Some(change),
+                        }
+                    }
+                    None => failed_change = None,
+                }
+            }
+
+            let changed = match PROCESS_RULE_STATUS.lock() {
+                Ok(mut current) => {
+                    let changed = *current != status;
+                    *current = status.clone();
+                    changed
+                }
+                Err(_) => false,
+            };
+            if changed {
+                info!("进程规则状态变化: {:?}", status);
+                let _ = app.emit("process-rules-changed", &status);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn load_process_rules() -> Result<Vec<ProcessRule>, String> {
+    read_process_rules_file()
+}
+
+#[tauri::command]
+pub async fn save_process_rules(rules: Vec<ProcessRule>) -> Result<(), String> {
+    validate_process_rules(&rules, &check_if_scheme_is_valid)?;
+    PROCESS_RULES_FILE.write(json!({ "rules": rules }))
+}
+
+#[tauri::command]
+pub async fn get_process_rule_status() -> Result<ProcessRuleStatus, String> {
+    PROCESS_RULE_STATUS
+        .lock()
+        .map(|status| status.clone())
+        .map_err(|e| format!("获取进程规则状态失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(name: &str, exe: &str) -> ProcessInfo {
+        ProcessInfo {
+            name: name.to_string(),
+            exe: Some(exe.to_string()),
+        }
+    }
+
+    fn rule(id: &str, patterns: &[&str], plan_guid: Option<&str>) -> ProcessRule {
+        ProcessRule {
+            id: id.to_string(),
+            name: id.to_string(),
+            enabled: true,
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            plan_guid: plan_guid.map(|g| g.to_string()),
+            suppress_alerts: true,
+            suppress_actions: false,
+        }
+    }
+
+    #[test]
+    fn test_pattern_matching() {
+        let cargo = process("cargo.exe", r"C:\Users\dev\.cargo\bin\cargo.exe");
+        assert!(rule("a", &["CARGO.EXE"], None).matches(&cargo));
+        assert!(rule("a", &["car*.exe"], None).matches(&cargo));
+        assert!(rule("a", &["c?rgo.*"], None).matches(&cargo));
+        assert!(rule("a", &[r"*\.cargo\bin\*"], None).matches(&cargo));
+        assert!(!rule("a", &["rustc.exe"], None).matches(&cargo));
+        assert!(!rule("a", &["C:/Games/*"], None).matches(&cargo));
+    }
+
+    #[test]
+    fn test_evaluate_uses_first_plan_and_merges_suppression() {
+        let mut game = rule("game", &["game.exe"], Some("plan-game"));
+        game.suppress_alerts = false;
+        let mut build = rule("build", &["cargo.exe", "rustc.exe"], Some("plan-build"));
+        build.suppress_actions = true;
+        let processes = vec![process("rustc.exe", r"C:\rust\rustc.exe"), process("game.exe", r"D:\game.exe")];
+
+        let status = evaluate_process_rules(&[game, build], &processes);
+        assert_eq!(status.active_rules, vec!["game", "build"]);
+        assert_eq!(status.plan_guid.as_deref(), Some("plan-game"));
+        assert!(status.suppress_alerts);
+        assert!(status.suppress_actions);
+    }
+
+    #[test]
+    fn test_plan_switch_and_restore() {
+        let mut state = PlanSwitchState::default();
+        assert_eq!(
+            state.transition(Some("high"), Some("balanced")),
+            Some(PlanChange::Switch("high".to_string()))
+        );
+        state.switched("high", Some("balanced"));
+        assert_eq!(state.transition(Some("high"), Some("high")), None);
+        // 切换到另一条规则的计划，退出后仍还原最初的计划
+        assert_eq!(
+            state.transition(Some("ultimate"), Some("high")),
+            Some(PlanChange::Switch("ultimate".to_string()))
+        );
+        state.switched("ultimate", Some("high"));
+        assert_eq!(
+            state.transition(None, Some("ultimate")),
+            Some(PlanChange::Restore("balanced".to_string()))
+        );
+        assert_eq!(state.transition(None, Some("balanced")), None);
+    }
+
+    #[test]
+    fn test_failed_switch_is_retried_and_not_restored() {
+        let mut state = PlanSwitchState::default();
+        let switch = Some(PlanChange::Switch("high".to_string()));
+        // 切换失败时不调用 switched，下个周期仍要求切换
+        assert_eq!(state.transition(Some("high"), Some("balanced")), switch);
+        assert_eq!(state.transition(Some("high"), Some("balanced")), switch);
+        // 从未切换成功，进程退出后没有需要还原的计划
+        assert_eq!(state.transition(None, Some("balanced")), None);
+
+        // 重试成功后记下的是最初的计划
+        assert_eq!(state.transition(Some("high"), Some("balanced")), switch);
+        state.switched("high", Some("balanced"));
+        assert_eq!(
+            state.transition(None, Some("high")),
+            Some(PlanChange::Restore("balanced".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_restore_skipped_after_manual_change() {
+        let mut state = PlanSwitchState::default();
+        state.transition(Some("high"), Some("balanced"));
+        state.switched("high", Some("balanced"));
+        assert_eq!(state.transition(None, Some("saver")), None);
+        // 状态已清空，下次匹配重新记录
+        assert_eq!(
+            state.transition(Some("high"), Some("saver")),
+            Some(PlanChange::Switch("high".to_string()))
+        );
+        state.switched("high", Some("saver"));
+        assert_eq!(
+            state.transition(None, Some("high")),
+            Some(PlanChange::Restore("saver".to_string()))
+        );
+    }
+}
@@ -0,0 +1,545 @@
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::monitor::{
+    alert_due, alert_message, exceeded_cores, indicator_status, AutoSwitchTracker, ALERT_TITLE, AUTO_SWITCH_MESSAGE,
+    AUTO_SWITCH_TITLE,
+};
+use crate::power_source::{PowerSource, PowerSourceProfiles};
+use crate::settings_store;
+use crate::trigger_action::{self, plan_trigger_action, select_dispatch, DispatchPolicy, TriggerAction};
+use crate::trigger_condition::{ConditionTracker, FrequencySample};
+use crate::workflow::PlannedStep;
+
+// 频率记录中的一次采样
+// CSV 格式：表头为 timestamp_ms,temperature,power_source,core0..coreN,load0..loadN，除 timestamp_ms 外的列都可省略
+// trace_recorder.rs 录制的 CSV 和 JSONL 文件可以直接回放
+// JSONL 格式：每行一个本结构体的 JSON
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TraceSample {
+    // 毫秒时间戳，回放时作为虚拟时钟的时间
+    pub timestamp_ms: u64,
+    // 各核心频率（MHz）
+    pub frequencies: Vec<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub loads: Vec<f32>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub power_source: Option<PowerSource>,
+}
+
+fn parse_jsonl_trace(content: &str) -> Result<Vec<TraceSample>, String> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| format!("解析频率记录第 {} 行失败: {}", index + 1, e))
+        })
+        .collect()
+}
+
+fn parse_csv_trace(content: &str) -> Result<Vec<TraceSample>, String> {
+    let mut lines = content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+    let Some((_, header)) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let columns: Vec<&str> = header.split(',').map(|c| c.trim()).collect();
+    if columns.first() != Some(&"timestamp_ms") {
+        return Err("频率记录的第一列必须是 timestamp_ms".to_string());
+    }
+
+    let mut samples = Vec::new();
+    for (index, line) in lines {
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        if fields.len() != columns.len() {
+            return Err(format!("频率记录第 {} 行的列数与表头不一致", index + 1));
+        }
+        let invalid = |column: &str| format!("频率记录第 {} 行的 {} 列无效", index + 1, column);
+
+        let mut sample = TraceSample {
+            timestamp_ms: 0,
+            frequencies: Vec::new(),
+            loads: Vec::new(),
+            temperature: None,
+            power_source: None,
+        };
+        for (column, field) in columns.iter().zip(&fields) {
+            match *column {
+                "timestamp_ms" => sample.timestamp_ms = field.parse().map_err(|_| invalid(column))?,
+                // trace_recorder.rs 录制时附带的信息，回放时不需要
+                "frequency_source" | "active_plan" | "alert_state" | "alert_sent" => {}
+                // 空值表示当时没有读到
+                _ if field.is_empty() => {}
+                "temperature" => sample.temperature = Some(field.parse().map_err(|_| invalid(column))?),
+                "power_source" => {
+                    sample.power_source = Some(
+                        serde_json::from_value(serde_json::Value::String(field.to_string()))
+                            .map_err(|_| invalid(column))?,
+                    )
+                }
+                _ if column.starts_with("core") => sample.frequencies.push(field.parse().map_err(|_| invalid(column))?),
+                _ if column.starts_with("load") => sample.loads.push(field.parse().map_err(|_| invalid(column))?),
+                _ => return Err(format!("频率记录中有未知的列: {}", column)),
+            }
+        }
+        samples.push(sample);
+    }
+    Ok(samples)
+}
+
+// 解析频率记录，以 { 开头的按 JSONL 解析，否则按 CSV 解析；结果按时间排序
+pub fn parse_trace(content: &str) -> Result<Vec<TraceSample>, String> {
+    let mut samples = if content.trim_start().starts_with('{') {
+        parse_jsonl_trace(content)?
+    } else {
+        parse_csv_trace(content)?
+    };
+    samples.sort_by_key(|s| s.timestamp_ms);
+    Ok(samples)
+}
+
+// 回放使用的设置，默认取当前设置，可以逐项覆盖用于离线调整阈值
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ReplayConfig {
+    pub frequency_threshold: f64,
+    pub frequency_mode: String,
+    pub alert_debounce_seconds: u64,
+    pub auto_switch_enabled: bool,
+    pub auto_switch_threshold: u64,
+    pub trigger_action_enabled: bool,
+    pub dispatch_policy: DispatchPolicy,
+    pub power_source_profiles: PowerSourceProfiles,
+}
+
+impl ReplayConfig {
+    pub fn from_settings() -> Result<Self, String> {
+        Ok(Self {
+            frequency_threshold: settings_store::get_frequency_threshold(),
+            frequency_mode: settings_store::get_frequency_mode(),
+            alert_debounce_seconds: settings_store::get_alert_debounce_seconds(),
+            auto_switch_enabled: settings_store::get_auto_switch_enabled(),
+            auto_switch_threshold: settings_store::get_auto_switch_threshold(),
+            trigger_action_enabled: settings_store::get_trigger_action_enabled().unwrap_or(false),
+            dispatch_policy: DispatchPolicy::from_setting(&settings_store::get_trigger_dispatch_policy())?,
+            power_source_profiles: settings_store::get_power_source_profiles(),
+        })
+    }
+}
+
+// 对当前设置的覆盖，为空的项保持不变
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ReplayOverrides {
+    pub frequency_threshold: Option<f64>,
+    pub frequency_mode: Option<String>,
+    pub alert_debounce_seconds: Option<u64>,
+    pub auto_switch_enabled: Option<bool>,
+    pub auto_switch_threshold: Option<u64>,
+    pub trigger_action_enabled: Option<bool>,
+    pub dispatch_policy: Option<DispatchPolicy>,
+}
+
+impl ReplayOverrides {
+    fn apply(self, config: &mut ReplayConfig) {
+        if let Some(v) = self.frequency_threshold {
+            config.frequency_threshold = v;
+        }
+        if let Some(v) = self.frequency_mode {
+            config.frequency_mode = v;
+        }
+        if let Some(v) = self.alert_debounce_seconds {
+            config.alert_debounce_seconds = v;
+        }
+        if let Some(v) = self.auto_switch_enabled {
+            config.auto_switch_enabled = v;
+        }
+        if let Some(v) = self.auto_switch_threshold {
+            config.auto_switch_threshold = v;
+        }
+        if let Some(v) = self.trigger_action_enabled {
+            config.trigger_action_enabled = v;
+        }
+        if let Some(v) = self.dispatch_policy {
+            config.dispatch_policy = v;
+        }
+    }
+}
+
+// 回放时代替真实的电源计划操作
+pub trait PlanBackend {
+    fn set_active_plan(&mut self, guid: &str) -> Result<(), String>;
+}
+
+// 回放时代替系统通知
+pub trait NotificationBackend {
+    fn send(&mut self, title: &str, body: &str);
+}
+
+// 只记录切换过的计划
+#[derive(Default, Debug)]
+pub struct FakePlanBackend {
+    pub switches: Vec<String>,
+}
+
+impl PlanBackend for FakePlanBackend {
+    fn set_active_plan(&mut self, guid: &str) -> Result<(), String> {
+        self.switches.push(guid.to_string());
+        Ok(())
+    }
+}
+
+// 只记录发出的通知（标题, 内容）
+#[derive(Default, Debug)]
+pub struct FakeNotificationBackend {
+    pub sent: Vec<(String, String)>,
+}
+
+impl NotificationBackend for FakeNotificationBackend {
+    fn send(&mut self, title: &str, body: &str) {
+        self.sent.push((title.to_string(), body.to_string()));
+    }
+}
+
+// 回放过程中监控做出的一次决定
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReplayDecision {
+    // 指示器状态变化
+    Indicator { status: String },
+    // 发送了频率提醒
+    Alert { exceeded_cores: Vec<usize>, threshold: f64, message: String },
+    // 频率长时间不变，自动切换到 CalcMhz 模式
+    AutoSwitch { unchanged_count: u64 },
+    // 派发了触发动作，steps 为动作会执行的步骤
+    TriggerAction { action_id: String, action_name: String, steps: Vec<PlannedStep> },
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TimedDecision {
+    pub timestamp_ms: u64,
+    #[serde(flatten)]
+    pub decision: ReplayDecision,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ReplayReport {
+    pub config: ReplayConfig,
+    pub samples: usize,
+    pub decisions: Vec<TimedDecision>,
+    pub final_frequency_mode: String,
+    pub plan_switches: Vec<String>,
+    pub notifications: Vec<(String, String)>,
+}
+
+// 按记录中的时间逐个回放采样，判断顺序与 Monitor::start 中的监控循环一致：
+// 自动切换检测（切换后跳过本次的其余判断）→ 触发动作派发 → 阈值提醒
+// 触发动作不会真正执行，只把其中切换计划的步骤交给 PlanBackend，等待步骤不占用虚拟时间
+pub struct Replayer<'a, P: PlanBackend, N: NotificationBackend> {
+    config: ReplayConfig,
+    actions: &'a [TriggerAction],
+    plans: P,
+    notifications: N,
+    // 虚拟时钟，为当前采样的时间（毫秒）
+    now_ms: u64,
+    frequency_mode: String,
+    auto_switch: AutoSwitchTracker,
+    tracker: ConditionTracker,
+    last_round_robin: Option<String>,
+    last_alert_time: u64,
+    indicator: Option<&'static str>,
+    decisions: Vec<TimedDecision>,
+}
+
+impl<'a, P: PlanBackend, N: NotificationBackend> Replayer<'a, P, N> {
+    pub fn new(config: ReplayConfig, actions: &'a [TriggerAction], plans: P, notifications: N) -> Self {
+        Self {
+            frequency_mode: config.frequency_mode.clone(),
+            config,
+            actions,
+            plans,
+            notifications,
+            now_ms: 0,
+            auto_switch: AutoSwitchTracker::default(),
+            tracker: ConditionTracker::default(),
+            last_round_robin: None,
+            last_alert_time: 0,
+            indicator: None,
+            decisions: Vec::new(),
+        }
+    }
+
+    fn decide(&mut self, decision: ReplayDecision) {
+        self.decisions.push(TimedDecision {
+            timestamp_ms: self.now_ms,
+            decision,
+        });
+    }
+
+    pub fn step(&mut self, sample: &TraceSample) {
+        self.now_ms = sample.timestamp_ms;
+        let now = self.now_ms / 1000;
+        let frequencies = &sample.frequencies;
+
+        if self.config.auto_switch_enabled && self.frequency_mode == "1" {
+            let decision = self.auto_switch.observe(frequencies, self.config.auto_switch_threshold);
+            if decision.switch_mode {
+                self.notifications.send(AUTO_SWITCH_TITLE, AUTO_SWITCH_MESSAGE);
+                self.frequency_mode = "2".to_string();
+                self.decide(ReplayDecision::AutoSwitch {
+                    unchanged_count: decision.unchanged_count,
+                });
+                return;
+            }
+        } else {
+            self.auto_switch.reset(frequencies);
+        }
+
+        let threshold = self
+            .config
+            .power_source_profiles
+            .frequency_threshold(sample.power_source, self.config.frequency_threshold);
+
+        if self.config.trigger_action_enabled {
+            let frequency_sample = FrequencySample {
+                frequencies: frequencies.clone(),
+                frequency_mode: self.frequency_mode.clone(),
+                global_threshold: threshold,
+                global_cooldown_seconds: self.config.alert_debounce_seconds,
+                power_source: sample.power_source,
+                temperature: sample.temperature,
+                loads: sample.loads.clone(),
+            };
+            let selected = select_dispatch(
+                self.actions,
+                &frequency_sample,
+                self.config.dispatch_policy,
+                &mut self.tracker,
+                &mut self.last_round_robin,
+                now,
+            );
+            for action in selected {
+                let steps = plan_trigger_action(action);
+                for step in steps.iter().filter(|s| s.kind == "switch_plan") {
+                    if let Some(guid) = &step.target {
+                        let _ = self.plans.set_active_plan(guid);
+                    }
+                }
+                self.decide(ReplayDecision::TriggerAction {
+                    action_id: action.id.clone(),
+                    action_name: action.name.clone(),
+                    steps,
+                });
+            }
+        }
+
+        let exceeded = exceeded_cores(frequencies, threshold);
+        if !exceeded.is_empty() && alert_due(&mut self.last_alert_time, now, self.config.alert_debounce_seconds) {
+            let message = alert_message(exceeded.len(), frequencies.len(), threshold, sample.temperature);
+            self.notifications.send(ALERT_TITLE, &message);
+            self.decide(ReplayDecision::Alert {
+                exceeded_cores: exceeded.clone(),
+                threshold,
+                message,
+            });
+        }
+
+        let status = indicator_status(exceeded.len(), frequencies.len());
+        if self.indicator != Some(status) {
+            self.indicator = Some(status);
+            self.decide(ReplayDecision::Indicator {
+                status: status.to_string(),
+            });
+        }
+    }
+
+    pub fn into_parts(self) -> (Vec<TimedDecision>, String, P, N) {
+        (self.decisions, self.frequency_mode, self.plans, self.notifications)
+    }
+}
+
+// 用假的计划和通知后端回放整段记录
+pub fn replay_trace(samples: &[TraceSample], config: ReplayConfig, actions: &[TriggerAction]) -> ReplayReport {
+    let mut replayer = Replayer::new(
+        config.clone(),
+        actions,
+        FakePlanBackend::default(),
+        FakeNotificationBackend::default(),
+    );
+    for sample in samples {
+        replayer.step(sample);
+    }
+    let (decisions, final_frequency_mode, plans, notifications) = replayer.into_parts();
+    ReplayReport {
+        config,
+        samples: samples.len(),
+        decisions,
+        final_frequency_mode,
+        plan_switches: plans.switches,
+        notifications: notifications.sent,
+    }
+}
+
+// 用当前设置和触发动作回放一份频率记录，overrides 中的项覆盖当前设置
+#[tauri::command]
+pub async fn replay_frequency_trace(
+    app: AppHandle,
+    path: String,
+    overrides: Option<ReplayOverrides>,
+) -> Result<ReplayReport, String> {
+    let content = fs::read_to_string(Path::new(&path)).map_err(|e| format!("读取频率记录失败: {}", e))?;
+    let samples = parse_trace(&content)?;
+    let mut config = ReplayConfig::from_settings()?;
+    overrides.unwrap_or_default().apply(&mut config);
+    let actions = trigger_action::load_trigger_actions(app).await?;
+
+    let report = replay_trace(&samples, config, &actions);
+    info!(
+        "回放频率记录 {}: {} 个采样，{} 个决定",
+        path,
+        report.samples,
+        report.decisions.len()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trigger_action::TriggerActionWorker;
+    use crate::trigger_condition::TriggerCondition;
+
+    fn config() -> ReplayConfig {
+        ReplayConfig {
+            frequency_threshold: 3.0,
+            frequency_mode: "1".to_string(),
+            alert_debounce_seconds: 10,
+            auto_switch_enabled: false,
+            auto_switch_threshold: 3,
+            trigger_action_enabled: true,
+            dispatch_policy: DispatchPolicy::FirstMatch,
+            power_source_profiles: PowerSourceProfiles::default(),
+        }
+    }
+
+    // 记录中的时间为毫秒时间戳
+    const BASE_MS: u64 = 1_700_000_000_000;
+
+    fn samples(frequencies: &[&[u64]], step_ms: u64) -> Vec<TraceSample> {
+        frequencies
+            .iter()
+            .enumerate()
+            .map(|(index, f)| TraceSample {
+                timestamp_ms: BASE_MS + index as u64 * step_ms,
+                frequencies: f.to_vec(),
+                loads: Vec::new(),
+                temperature: None,
+                power_source: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_csv_and_jsonl_trace() {
+        let csv = "timestamp_ms,temperature,power_source,core0,core1,load0,load1\n\
+                   2000,,battery,3100,2900,5,80\n\
+                   1000,61.5,ac,2800,2800,10,20\n";
+        let parsed = parse_trace(csv).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].timestamp_ms, 1000);
+        assert_eq!(parsed[0].temperature, Some(61.5));
+        assert_eq!(parsed[1].power_source, Some(PowerSource::Battery));
+        assert_eq!(parsed[1].frequencies, vec![3100, 2900]);
+        assert_eq!(parsed[1].loads, vec![5.0, 80.0]);
+
+        let jsonl: Vec<String> = parsed.iter().map(|s| serde_json::to_string(s).unwrap()).collect();
+        assert_eq!(parse_trace(&jsonl.join("\n")).unwrap(), parsed);
+
+        assert!(parse_trace("time,core0\n1,2\n").is_err());
+        assert!(parse_trace("timestamp_ms,core0\n1\n").is_err());
+    }
+
+    #[test]
+    fn test_alerts_are_debounced_on_virtual_clock() {
+        let trace = samples(&[&[3500, 2000], &[3500, 2000], &[3500, 3500], &[2000, 2000], &[3500, 2000]], 5000);
+        let report = replay_trace(&trace, config(), &[]);
+
+        let alerts: Vec<u64> = report
+            .decisions
+            .iter()
+            .filter(|d| matches!(d.decision, ReplayDecision::Alert { .. }))
+            .map(|d| d.timestamp_ms - BASE_MS)
+            .collect();
+        // 0 秒提醒，5 秒在防抖时间内，10 秒再次提醒，20 秒距上次已满 10 秒
+        assert_eq!(alerts, vec![0, 10000, 20000]);
+        assert_eq!(report.notifications.len(), 3);
+        assert_eq!(report.notifications[1].1, "所有核心频率均超过 3.0 GHz");
+
+        let indicators: Vec<&ReplayDecision> = report
+            .decisions
+            .iter()
+            .map(|d| &d.decision)
+            .filter(|d| matches!(d, ReplayDecision::Indicator { .. }))
+            .collect();
+        // warning → danger → normal → warning，状态不变时不重复记录
+        assert_eq!(indicators.len(), 4);
+    }
+
+    #[test]
+    fn test_auto_switch_after_unchanged_frequencies() {
+        let config = ReplayConfig {
+            auto_switch_enabled: true,
+            trigger_action_enabled: false,
+            ..config()
+        };
+        let trace = samples(&[&[2000, 2000], &[2100, 2000], &[2100, 2000], &[2100, 2000], &[2100, 2000]], 1000);
+        let report = replay_trace(&trace, config, &[]);
+
+        // 第一个采样没有上一次的数据也计数，第二个采样频率变化后清零，之后连续 3 次未变化
+        let switched: Vec<&TimedDecision> = report
+            .decisions
+            .iter()
+            .filter(|d| matches!(d.decision, ReplayDecision::AutoSwitch { .. }))
+            .collect();
+        assert_eq!(switched.len(), 1);
+        assert_eq!(switched[0].timestamp_ms - BASE_MS, 4000);
+        assert_eq!(report.final_frequency_mode, "2");
+        assert_eq!(report.notifications[0].0, AUTO_SWITCH_TITLE);
+    }
+
+    #[test]
+    fn test_trigger_action_sustain_and_cooldown() {
+        let action = TriggerAction {
+            id: "a".to_string(),
+            name: "降频".to_string(),
+            enabled: true,
+            condition: TriggerCondition {
+                sustain_seconds: 2,
+                cooldown_seconds: Some(60),
+                ..Default::default()
+            },
+            worker: TriggerActionWorker::Simple {
+                temp_plan_guid: "TEMP".to_string(),
+                pause_seconds: 1,
+                target_plan_guid: "TARGET".to_string(),
+            },
+            ..Default::default()
+        };
+        let trace = samples(&[&[3500], &[3500], &[3500], &[3500], &[3500]], 1000);
+        let report = replay_trace(&trace, config(), std::slice::from_ref(&action));
+
+        let fired: Vec<u64> = report
+            .decisions
+            .iter()
+            .filter(|d| matches!(d.decision, ReplayDecision::TriggerAction { .. }))
+            .map(|d| d.timestamp_ms - BASE_MS)
+            .collect();
+        // 持续 2 秒后触发一次，之后处于冷却中
+        assert_eq!(fired, vec![2000]);
+        assert_eq!(report.plan_switches, vec!["TEMP", "TARGET"]);
+    }
+}
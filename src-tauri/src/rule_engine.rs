@@ -0,0 +1,908 @@
+use chrono::{Local, NaiveTime, Timelike};
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use sysinfo::{ProcessesToUpdate, System};
+use tauri::{async_runtime, AppHandle, Emitter};
+
+use crate::action_executor::ACTION_EXECUTOR;
+use crate::action_journal::JournalTrigger;
+use crate::notification::send_notification;
+use crate::power_plan::{cached_active_plan_guid, check_if_scheme_is_valid};
+use crate::power_source::{current_power_source, PowerSource};
+use crate::trigger_action::{load_trigger_actions, TriggerAction};
+use crate::versioned_file::VersionedFile;
+
+// rules.json 的文件版本
+pub const RULES_SCHEMA_VERSION: u32 = 1;
+
+static RULES_FILE: VersionedFile = VersionedFile::new("rules.json", "规则", RULES_SCHEMA_VERSION);
+
+// 频率历史最多保留的时长，也是聚合窗口的上限
+const MAX_HISTORY_SECONDS: u64 = 3600;
+
+static RULE_ENGINE: Lazy<Mutex<RuleEngine>> = Lazy::new(|| Mutex::new(RuleEngine::default()));
+
+// 频率的取值方式：所有核心中的最高、最低或平均值
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregate {
+    Max,
+    Min,
+    Average,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    Above,
+    Below,
+}
+
+// 规则条件，可以用 all / any / not 组合
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleCondition {
+    All {
+        conditions: Vec<RuleCondition>,
+    },
+    Any {
+        conditions: Vec<RuleCondition>,
+    },
+    Not {
+        condition: Box<RuleCondition>,
+    },
+    // 当前频率，或最近 window_seconds 秒内的聚合频率
+    Frequency {
+        aggregate: Aggregate,
+        #[serde(default)]
+        window_seconds: Option<u64>,
+        comparison: Comparison,
+        ghz: f64,
+    },
+    ActivePlan {
+        plan_guid: String,
+    },
+    PowerSource {
+        source: PowerSource,
+    },
+    // 每天的时间段，格式为 HH:MM，结束早于开始时表示跨越午夜
+    TimeOfDay {
+        start: String,
+        end: String,
+    },
+    ProcessRunning {
+        name: String,
+    },
+    // 最近一次采样的 CPU 温度（摄氏度）
+    Temperature {
+        comparison: Comparison,
+        celsius: f32,
+    },
+}
+
+// 规则满足时要做的事
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    // 执行已有的触发动作（即使该动作本身未启用）
+    TriggerAction { action_id: String },
+    SwitchPlan { plan_guid: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Rule {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+    pub condition: RuleCondition,
+    pub actions: Vec<RuleAction>,
+    // 条件持续满足时每隔多少秒再次执行，为空则只在条件由不满足变为满足时执行
+    #[serde(default)]
+    pub cooldown_seconds: Option<u64>,
+}
+
+// 条件的判断结果及原因
+#[derive(Serialize, Clone, Debug)]
+pub struct Evaluation {
+    pub matched: bool,
+    pub explanation: String,
+}
+
+impl Evaluation {
+    fn new(matched: bool, explanation: String) -> Self {
+        Self { matched, explanation }
+    }
+}
+
+// 一次采样的频率（MHz）
+#[derive(Clone, Debug)]
+pub struct FrequencyPoint {
+    pub at: u64,
+    pub max: u64,
+    pub min: u64,
+    pub average: f64,
+}
+
+impl FrequencyPoint {
+    fn from_frequencies(at: u64, frequencies: &[u64]) -> Option<Self> {
+        if frequencies.is_empty() {
+            return None;
+        }
+        Some(Self {
+            at,
+            max: *frequencies.iter().max()?,
+            min: *frequencies.iter().min()?,
+            average: frequencies.iter().sum::<u64>() as f64 / frequencies.len() as f64,
+        })
+    }
+
+    fn value(&self, aggregate: Aggregate) -> f64 {
+        match aggregate {
+            Aggregate::Max => self.max as f64,
+            Aggregate::Min => self.min as f64,
+            Aggregate::Average => self.average,
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct FrequencyHistory {
+    points: VecDeque<FrequencyPoint>,
+}
+
+impl FrequencyHistory {
+    pub fn record(&mut self, at: u64, frequencies: &[u64]) {
+        if let Some(point) = FrequencyPoint::from_frequencies(at, frequencies) {
+            self.points.push_back(point);
+        }
+        while self
+            .points
+            .front()
+            .is_some_and(|p| at.saturating_sub(p.at) > MAX_HISTORY_SECONDS)
+        {
+            self.points.pop_front();
+        }
+    }
+
+    // 返回 MHz；有窗口时对窗口内每次采样的值再做同样的聚合
+    fn aggregate(&self, aggregate: Aggregate, window_seconds: Option<u64>, now: u64) -> Option<f64> {
+        let Some(window) = window_seconds else {
+            return self.points.back().map(|p| p.value(aggregate));
+        };
+        let values: Vec<f64> = self
+            .points
+            .iter()
+            .filter(|p| now.saturating_sub(p.at) <= window)
+            .map(|p| p.value(aggregate))
+            .collect();
+        if values.is_empty() {
+            return None;
+        }
+        Some(match aggregate {
+            Aggregate::Max => values.iter().cloned().fold(f64::MIN, f64::max),
+            Aggregate::Min => values.iter().cloned().fold(f64::MAX, f64::min),
+            Aggregate::Average => values.iter().sum::<f64>() / values.len() as f64,
+        })
+    }
+}
+
+// 规则判断时用到的系统状态，只采集规则实际用到的部分
+pub struct RuleContext<'a> {
+    pub now: u64,
+    pub minute_of_day: u32,
+    pub history: &'a FrequencyHistory,
+    pub active_plan_guid: Option<String>,
+    pub power_source: Option<PowerSource>,
+    pub processes: HashSet<String>,
+    pub temperature: Option<f32>,
+}
+
+#[derive(Default, Clone, Copy)]
+struct ContextNeeds {
+    active_plan: bool,
+    power_source: bool,
+    processes: bool,
+}
+
+fn parse_time_of_day(value: &str) -> Result<u32, String> {
+    let time = NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| format!("时间格式应为 HH:MM: {}", value))?;
+    Ok(time.hour() * 60 + time.minute())
+}
+
+fn normalize_process_name(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    name.strip_suffix(".exe").map(|n| n.to_string()).unwrap_or(name)
+}
+
+fn aggregate_name(aggregate: Aggregate) -> &'static str {
+    match aggregate {
+        Aggregate::Max => "最高",
+        Aggregate::Min => "最低",
+        Aggregate::Average => "平均",
+    }
+}
+
+impl RuleCondition {
+    // 校验条件本身，plan_exists 用于检查电源计划是否存在
+    pub fn validate(&self, plan_exists: &dyn Fn(&str) -> bool) -> Result<(), String> {
+        match self {
+            RuleCondition::All { conditions } | RuleCondition::Any { conditions } => {
+                if conditions.is_empty() {
+                    return Err("组合条件至少需要一个子条件".to_string());
+                }
+                conditions.iter().try_for_each(|c| c.validate(plan_exists))
+            }
+            RuleCondition::Not { condition } => condition.validate(plan_exists),
+            RuleCondition::Frequency { window_seconds, ghz, .. } => {
+                if *ghz <= 0.0 {
+                    return Err(format!("频率必须大于0，当前: {}", ghz));
+                }
+                match window_seconds {
+                    Some(0) => Err("聚合窗口必须大于0秒".to_string()),
+                    Some(window) if *window > MAX_HISTORY_SECONDS => {
+                        Err(format!("聚合窗口不能超过 {} 秒", MAX_HISTORY_SECONDS))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            RuleCondition::ActivePlan { plan_guid } => {
+                if !plan_exists(plan_guid) {
+                    return Err(format!("电源计划不存在: {}", plan_guid));
+                }
+                Ok(())
+            }
+            RuleCondition::PowerSource { .. } => Ok(()),
+            RuleCondition::Temperature { celsius, .. } => {
+                if *celsius <= 0.0 {
+                    return Err(format!("温度必须大于0，当前: {}", celsius));
+                }
+                Ok(())
+            }
+            RuleCondition::TimeOfDay { start, end } => {
+                if parse_time_of_day(start)? == parse_time_of_day(end)? {
+                    return Err("时间段的开始和结束不能相同".to_string());
+                }
+                Ok(())
+            }
+            RuleCondition::ProcessRunning { name } => {
+                if normalize_process_name(name).is_empty() {
+                    return Err("进程名不能为空".to_string());
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn collect_needs(&self, needs: &mut ContextNeeds) {
+        match self {
+            RuleCondition::All { conditions } | RuleCondition::Any { conditions } => {
+                conditions.iter().for_each(|c| c.collect_needs(needs))
+            }
+            RuleCondition::Not { condition } => condition.collect_needs(needs),
+            RuleCondition::ActivePlan { .. } => needs.active_plan = true,
+            RuleCondition::PowerSource { .. } => needs.power_source = true,
+            RuleCondition::ProcessRunning { .. } => needs.processes = true,
+            RuleCondition::Frequency { .. } | RuleCondition::TimeOfDay { .. } | RuleCondition::Temperature { .. } => {}
+        }
+    }
+
+    // 判断条件，说明中描述的是实际状态，因此 not 直接沿用子条件的说明
+    pub fn evaluate(&self, ctx: &RuleContext) -> Evaluation {
+        match self {
+            RuleCondition::All { conditions } => {
+                let results: Vec<Evaluation> = conditions.iter().map(|c| c.evaluate(ctx)).collect();
+                match results.iter().find(|r| !r.matched) {
+                    Some(failed) => Evaluation::new(false, failed.explanation.clone()),
+                    None => Evaluation::new(
+                        true,
+                        results.iter().map(|r| r.explanation.as_str()).collect::<Vec<_>>().join("，并且"),
+                    ),
+                }
+            }
+            RuleCondition::Any { conditions } => {
+                let results: Vec<Evaluation> = conditions.iter().map(|c| c.evaluate(ctx)).collect();
+                let matched: Vec<&str> = results
+                    .iter()
+                    .filter(|r| r.matched)
+                    .map(|r| r.explanation.as_str())
+                    .collect();
+                if matched.is_empty() {
+                    let reasons: Vec<&str> = results.iter().map(|r| r.explanation.as_str()).collect();
+                    Evaluation::new(false, format!("以下条件都不满足: {}", reasons.join("；")))
+                } else {
+                    Evaluation::new(true, matched.join("，或者"))
+                }
+            }
+            RuleCondition::Not { condition } => {
+                let inner = condition.evaluate(ctx);
+                Evaluation::new(!inner.matched, inner.explanation)
+            }
+            RuleCondition::Frequency { aggregate, window_seconds, comparison, ghz } => {
+                let subject = match window_seconds {
+                    Some(window) => format!("最近 {} 秒{}频率", window, aggregate_name(*aggregate)),
+                    None => format!("当前{}频率", aggregate_name(*aggregate)),
+                };
+                let Some(mhz) = ctx.history.aggregate(*aggregate, *window_seconds, ctx.now) else {
+                    return Evaluation::new(false, format!("{}没有数据", subject));
+                };
+                let value = mhz / 1000.0;
+                let matched = match comparison {
+                    Comparison::Above => value > *ghz,
+                    Comparison::Below => value < *ghz,
+                };
+                let relation = match (comparison, matched) {
+                    (Comparison::Above, true) => "高于",
+                    (Comparison::Above, false) => "不高于",
+                    (Comparison::Below, true) => "低于",
+                    (Comparison::Below, false) => "不低于",
+                };
+                Evaluation::new(matched, format!("{} {:.2} GHz {} {:.2} GHz", subject, value, relation, ghz))
+            }
+            RuleCondition::ActivePlan { plan_guid } => match &ctx.active_plan_guid {
+                Some(active) if active.eq_ignore_ascii_case(plan_guid) => {
+                    Evaluation::new(true, format!("当前电源计划为 {}", active))
+                }
+                Some(active) => Evaluation::new(false, format!("当前电源计划为 {}，不是 {}", active, plan_guid)),
+                None => Evaluation::new(false, "无法读取当前电源计划".to_string()),
+            },
+            RuleCondition::PowerSource { source } => match ctx.power_source {
+                Some(current) => Evaluation::new(
+                    current == *source,
+                    format!("当前正在使用{}", current.display_name()),
+                ),
+                None => Evaluation::new(false, "无法判断当前供电方式".to_string()),
+            },
+            RuleCondition::TimeOfDay { start, end } => {
+                let (Ok(start_minute), Ok(end_minute)) = (parse_time_of_day(start), parse_time_of_day(end)) else {
+                    return Evaluation::new(false, format!("时间段 {}-{} 格式无效", start, end));
+                };
+                let minute = ctx.minute_of_day;
+                let matched = if start_minute < end_minute {
+                    minute >= start_minute && minute < end_minute
+                } else {
+                    minute >= start_minute || minute < end_minute
+                };
+                Evaluation::new(
+                    matched,
+                    format!(
+                        "当前时间 {:02}:{:02} {} {}-{} 之间",
+                        minute / 60,
+                        minute % 60,
+                        if matched { "在" } else { "不在" },
+                        start,
+                        end
+                    ),
+                )
+            }
+            RuleCondition::Temperature { comparison, celsius } => {
+                let Some(temperature) = ctx.temperature else {
+                    return Evaluation::new(false, "没有 CPU 温度数据".to_string());
+                };
+                let (matched, relation) = match comparison {
+                    Comparison::Above if temperature > *celsius => (true, "高于"),
+                    Comparison::Above => (false, "不高于"),
+                    Comparison::Below if temperature < *celsius => (true, "低于"),
+                    Comparison::Below => (false, "不低于"),
+                };
+                Evaluation::new(
+                    matched,
+                    format!("CPU 温度 {:.1}°C {} {:.1}°C", temperature, relation, celsius),
+                )
+            }
+            RuleCondition::ProcessRunning { name } => {
+                let matched = ctx.processes.contains(&normalize_process_name(name));
+                Evaluation::new(
+                    matched,
+                    format!("进程 {} {}", name, if matched { "正在运行" } else { "未运行" }),
+                )
+            }
+        }
+    }
+}
+
+impl Rule {
+    pub fn validate(&self, action_ids: &HashSet<&str>, plan_exists: &dyn Fn(&str) -> bool) -> Result<(), String> {
+        if self.actions.is_empty() {
+            return Err("至少需要一个执行动作".to_string());
+        }
+        for action in &self.actions {
+            match action {
+                RuleAction::TriggerAction { action_id } => {
+                    if !action_ids.contains(action_id.as_str()) {
+                        return Err(format!("引用的触发动作不存在: {}", action_id));
+                    }
+                }
+                RuleAction::SwitchPlan { plan_guid } => {
+                    if !plan_exists(plan_guid) {
+                        return Err(format!("电源计划不存在: {}", plan_guid));
+                    }
+                }
+            }
+        }
+        self.condition.validate(plan_exists)
+    }
+}
+
+// 校验所有规则，返回无效规则的 id 和原因
+pub fn validate_rules(
+    rules: &[Rule],
+    actions: &[TriggerAction],
+    plan_exists: &dyn Fn(&str) -> bool,
+) -> Vec<(String, String)> {
+    let action_ids: HashSet<&str> = actions.iter().map(|a| a.id.as_str()).collect();
+    let mut seen = HashSet::new();
+    let mut invalid = Vec::new();
+    for rule in rules {
+        if !seen.insert(rule.id.as_str()) {
+            invalid.push((rule.id.clone(), format!("规则 {} 的 id 重复", rule.name)));
+            continue;
+        }
+        if let Err(e) = rule.validate(&action_ids, plan_exists) {
+            invalid.push((rule.id.clone(), format!("规则 {} 无效: {}", rule.name, e)));
+        }
+    }
+    invalid
+}
+
+#[derive(Default, Debug)]
+struct RuleState {
+    matched: bool,
+    last_fired: Option<u64>,
+}
+
+#[derive(Default)]
+pub struct RuleEngine {
+    history: FrequencyHistory,
+    temperature: Option<f32>,
+    rules: Vec<Rule>,
+    invalid: HashMap<String, String>,
+    loaded_modified: Option<SystemTime>,
+    loaded: bool,
+    states: HashMap<String, RuleState>,
+}
+
+impl RuleEngine {
+    // 条件由不满足变为满足时执行；设置了冷却时间时，持续满足期间每隔冷却时间再执行
+    fn should_fire(&mut self, rule: &Rule, matched: bool, now: u64) -> bool {
+        let state = self.states.entry(rule.id.clone()).or_default();
+        let was_matched = std::mem::replace(&mut state.matched, matched);
+        if !matched {
+            return false;
+        }
+        let fire = match (was_matched, rule.cooldown_seconds, state.last_fired) {
+            (false, _, _) => true,
+            (true, Some(cooldown), Some(last_fired)) => now.saturating_sub(last_fired) >= cooldown,
+            _ => false,
+        };
+        if fire {
+            state.last_fired = Some(now);
+        }
+        fire
+    }
+
+    fn needs(&self) -> ContextNeeds {
+        let mut needs = ContextNeeds::default();
+        for rule in self.rules.iter().filter(|r| r.enabled) {
+            rule.condition.collect_needs(&mut needs);
+        }
+        needs
+    }
+}
+
+fn now_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+pub fn parse_rules(content: &str) -> Result<Vec<Rule>, String> {
+    RULES_FILE.parse_field(content, "rules")
+}
+
+fn read_rules_file() -> Result<Vec<Rule>, String> {
+    RULES_FILE.read_field("rules")
+}
+
+fn write_rules_file(rules: &[Rule]) -> Result<(), String> {
+    RULES_FILE.write(json!({ "rules": rules }))
+}
+
+fn collect_processes() -> HashSet<String> {
+    let mut sys = System::new();
+    sys.refresh_processes(ProcessesToUpdate::All, true);
+    sys.processes()
+        .values()
+        .map(|p| normalize_process_name(&p.name().to_string_lossy()))
+        .collect()
+}
+
+// 规则用到的系统状态，读取进程列表和当前计划较慢，在规则引擎锁外的阻塞线程中采集
+#[derive(Default)]
+struct SystemState {
+    active_plan_guid: Option<String>,
+    power_source: Option<PowerSource>,
+    processes: HashSet<String>,
+}
+
+impl SystemState {
+    fn collect(needs: &ContextNeeds) -> Self {
+        SystemState {
+            active_plan_guid: if needs.active_plan { cached_active_plan_guid() } else { None },
+            power_source: if needs.power_source { current_power_source() } else { None },
+            processes: if needs.processes { collect_processes() } else { HashSet::new() },
+        }
+    }
+}
+
+async fn collect_system_state(needs: ContextNeeds) -> SystemState {
+    async_runtime::spawn_blocking(move || SystemState::collect(&needs))
+        .await
+        .unwrap_or_else(|e| {
+            error!("采集规则所需的系统状态失败: {}", e);
+            SystemState::default()
+        })
+}
+
+fn build_context(history: &FrequencyHistory, temperature: Option<f32>, system: SystemState, now: u64) -> RuleContext<'_> {
+    let local = Local::now();
+    RuleContext {
+        now,
+        minute_of_day: local.hour() * 60 + local.minute(),
+        history,
+        active_plan_guid: system.active_plan_guid,
+        power_source: system.power_source,
+        processes: system.processes,
+        temperature,
+    }
+}
+
+// 规则文件修改后重新加载并校验，无效的规则不参与判断
+// 读取、校验和通知都在锁外进行，只在最后替换规则时加锁
+async fn reload_if_changed(app: &AppHandle, actions: &[TriggerAction]) {
+    let modified = fs::metadata(RULES_FILE.path()).and_then(|m| m.modified()).ok();
+    match RULE_ENGINE.lock() {
+        Ok(engine) if engine.loaded && modified == engine.loaded_modified => return,
+        Ok(_) => {}
+        Err(_) => {
+            error!("获取规则引擎锁失败");
+            return;
+        }
+    }
+
+    let actions = actions.to_vec();
+    let loaded = async_runtime::spawn_blocking(move || {
+        read_rules_file().map(|rules| {
+            let invalid = validate_rules(&rules, &actions, &check_if_scheme_is_valid);
+            (rules, invalid)
+        })
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("加载规则任务异常: {}", e)));
+
+    let (rules, invalid) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            error!("加载规则失败: {}", e);
+            let _ = send_notification("规则加载失败", &e);
+            (Vec::new(), Vec::new())
+        }
+    };
+    for (_, reason) in &invalid {
+        error!("{}", reason);
+    }
+    if !invalid.is_empty() {
+        let reasons: Vec<&str> = invalid.iter().map(|(_, reason)| reason.as_str()).collect();
+        let _ = send_notification("部分规则无效，已跳过", &reasons.join("\n"));
+        let _ = app.emit("rules-invalid", &reasons);
+    }
+
+    let Ok(mut engine) = RULE_ENGINE.lock() else {
+        error!("获取规则引擎锁失败");
+        return;
+    };
+    engine.loaded = true;
+    engine.loaded_modified = modified;
+    engine.invalid = invalid.into_iter().collect();
+    engine.rules = rules;
+    let engine = &mut *engine;
+    engine.states.retain(|id, _| engine.rules.iter().any(|r| &r.id == id));
+    info!("已加载 {} 条规则，其中 {} 条无效", engine.rules.len(), engine.invalid.len());
+}
+
+// 每次采样后记录频率和温度，供聚合条件和温度条件使用
+pub fn record_sample(frequencies: &[u64], temperature: Option<f32>) {
+    if let Ok(mut engine) = RULE_ENGINE.lock() {
+        engine.history.record(now_seconds(), frequencies);
+        engine.temperature = temperature;
+    }
+}
+
+// 每个监控周期判断一次所有规则，满足的规则把动作放入执行队列
+pub async fn evaluate_rules(app: &AppHandle) {
+    let actions = match load_trigger_actions(app.clone()).await {
+        Ok(actions) => actions,
+        Err(e) => {
+            error!("加载触发动作失败: {}", e);
+            return;
+        }
+    };
+
+    reload_if_changed(app, &actions).await;
+
+    // 没有可用的规则时不采集系统状态
+    let needs = match RULE_ENGINE.lock() {
+        Ok(engine) if engine.rules.iter().any(|r| r.enabled && !engine.invalid.contains_key(&r.id)) => engine.needs(),
+        Ok(_) => return,
+        Err(_) => {
+            error!("获取规则引擎锁失败");
+            return;
+        }
+    };
+    let system = collect_system_state(needs).await;
+
+    let fired = {
+        let Ok(mut engine) = RULE_ENGINE.lock() else {
+            error!("获取规则引擎锁失败");
+            return;
+        };
+        let now = now_seconds();
+        let evaluations: Vec<(Rule, Evaluation)> = {
+            let ctx = build_context(&engine.history, engine.temperature, system, now);
+            engine
+                .rules
+                .iter()
+                .filter(|r| r.enabled && !engine.invalid.contains_key(&r.id))
+                .map(|rule| (rule.clone(), rule.condition.evaluate(&ctx)))
+                .collect()
+        };
+        evaluations
+            .into_iter()
+            .filter(|(rule, evaluation)| engine.should_fire(rule, evaluation.matched, now))
+            .collect::<Vec<_>>()
+    };
+
+    for (rule, evaluation) in fired {
+        info!("规则 {} 已触发，原因: {}", rule.name, evaluation.explanation);
+        let _ = app.emit(
+            "rule-fired",
+            json!({
+                "rule_id": rule.id,
+                "rule_name": rule.name,
+                "explanation": evaluation.explanation,
+            }),
+        );
+        run_rule_actions(&rule, &evaluation.explanation, &actions);
+    }
+}
+
+fn run_rule_actions(rule: &Rule, explanation: &str, actions: &[TriggerAction]) {
+    let trigger = JournalTrigger::Rule {
+        rule_id: rule.id.clone(),
+        rule_name: rule.name.clone(),
+        explanation: explanation.to_string(),
+    };
+    for rule_action in &rule.actions {
+        let action = match rule_action {
+            RuleAction::TriggerAction { action_id } => match actions.iter().find(|a| &a.id == action_id) {
+                Some(action) => action.clone(),
+                None => {
+                    warn!("规则 {} 引用的触发动作不存在: {}", rule.name, action_id);
+                    continue;
+                }
+            },
+            RuleAction::SwitchPlan { plan_guid } => TriggerAction::switch_plan(
+                format!("rule:{}:switch_plan:{}", rule.id, plan_guid),
+                format!("{}（切换电源计划）", rule.name),
+                plan_guid,
+            ),
+        };
+        if let Err(e) = ACTION_EXECUTOR.submit(action, trigger.clone()) {
+            warn!("{}", e);
+        }
+    }
+}
+
+// 单条规则当前的判断结果，用于在界面上解释规则为什么（没有）触发
+#[derive(Serialize, Clone, Debug)]
+pub struct RuleExplanation {
+    pub rule_id: String,
+    pub name: String,
+    pub enabled: bool,
+    pub error: Option<String>,
+    pub matched: bool,
+    pub explanation: String,
+}
+
+#[tauri::command]
+pub async fn load_rules() -> Result<Vec<Rule>, String> {
+    read_rules_file()
+}
+
+#[tauri::command]
+pub async fn save_rules(app: AppHandle, rules: Vec<Rule>) -> Result<(), String> {
+    let actions = load_trigger_actions(app).await?;
+    let invalid = validate_rules(&rules, &actions, &check_if_scheme_is_valid);
+    if let Some((_, reason)) = invalid.into_iter().next() {
+        return Err(reason);
+    }
+    write_rules_file(&rules)?;
+
+    // 下一个监控周期重新加载
+    if let Ok(mut engine) = RULE_ENGINE.lock() {
+        engine.loaded = false;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn explain_rules(app: AppHandle) -> Result<Vec<RuleExplanation>, String> {
+    let actions = load_trigger_actions(app.clone()).await?;
+    reload_if_changed(&app, &actions).await;
+
+    let system = collect_system_state(ContextNeeds {
+        active_plan: true,
+        power_source: true,
+        processes: true,
+    })
+    .await;
+    let engine = RULE_ENGINE.lock().map_err(|_| "获取规则引擎锁失败".to_string())?;
+    let ctx = build_context(&engine.history, engine.temperature, system, now_seconds());
+    Ok(engine
+        .rules
+        .iter()
+        .map(|rule| {
+            let evaluation = rule.condition.evaluate(&ctx);
+            RuleExplanation {
+                rule_id: rule.id.clone(),
+                name: rule.name.clone(),
+                enabled: rule.enabled,
+                error: engine.invalid.get(&rule.id).cloned(),
+                matched: evaluation.matched,
+                explanation: evaluation.explanation,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(history: &FrequencyHistory) -> RuleContext<'_> {
+        RuleContext {
+            now: 100,
+            minute_of_day: 23 * 60 + 30,
+            history,
+            active_plan_guid: Some("381B4222-F694-41F0-9685-FF5BB260DF2E".to_string()),
+            power_source: Some(PowerSource::Battery),
+            processes: ["game".to_string()].into_iter().collect(),
+            temperature: Some(72.5),
+        }
+    }
+
+    #[test]
+    fn test_parse_and_evaluate_combined_rule() {
+        let content = r#"{
+            "schema_version": 1,
+            "rules": [{
+                "id": "r1",
+                "name": "夜间电池高频",
+                "enabled": true,
+                "condition": {
+                    "type": "all",
+                    "conditions": [
+                        { "type": "frequency", "aggregate": "average", "window_seconds": 30, "comparison": "above", "ghz": 3.5 },
+                        { "type": "power_source", "source": "battery" },
+                        { "type": "time_of_day", "start": "22:00", "end": "06:00" },
+                        { "type": "not", "condition": { "type": "process_running", "name": "Game.exe" } }
+                    ]
+                },
+                "actions": [{ "type": "switch_plan", "plan_guid": "381B4222-F694-41F0-9685-FF5BB260DF2E" }]
+            }]
+        }"#;
+        let rules = parse_rules(content).unwrap();
+        let mut history = FrequencyHistory::default();
+        history.record(80, &[3800, 3600]);
+        history.record(100, &[3600, 3600]);
+
+        // 游戏进程正在运行，not 条件不满足
+        let evaluation = rules[0].condition.evaluate(&context(&history));
+        assert!(!evaluation.matched);
+        assert_eq!(evaluation.explanation, "进程 Game.exe 正在运行");
+
+        let mut ctx = context(&history);
+        ctx.processes.clear();
+        let evaluation = rules[0].condition.evaluate(&ctx);
+        assert!(evaluation.matched);
+        assert!(evaluation.explanation.starts_with("最近 30 秒平均频率 3.65 GHz 高于 3.50 GHz"));
+        assert!(evaluation.explanation.contains("当前时间 23:30 在 22:00-06:00 之间"));
+    }
+
+    #[test]
+    fn test_any_condition_explains_all_failures() {
+        let history = FrequencyHistory::default();
+        let condition = RuleCondition::Any {
+            conditions: vec![
+                RuleCondition::PowerSource { source: PowerSource::Ac },
+                RuleCondition::Frequency {
+                    aggregate: Aggregate::Max,
+                    window_seconds: None,
+                    comparison: Comparison::Above,
+                    ghz: 3.0,
+                },
+            ],
+        };
+        let evaluation = condition.evaluate(&context(&history));
+        assert!(!evaluation.matched);
+        assert_eq!(evaluation.explanation, "以下条件都不满足: 当前正在使用电池；当前最高频率没有数据");
+    }
+
+    #[test]
+    fn test_validate_rules() {
+        let rule: Rule = serde_json::from_value(json!({
+            "id": "r1",
+            "name": "规则",
+            "enabled": true,
+            "condition": { "type": "any", "conditions": [] },
+            "actions": [{ "type": "trigger_action", "action_id": "missing" }]
+        }))
+        .unwrap();
+        let plan_exists = |_: &str| true;
+
+        let invalid = validate_rules(std::slice::from_ref(&rule), &[], &plan_exists);
+        assert!(invalid[0].1.contains("引用的触发动作不存在"));
+
+        let action = TriggerAction {
+            id: "missing".to_string(),
+            ..Default::default()
+        };
+        let invalid = validate_rules(&[rule.clone(), rule], &[action], &plan_exists);
+        assert!(invalid[0].1.contains("组合条件至少需要一个子条件"));
+        assert!(invalid[1].1.contains("id 重复"));
+    }
+
+    #[test]
+    fn test_should_fire_on_rising_edge_and_cooldown() {
+        let mut engine = RuleEngine::default();
+        let mut rule: Rule = serde_json::from_value(json!({
+            "id": "r1",
+            "name": "规则",
+            "enabled": true,
+            "condition": { "type": "power_source", "source": "ac" },
+            "actions": []
+        }))
+        .unwrap();
+
+        assert!(engine.should_fire(&rule, true, 0));
+        assert!(!engine.should_fire(&rule, true, 100));
+        assert!(!engine.should_fire(&rule, false, 101));
+        assert!(engine.should_fire(&rule, true, 102));
+
+        rule.cooldown_seconds = Some(10);
+        assert!(!engine.should_fire(&rule, true, 105));
+        assert!(engine.should_fire(&rule, true, 112));
+    }
+
+    #[test]
+    fn test_temperature_condition() {
+        let history = FrequencyHistory::default();
+        let hot = RuleCondition::Temperature {
+            comparison: Comparison::Above,
+            celsius: 70.0,
+        };
+        let evaluation = hot.evaluate(&context(&history));
+        assert!(evaluation.matched);
+        assert_eq!(evaluation.explanation, "CPU 温度 72.5°C 高于 70.0°C");
+
+        let mut ctx = context(&history);
+        ctx.temperature = None;
+        assert!(!hot.evaluate(&ctx).matched);
+    }
+}
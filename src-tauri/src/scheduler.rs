@@ -0,0 +1,627 @@
+use chrono::{Datelike, Duration as ChronoDuration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use tauri::AppHandle;
+use tokio::time::{interval as tokio_interval, Duration};
+
+use crate::action_executor::ACTION_EXECUTOR;
+use crate::action_journal::JournalTrigger;
+use crate::notification::send_notification;
+use crate::power_plan::{check_if_scheme_is_valid, get_power_plans};
+use crate::trigger_action::{load_trigger_actions, TriggerAction};
+use crate::versioned_file::{exe_dir, VersionedFile};
+
+// schedules.json 的文件版本
+pub const SCHEDULES_SCHEMA_VERSION: u32 = 1;
+
+static SCHEDULES_FILE: VersionedFile = VersionedFile::new("schedules.json", "定时任务", SCHEDULES_SCHEMA_VERSION);
+
+// 检查定时任务的间隔
+const TICK_SECONDS: u64 = 30;
+
+// 两次检查间隔超过这个时长视为睡眠唤醒或程序重启，按补执行处理
+const CATCH_UP_GAP_SECONDS: i64 = 90;
+
+// 补执行最多回溯的时长，更早错过的任务不再执行
+const MAX_CATCH_UP_HOURS: i64 = 24;
+
+// 查找下一次执行时间时最多向后查找的天数，cron 表达式可能只在某些月份的某天执行
+const MAX_LOOKAHEAD_DAYS: i64 = 366;
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+// 到点后要做的事
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleAction {
+    SwitchPlan { plan_guid: String },
+    TriggerAction { action_id: String },
+}
+
+fn default_catch_up() -> bool {
+    true
+}
+
+// 类似 cron 的时间表达式，五个字段依次为：分 时 日 月 星期
+// 每个字段支持 *、列表（1,3）、范围（9-17）和步长（*/15、9-17/2），星期 0 和 7 都表示周日
+// 与 cron 相同，日和星期都有限制时满足其一即可
+#[derive(Clone, Debug, PartialEq)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+// 解析一个字段，返回按位表示的取值集合及该字段是否有限制（不以 * 开头）
+fn parse_cron_field(field: &str, name: &str, min: u32, max: u32) -> Result<(u64, bool), String> {
+    let parse_value = |value: &str| match value.parse::<u32>() {
+        Ok(v) if (min..=max).contains(&v) => Ok(v),
+        _ => Err(format!("{}应为 {}-{}，当前: {}", name, min, max, value)),
+    };
+
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, step),
+                _ => return Err(format!("{}的步长无效: {}", name, part)),
+            },
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (parse_value(start)?, parse_value(end)?),
+                // 带步长的单个值表示从该值开始到最大值，例如 5/15
+                None if step > 1 => (parse_value(range)?, max),
+                None => {
+                    let value = parse_value(range)?;
+                    (value, value)
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("{}的范围无效: {}", name, part));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok((bits, !field.starts_with('*')))
+}
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            return Err(format!("cron 表达式应包含 5 个字段（分 时 日 月 星期）: {}", expr));
+        };
+        let (minutes, _) = parse_cron_field(minutes, "分钟", 0, 59)?;
+        let (hours, _) = parse_cron_field(hours, "小时", 0, 23)?;
+        let (days, days_restricted) = parse_cron_field(days, "日期", 1, 31)?;
+        let (months, _) = parse_cron_field(months, "月份", 1, 12)?;
+        let (mut weekdays, weekdays_restricted) = parse_cron_field(weekdays, "星期", 0, 7)?;
+        // 7 和 0 都是周日
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes,
+            hours,
+            days,
+            months,
+            weekdays,
+            days_restricted,
+            weekdays_restricted,
+        })
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+
+    // 某天的所有执行时间，按先后排序
+    fn times_on(&self, date: NaiveDate) -> Vec<NaiveTime> {
+        if !self.matches_date(date) {
+            return Vec::new();
+        }
+        (0..24u32)
+            .filter(|h| self.hours & (1 << h) != 0)
+            .flat_map(|h| {
+                (0..60u32)
+                    .filter(|m| self.minutes & (1 << m) != 0)
+                    .filter_map(move |m| NaiveTime::from_hms_opt(h, m, 0))
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Schedule {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+    // 每天的执行时间，格式为 HH:MM，未设置 cron 时使用
+    #[serde(default)]
+    pub time: String,
+    // 在星期几执行，1 为周一、7 为周日，为空表示每天，未设置 cron 时使用
+    #[serde(default)]
+    pub weekdays: Vec<u32>,
+    // cron 表达式，例如 "*/30 9-18 * * 1-5"，设置后忽略 time 和 weekdays
+    #[serde(default)]
+    pub cron: Option<String>,
+    pub action: ScheduleAction,
+    // 睡眠或关机期间错过执行时间后，是否在唤醒时补执行
+    #[serde(default = "default_catch_up")]
+    pub catch_up: bool,
+}
+
+impl Schedule {
+    fn time_of_day(&self) -> Result<NaiveTime, String> {
+        NaiveTime::parse_from_str(&self.time, "%H:%M").map_err(|_| format!("时间格式应为 HH:MM: {}", self.time))
+    }
+
+    fn runs_on(&self, date: NaiveDate) -> bool {
+        self.weekdays.is_empty() || self.weekdays.contains(&date.weekday().number_from_monday())
+    }
+
+    // 某天的所有执行时间，按先后排序；时间格式无效时为空
+    fn times_on(&self, date: NaiveDate) -> Vec<NaiveTime> {
+        match &self.cron {
+            Some(cron) => CronExpr::parse(cron).map(|cron| cron.times_on(date)).unwrap_or_default(),
+            None => match self.time_of_day() {
+                Ok(time) if self.runs_on(date) => vec![time],
+                _ => Vec::new(),
+            },
+        }
+    }
+
+    // (from, to] 区间内最近的一次执行时间
+    pub fn last_occurrence_between(&self, from: NaiveDateTime, to: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut date = to.date();
+        while date >= from.date() {
+            let latest = self
+                .times_on(date)
+                .into_iter()
+                .rev()
+                .map(|time| date.and_time(time))
+                .find(|candidate| *candidate > from && *candidate <= to);
+            if latest.is_some() {
+                return latest;
+            }
+            date = date.pred_opt()?;
+        }
+        None
+    }
+
+    // after 之后的下一次执行时间
+    pub fn next_occurrence(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        (0..=MAX_LOOKAHEAD_DAYS)
+            .filter_map(|days| after.date().checked_add_signed(ChronoDuration::days(days)))
+            .find_map(|date| {
+                self.times_on(date)
+                    .into_iter()
+                    .map(|time| date.and_time(time))
+                    .find(|candidate| *candidate > after)
+            })
+    }
+
+    pub fn validate(&self, action_ids: &HashSet<&str>, plan_exists: &dyn Fn(&str) -> bool) -> Result<(), String> {
+        match &self.cron {
+            Some(cron) => {
+                CronExpr::parse(cron)?;
+            }
+            None => {
+                self.time_of_day()?;
+                if let Some(day) = self.weekdays.iter().find(|d| !(1..=7).contains(*d)) {
+                    return Err(format!("星期应为 1-7，当前: {}", day));
+                }
+            }
+        }
+        match &self.action {
+            ScheduleAction::SwitchPlan { plan_guid } if !plan_exists(plan_guid) => {
+                Err(format!("电源计划不存在: {}", plan_guid))
+            }
+            ScheduleAction::TriggerAction { action_id } if !action_ids.contains(action_id.as_str()) => {
+                Err(format!("引用的触发动作不存在: {}", action_id))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// 校验所有定时任务，返回第一个错误
+pub fn validate_schedules(
+    schedules: &[Schedule],
+    actions: &[TriggerAction],
+    plan_exists: &dyn Fn(&str) -> bool,
+) -> Result<(), String> {
+    let action_ids: HashSet<&str> = actions.iter().map(|a| a.id.as_str()).collect();
+    let mut seen = HashSet::new();
+    for schedule in schedules {
+        if !seen.insert(schedule.id.as_str()) {
+            return Err(format!("定时任务 {} 的 id 重复", schedule.name));
+        }
+        schedule
+            .validate(&action_ids, plan_exists)
+            .map_err(|e| format!("定时任务 {} 无效: {}", schedule.name, e))?;
+    }
+    Ok(())
+}
+
+// 时间来源，测试时替换为固定时间
+pub trait Clock {
+    fn now(&self) -> NaiveDateTime;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+}
+
+// 到期需要执行的定时任务
+#[derive(Clone, Debug)]
+pub struct DueSchedule {
+    pub schedule: Schedule,
+    pub scheduled_at: NaiveDateTime,
+    // 是否为唤醒或重启后的补执行
+    pub catch_up: bool,
+}
+
+pub struct Scheduler<C: Clock> {
+    clock: C,
+    last_checked: Option<NaiveDateTime>,
+}
+
+impl<C: Clock> Scheduler<C> {
+    pub fn new(clock: C, last_checked: Option<NaiveDateTime>) -> Self {
+        Self { clock, last_checked }
+    }
+
+    pub fn last_checked(&self) -> Option<NaiveDateTime> {
+        self.last_checked
+    }
+
+    // 返回上次检查之后到期的任务，按执行时间排序，每个任务最多返回一次
+    pub fn collect_due(&mut self, schedules: &[Schedule]) -> Vec<DueSchedule> {
+        let now = self.clock.now();
+        let Some(last_checked) = self.last_checked.replace(now) else {
+            // 第一次运行，不执行之前的任务
+            return Vec::new();
+        };
+        if now <= last_checked {
+            // 系统时间被往回调整，从当前时间重新开始
+            return Vec::new();
+        }
+
+        let catch_up = (now - last_checked).num_seconds() > CATCH_UP_GAP_SECONDS;
+        let from = if catch_up {
+            last_checked.max(now - ChronoDuration::hours(MAX_CATCH_UP_HOURS))
+        } else {
+            last_checked
+        };
+
+        let mut due: Vec<DueSchedule> = schedules
+            .iter()
+            .filter(|s| s.enabled && (!catch_up || s.catch_up))
+            .filter_map(|s| {
+                s.last_occurrence_between(from, now).map(|scheduled_at| DueSchedule {
+                    schedule: s.clone(),
+                    scheduled_at,
+                    catch_up,
+                })
+            })
+            .collect();
+        // 先执行较早的任务，使最后切换的电源计划与时间表一致
+        due.sort_by_key(|d| d.scheduled_at);
+        due
+    }
+}
+
+// 记录上次检查时间，重启后据此补执行
+fn get_schedule_state_path() -> PathBuf {
+    exe_dir().join("schedule_state.json")
+}
+
+pub fn parse_schedules(content: &str) -> Result<Vec<Schedule>, String> {
+    SCHEDULES_FILE.parse_field(content, "schedules")
+}
+
+fn read_schedules_file() -> Result<Vec<Schedule>, String> {
+    SCHEDULES_FILE.read_field("schedules")
+}
+
+fn read_last_checked() -> Option<NaiveDateTime> {
+    let content = fs::read_to_string(get_schedule_state_path()).ok()?;
+    let value: serde_json::Value = serde_json::from_str(&content).ok()?;
+    NaiveDateTime::parse_from_str(value.get("last_checked")?.as_str()?, TIME_FORMAT).ok()
+}
+
+fn write_last_checked(last_checked: NaiveDateTime) {
+    let content = json!({ "last_checked": last_checked.format(TIME_FORMAT).to_string() }).to_string();
+    if let Err(e) = fs::write(get_schedule_state_path(), content) {
+        warn!("保存定时任务状态失败: {}", e);
+    }
+}
+
+fn plan_display_name(guid: &str) -> String {
+    get_power_plans()
+        .ok()
+        .and_then(|plans| plans.into_iter().find(|p| p.guid.eq_ignore_ascii_case(guid)))
+        .map(|p| p.name)
+        .unwrap_or_else(|| guid.to_string())
+}
+
+async fn run_due_schedule(app: &AppHandle, due: &DueSchedule) {
+    let schedule = &due.schedule;
+    let prefix = if due.catch_up {
+        format!("（补执行 {} 的任务）", due.scheduled_at.format("%m-%d %H:%M"))
+    } else {
+        String::new()
+    };
+    info!("定时任务 {} 到期{}", schedule.name, prefix);
+
+    let trigger = JournalTrigger::Schedule {
+        schedule_id: schedule.id.clone(),
+        schedule_name: schedule.name.clone(),
+        scheduled_at: due.scheduled_at.format(TIME_FORMAT).to_string(),
+    };
+    // 切换计划也交给执行队列，不会和正在执行的触发动作同时修改电源计划
+    let submitted = match &schedule.action {
+        ScheduleAction::SwitchPlan { plan_guid } => {
+            let guid = plan_guid.clone();
+            let plan_name = tauri::async_runtime::spawn_blocking(move || plan_display_name(&guid))
+                .await
+                .unwrap_or_else(|_| plan_guid.clone());
+            let action = TriggerAction::switch_plan(
+                format!("schedule:{}:switch_plan:{}", schedule.id, plan_guid),
+                format!("{}（切换电源计划）", schedule.name),
+                plan_guid,
+            );
+            ACTION_EXECUTOR
+                .submit_with_result(action, trigger)
+                .map(|done| (done, format!("已切换到电源计划 {}", plan_name)))
+        }
+        ScheduleAction::TriggerAction { action_id } => match load_trigger_actions(app.clone()).await {
+            Ok(actions) => match actions.into_iter().find(|a| &a.id == action_id) {
+                Some(action) => {
+                    let name = action.name.clone();
+                    ACTION_EXECUTOR
+                        .submit_with_result(action, trigger)
+                        .map(|done| (done, format!("已执行触发动作 {}", name)))
+                }
+                None => Err(format!("引用的触发动作不存在: {}", action_id)),
+            },
+            Err(e) => Err(e),
+        },
+    };
+
+    let (done, message) = match submitted {
+        Ok(submitted) => submitted,
+        Err(e) => return notify_schedule_result(&schedule.name, &prefix, Err(e)),
+    };
+    // 在后台等待执行结束再通知，不阻塞后续定时任务的检查
+    let name = schedule.name.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = done
+            .await
+            .unwrap_or_else(|_| Err("动作在执行前被移出了执行队列".to_string()));
+        notify_schedule_result(&name, &prefix, result.map(|()| message));
+    });
+}
+
+fn notify_schedule_result(name: &str, prefix: &str, result: Result<String, String>) {
+    match result {
+        Ok(message) => {
+            info!("定时任务 {} 执行成功: {}", name, message);
+            let _ = send_notification("定时任务已执行", &format!("{}: {}{}", name, message, prefix));
+        }
+        Err(e) => {
+            error!("定时任务 {} 执行失败: {}", name, e);
+            let _ = send_notification("定时任务执行失败", &format!("{}: {}{}", name, e, prefix));
+        }
+    }
+}
+
+// 启动定时任务检查，上次检查时间保存在文件中，重启后可以补执行
+pub fn start_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut scheduler = Scheduler::new(SystemClock, read_last_checked());
+        let mut interval = tokio_interval(Duration::from_secs(TICK_SECONDS));
+        info!("定时任务已启动");
+
+        loop {
+            interval.tick().await;
+            // 每次都重新读取，保存后无需通知
+            let schedules = match read_schedules_file() {
+                Ok(schedules) => schedules,
+                Err(e) => {
+                    error!("加载定时任务失败: {}", e);
+                    continue;
+                }
+            };
+            for due in scheduler.collect_due(&schedules) {
+                run_due_schedule(&app, &due).await;
+            }
+            if let Some(last_checked) = scheduler.last_checked() {
+                write_last_checked(last_checked);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn load_schedules() -> Result<Vec<Schedule>, String> {
+    read_schedules_file()
+}
+
+#[tauri::command]
+pub async fn save_schedules(app: AppHandle, schedules: Vec<Schedule>) -> Result<(), String> {
+    let actions = load_trigger_actions(app).await?;
+    validate_schedules(&schedules, &actions, &check_if_scheme_is_valid)?;
+    SCHEDULES_FILE.write(json!({ "schedules": schedules }))
+}
+
+// 各定时任务的下一次执行时间，用于界面显示
+#[tauri::command]
+pub async fn get_schedule_next_runs() -> Result<Vec<serde_json::Value>, String> {
+    let now = SystemClock.now();
+    Ok(read_schedules_file()?
+        .iter()
+        .map(|s| {
+            json!({
+                "schedule_id": s.id,
+                "next_run": s
+                    .enabled
+                    .then(|| s.next_occurrence(now))
+                    .flatten()
+                    .map(|t| t.format(TIME_FORMAT).to_string()),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[derive(Clone)]
+    struct FakeClock(Rc<Cell<NaiveDateTime>>);
+
+    impl Clock for FakeClock {
+        fn now(&self) -> NaiveDateTime {
+            self.0.get()
+        }
+    }
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, TIME_FORMAT).unwrap()
+    }
+
+    fn schedule(id: &str, time: &str, weekdays: Vec<u32>) -> Schedule {
+        Schedule {
+            id: id.to_string(),
+            name: id.to_string(),
+            enabled: true,
+            time: time.to_string(),
+            weekdays,
+            cron: None,
+            action: ScheduleAction::SwitchPlan {
+                plan_guid: "a1841308-3541-4fab-bc81-f71556f20b4a".to_string(),
+            },
+            catch_up: true,
+        }
+    }
+
+    #[test]
+    fn test_fires_once_on_matching_weekday() {
+        // 2026-10-16 为周五
+        let clock = FakeClock(Rc::new(Cell::new(at("2026-10-16 17:59:40"))));
+        let mut scheduler = Scheduler::new(clock.clone(), None);
+        let schedules = vec![schedule("evening", "18:00", vec![1, 2, 3, 4, 5]), schedule("weekend", "18:00", vec![6, 7])];
+
+        assert!(scheduler.collect_due(&schedules).is_empty());
+        clock.0.set(at("2026-10-16 18:00:10"));
+        let due = scheduler.collect_due(&schedules);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].schedule.id, "evening");
+        assert!(!due[0].catch_up);
+
+        clock.0.set(at("2026-10-16 18:00:40"));
+        assert!(scheduler.collect_due(&schedules).is_empty());
+    }
+
+    #[test]
+    fn test_catch_up_after_wake_runs_latest_occurrence_in_order() {
+        let clock = FakeClock(Rc::new(Cell::new(at("2026-10-19 07:00:00"))));
+        // 周五 12:00 进入睡眠，周一 07:00 唤醒
+        let mut scheduler = Scheduler::new(clock, Some(at("2026-10-16 12:00:00")));
+        let mut skipped = schedule("no_catch_up", "20:00", vec![]);
+        skipped.catch_up = false;
+        let schedules = vec![schedule("morning", "06:30", vec![]), schedule("night", "22:00", vec![]), skipped];
+
+        let due = scheduler.collect_due(&schedules);
+        let runs: Vec<(&str, NaiveDateTime)> = due.iter().map(|d| (d.schedule.id.as_str(), d.scheduled_at)).collect();
+        // 只回溯 24 小时，每个任务只补执行最近一次，且按时间先后执行
+        assert_eq!(
+            runs,
+            vec![("night", at("2026-10-18 22:00:00")), ("morning", at("2026-10-19 06:30:00"))]
+        );
+        assert!(due.iter().all(|d| d.catch_up));
+    }
+
+    #[test]
+    fn test_next_occurrence_and_validation() {
+        let weekdays = schedule("weekdays", "08:30", vec![1, 2, 3, 4, 5]);
+        assert_eq!(
+            weekdays.next_occurrence(at("2026-10-16 09:00:00")),
+            Some(at("2026-10-19 08:30:00"))
+        );
+
+        let plan_exists = |_: &str| true;
+        assert!(validate_schedules(std::slice::from_ref(&weekdays), &[], &plan_exists).is_ok());
+        assert!(validate_schedules(&[schedule("bad", "25:00", vec![])], &[], &plan_exists).is_err());
+        assert!(validate_schedules(&[schedule("bad", "08:00", vec![0])], &[], &plan_exists).is_err());
+        assert!(validate_schedules(&[weekdays.clone(), weekdays], &[], &plan_exists).is_err());
+    }
+
+    fn cron_schedule(id: &str, cron: &str) -> Schedule {
+        Schedule {
+            cron: Some(cron.to_string()),
+            ..schedule(id, "", vec![])
+        }
+    }
+
+    #[test]
+    fn test_cron_ranges_and_steps() {
+        // 工作日 9:00-17:59 每 30 分钟一次
+        let work_hours = cron_schedule("work", "*/30 9-17 * * 1-5");
+        assert_eq!(work_hours.next_occurrence(at("2026-10-16 09:10:00")), Some(at("2026-10-16 09:30:00")));
+        // 周五 17:30 之后跳到周一 9:00
+        assert_eq!(work_hours.next_occurrence(at("2026-10-16 17:30:00")), Some(at("2026-10-19 09:00:00")));
+        assert_eq!(
+            work_hours.last_occurrence_between(at("2026-10-16 10:05:00"), at("2026-10-16 11:10:00")),
+            Some(at("2026-10-16 11:00:00"))
+        );
+
+        // 日和星期都有限制时满足其一即可：每月 1 日或每个周日
+        let first_or_sunday = cron_schedule("first", "0 8 1 * 0");
+        assert_eq!(first_or_sunday.next_occurrence(at("2026-10-16 12:00:00")), Some(at("2026-10-18 08:00:00")));
+        assert_eq!(first_or_sunday.next_occurrence(at("2026-11-29 12:00:00")), Some(at("2026-12-01 08:00:00")));
+
+        // 列表、带步长的范围，星期 7 等同于 0
+        let list = CronExpr::parse("0,15 8-20/6 * * 7").unwrap();
+        let times: Vec<String> = list
+            .times_on(NaiveDate::from_ymd_opt(2026, 10, 18).unwrap())
+            .iter()
+            .map(|t| t.format("%H:%M").to_string())
+            .collect();
+        assert_eq!(times, vec!["08:00", "08:15", "14:00", "14:15", "20:00", "20:15"]);
+
+        let plan_exists = |_: &str| true;
+        for bad in ["* * * *", "60 * * * *", "*/0 * * * *", "0 17-9 * * *", "0 8 * 13 *"] {
+            assert!(validate_schedules(&[cron_schedule("bad", bad)], &[], &plan_exists).is_err(), "{}", bad);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use crate::power_source::PowerSourceProfiles;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
@@ -13,6 +14,11 @@ pub struct Settings {
     pub frequency_detection_enabled: bool,
     pub alert_debounce_seconds: u64,
     pub accepted_terms_of_service: u64,
+    pub trigger_dispatch_policy: String,
+    // 按供电方式覆盖频率阈值和刷新间隔
+    pub power_source_profiles: PowerSourceProfiles,
+    // 试运行：触发动作和电源计划修改只记录不执行
+    pub dry_run_enabled: bool,
 }
 
 impl Default for Settings {
@@ -29,6 +35,9 @@ impl Default for Settings {
             frequency_detection_enabled: false,
             alert_debounce_seconds: 10,
             accepted_terms_of_service: 0,
+            trigger_dispatch_policy: "first_match".to_string(),
+            power_source_profiles: PowerSourceProfiles::default(),
+            dry_run_enabled: false,
         }
     }
 }
@@ -10,8 +10,12 @@ use tauri::{AppHandle, WebviewWindow, Manager,Emitter};
 use crate::notification::send_notification;
 use serde_json::json;
 use crate::trigger_action;
-use std::sync::atomic::{AtomicBool, Ordering};
+use crate::power_source::{self, PowerSourceProfiles};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
 use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 
 // 定义全局变量
 static SETTINGS_STORE: OnceCell<SettingsStore> = OnceCell::new();
@@ -20,12 +24,63 @@ static SETTINGS_MUTEX: Lazy<Mutex<Settings>> = Lazy::new(|| Mutex::new(Settings:
 // 添加一个静态变量来控制频率模式是否持久化
 static SKIP_FREQUENCY_MODE_PERSIST: AtomicBool = AtomicBool::new(false);
 
+// 设置变化事件，携带旧值和新值
+#[derive(Debug, Clone)]
+pub struct SettingChange {
+    pub key: String,
+    pub old_value: serde_json::Value,
+    pub new_value: serde_json::Value,
+}
+
 // 定义钩子函数类型
-type HookFunction = Box<dyn Fn(&str, &serde_json::Value) + Send + 'static>;
+type HookFunction = Arc<dyn Fn(&SettingChange) + Send + Sync + 'static>;
+type AsyncHookFunction =
+    Arc<dyn Fn(SettingChange) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync + 'static>;
+type ValidatorFunction = Arc<dyn Fn(&SettingChange) -> Result<(), String> + Send + Sync + 'static>;
+
+#[derive(Clone)]
+enum SettingHook {
+    // 提交前调用，返回错误即否决本次修改
+    Validator(ValidatorFunction),
+    // 提交后同步调用
+    Sync(HookFunction),
+    // 提交后在异步运行时中调用
+    Async(AsyncHookFunction),
+}
+
+struct RegisteredHook {
+    id: u64,
+    hook: SettingHook,
+}
 
 // 存储钩子的全局变量
-static SETTING_HOOKS: Lazy<Mutex<HashMap<String, Vec<HookFunction>>>> = 
+static SETTING_HOOKS: Lazy<Mutex<HashMap<String, Vec<RegisteredHook>>>> = 
     Lazy::new(|| Mutex::new(HashMap::new()));
+static NEXT_HOOK_ID: AtomicU64 = AtomicU64::new(1);
+
+// 钩子句柄，被丢弃时自动注销对应的钩子
+#[must_use = "句柄被丢弃时钩子会立即注销，如需永久保留请调用 detach()"]
+pub struct SettingHookHandle {
+    key: String,
+    id: u64,
+}
+
+impl SettingHookHandle {
+    // 放弃句柄但保留钩子，钩子将在程序运行期间一直有效
+    pub fn detach(self) {
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for SettingHookHandle {
+    fn drop(&mut self) {
+        if let Ok(mut hooks) = SETTING_HOOKS.lock() {
+            if let Some(key_hooks) = hooks.get_mut(&self.key) {
+                key_hooks.retain(|h| h.id != self.id);
+            }
+        }
+    }
+}
 
 pub struct SettingsStore {
     settings_path: PathBuf,
@@ -70,9 +125,13 @@ impl SettingsStore {
 
         let content = fs::read_to_string(path)
             .map_err(|e| format!("读取设置文件失败: {}", e))?;
-        
+        Self::parse_settings(&content)
+    }
+
+    // 解析设置文件内容，缺失的字段使用默认值
+    fn parse_settings(content: &str) -> Result<Settings, String> {
         // 先解析成 Value，这样我们可以检查和修复缺失的字段
-        let mut settings_value: serde_json::Value = serde_json::from_str(&content)
+        let mut settings_value: serde_json::Value = serde_json::from_str(content)
             .map_err(|e| format!("解析设置失败: {}", e))?;
         
         // 获取默认设置
@@ -93,6 +152,9 @@ impl SettingsStore {
                 ("frequency_detection_enabled", json!(default_settings.frequency_detection_enabled)),
                 ("alert_debounce_seconds", json!(default_settings.alert_debounce_seconds)),
                 ("accepted_terms_of_service", json!(default_settings.accepted_terms_of_service)),   
+                ("trigger_dispatch_policy", json!(default_settings.trigger_dispatch_policy)),
+                ("power_source_profiles", json!(default_settings.power_source_profiles)),
+                ("dry_run_enabled", json!(default_settings.dry_run_enabled)),
             ];
 
             for (key, default_value) in fields.iter() {
@@ -130,8 +192,9 @@ impl SettingsStore {
         let json = serde_json::to_string_pretty(&settings_to_save)
             .map_err(|e| format!("序列化设置失败: {}", e))?;
         
-        fs::write(&self.settings_path, json)
+        fs::write(&self.settings_path, &json)
             .map_err(|e| format!("保存设置失败: {}", e))?;
+        crate::config_watcher::record_app_write(&self.settings_path, &json);
         
         Ok(())
     }
@@ -143,28 +206,16 @@ impl SettingsStore {
     }
 
     pub fn set_trigger_action_master_switch(&self, enabled: bool) -> Result<(), String> {
-        let mut settings = SETTINGS_MUTEX.lock()
-            .map_err(|_| "获取设置锁失败".to_string())?;
-        
-        settings.trigger_action_enabled = enabled;
-        self.save_to_file(&settings)?;
-        
-        // 通知前端
-        self.notify_settings_changed(&settings);
-        
-        Ok(())
+        self.set_setting("trigger_action_enabled", enabled)
     }
 
-    pub fn update_settings(&mut self, new_settings: Settings) -> Result<(), String> {
-        let mut settings = SETTINGS_MUTEX.lock()
-            .map_err(|_| "获取设置锁失败".to_string())?;
-        *settings = new_settings;
-        self.save_to_file(&settings)?;
-        
-        // 通知前端
-        self.notify_settings_changed(&settings);
-        
-        Ok(())
+    // 整体更新设置时只提交变化的设置项，每一项都经过校验和钩子
+    pub fn update_settings(&self, new_settings: Settings) -> Result<(), String> {
+        let values = changed_values(&get_settings()?, &new_settings)?;
+        if values.is_empty() {
+            return Ok(());
+        }
+        self.set_values(values)
     }
 
     // 其他便捷方法...
@@ -198,7 +249,24 @@ impl SettingsStore {
     // 将 has_valid_actions 改为异步方法
     async fn has_valid_actions(&self) -> Result<bool, String> {
         let actions = crate::trigger_action::load_trigger_actions(self.app.clone()).await?;
-        Ok(actions.iter().any(|action| action.enabled))
+        Ok(!crate::trigger_action::eligible_actions(&actions).is_empty())
+    }
+
+    // 打开总开关前确认至少有一个动作可以被派发
+    fn check_eligible_actions(&self) -> Result<(), String> {
+        let eligible_count = crate::trigger_action::get_trigger_action_eligible_count(&self.app.clone()).unwrap_or(0);
+        if eligible_count == 0 {
+            return Err("没有可执行的触发动作，请先添加并启用至少一个有效的触发动作".to_string());
+        }
+        Ok(())
+    }
+
+    // 打开总开关前的检查需要读取触发动作文件，必须在获取设置锁之前调用
+    fn check_enabling(&self, key: &str, value: &serde_json::Value) -> Result<(), String> {
+        if key == "trigger_action_enabled" && value.as_bool().unwrap_or(false) {
+            self.check_eligible_actions()?;
+        }
+        Ok(())
     }
 
     // validate_and_update_setting 也需要改为异步
@@ -318,13 +386,53 @@ impl SettingsStore {
 
     // 添加一个通用的 setter 方法
     pub fn set_setting<T: serde::Serialize>(&self, key: &str, value: T) -> Result<(), String> {
-        let mut settings = SETTINGS_MUTEX.lock()
-            .map_err(|_| "获取设置锁失败".to_string())?;
-        
         // 将值序列化为 JSON Value
         let value = serde_json::to_value(value)
             .map_err(|_| "序列化值失败".to_string())?;
-        
+        self.set_values(vec![(key.to_string(), value)])
+    }
+
+    // 所有写入设置的入口最终都走这里：先做需要读取文件的检查，再提交、保存并通知前端
+    fn set_values(&self, values: Vec<(String, serde_json::Value)>) -> Result<(), String> {
+        for (key, value) in &values {
+            self.check_enabling(key, value)?;
+        }
+        Self::commit_values(&SETTINGS_MUTEX, values, |settings| {
+            self.save_to_file(settings)?;
+            self.notify_settings_changed(settings);
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    // 在设置锁内逐项调用校验钩子，全部通过且保存成功后才整体生效，释放锁后再触发钩子
+    fn commit_values<F>(
+        settings_lock: &Mutex<Settings>,
+        values: Vec<(String, serde_json::Value)>,
+        persist: F,
+    ) -> Result<Vec<SettingChange>, String>
+    where
+        F: FnOnce(&Settings) -> Result<(), String>,
+    {
+        let mut settings = settings_lock.lock()
+            .map_err(|_| "获取设置锁失败".to_string())?;
+        let mut candidate = settings.clone();
+        let changes = values
+            .into_iter()
+            .map(|(key, value)| Self::stage_change(&mut candidate, &key, value))
+            .collect::<Result<Vec<_>, _>>()?;
+        persist(&candidate)?;
+        *settings = candidate;
+        drop(settings);
+
+        for change in &changes {
+            trigger_hooks(change);
+        }
+        Ok(changes)
+    }
+
+    // 校验并把单个设置项写入给定的 Settings，不负责保存，也不做需要读取文件的检查
+    fn apply_setting(settings: &mut Settings, key: &str, value: &serde_json::Value) -> Result<(), String> {
         // 根据字段名更新对应的值
         match key {
             "auto_start" => {
@@ -369,12 +477,7 @@ impl SettingsStore {
                 //     return Err("模拟出故障".to_string());
                 // }
 
-                //检查是否大于等于启用的动作个数
-                let enabled_count = crate::trigger_action::get_trigger_action_count(&self.app.clone()).unwrap_or(0);
-                if value.as_bool().unwrap_or(false) && enabled_count == 0 {
-                    return Err("没有可用的触发动作，请先去添加至少一个触发动作".to_string());
-                }
-
+                //是否有可以被派发的动作（已启用且校验通过）由 check_enabling 在加锁前检查
                 settings.trigger_action_enabled = value.as_bool()
                     .ok_or("无效的值类型")?;
             },
@@ -395,16 +498,83 @@ impl SettingsStore {
                 settings.accepted_terms_of_service = value.as_u64()
                     .ok_or("无效的值类型")?;
             },
+            "trigger_dispatch_policy" => {
+                let policy = value.as_str()
+                    .ok_or("无效的值类型")?;
+                crate::trigger_action::DispatchPolicy::from_setting(policy)?;
+                settings.trigger_dispatch_policy = policy.to_string();
+            },
+            "power_source_profiles" => {
+                let profiles: PowerSourceProfiles = serde_json::from_value(value.clone())
+                    .map_err(|e| format!("无效的供电方式设置: {}", e))?;
+                profiles.validate()?;
+                settings.power_source_profiles = profiles;
+            },
+            "dry_run_enabled" => {
+                settings.dry_run_enabled = value.as_bool()
+                    .ok_or("无效的值类型")?;
+            },
             _ => return Err(format!("未知的设置项: {}", key))
         }
 
-        // 保存并通知
-        self.save_to_file(&settings)?;
-        self.notify_settings_changed(&settings);
-        
         Ok(())
     }
 
+    // 按文件被外部修改后的内容重新加载设置，内容由调用方读取，避免再次读取时文件已经变化
+    // 所有变化的设置项都通过 apply_setting 校验，任何一项失败则整体拒绝，不会部分生效
+    pub fn reload_from_content(&self, content: &str) -> Result<Vec<String>, String> {
+        let file_settings = Self::parse_settings(content)?;
+        let current = get_settings()?;
+
+        let skip_frequency_mode = SKIP_FREQUENCY_MODE_PERSIST.load(Ordering::SeqCst);
+        let mut changes = Vec::new();
+        for (key, new_value) in changed_values(&current, &file_settings)? {
+            // 频率模式不持久化时，文件里的旧值不代表外部修改
+            if skip_frequency_mode && key == "frequency_mode" {
+                continue;
+            }
+            changes.push(SettingChange {
+                old_value: setting_value(&current, &key)?,
+                key,
+                new_value,
+            });
+        }
+
+        if changes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // 校验时不持有设置锁，打开总开关的检查需要读取触发动作文件
+        let mut candidate = current;
+        for change in &changes {
+            Self::apply_setting(&mut candidate, &change.key, &change.new_value)
+                .and_then(|()| self.check_enabling(&change.key, &change.new_value))
+                .map_err(|e| format!("设置项 {} 无效: {}", change.key, e))?;
+            run_validators(change)
+                .map_err(|e| format!("设置项 {} 被拒绝: {}", change.key, e))?;
+        }
+
+        // 只写入变化的设置项，校验期间程序自己修改的其他设置项不会被覆盖
+        let mut settings = SETTINGS_MUTEX.lock()
+            .map_err(|_| "获取设置锁失败".to_string())?;
+        for change in &changes {
+            Self::apply_setting(&mut settings, &change.key, &change.new_value)?;
+        }
+        self.notify_settings_changed(&settings);
+        drop(settings);
+
+        for change in &changes {
+            info!("设置文件外部修改: {} = {}", change.key, change.new_value);
+            trigger_hooks(change);
+        }
+
+        Ok(changes.into_iter().map(|change| change.key).collect())
+    }
+
+    pub(crate) fn settings_path(&self) -> &PathBuf {
+        &self.settings_path
+    }
+
     // pub fn set_trigger_action_enabled(&self, enabled: bool) -> Result<(), String> {
     //     self.set_setting("trigger_action_enabled", enabled)
     // }
@@ -425,36 +595,30 @@ impl SettingsStore {
         //     .map_err(|_| "获取设置锁失败".to_string())?;
 
         info!("验证和更新设置: {} = {}", key, value);
-        self.set_setting(key, value.clone())?;
-
-        // 在更新前触发钩子
-        self.trigger_hooks(key, &value);
+        // 读取旧值和写入新值在同一次加锁中完成，并发修改时事件里的旧值不会过时
+        self.set_values(vec![(key.to_string(), value)])
+    }
 
-        // ... 现有的更新逻辑 ...
+    // 在已加锁的设置上记录旧值、调用校验钩子并写入新值，返回变化事件
+    // 校验钩子在持有设置锁时调用，不能再通过设置存储读写设置，需要的值都在变化事件中
+    fn stage_change(settings: &mut Settings, key: &str, value: serde_json::Value) -> Result<SettingChange, String> {
+        let change = SettingChange {
+            key: key.to_string(),
+            old_value: setting_value(settings, key)?,
+            new_value: value,
+        };
 
-        Ok(())
+        // 任何一个校验钩子否决都不会写入
+        run_validators(&change)?;
+        Self::apply_setting(settings, key, &change.new_value)?;
+        Ok(change)
     }
 
     // 添加一个通用的 getter 方法
     pub fn get_setting(&self, key: &str) -> Result<serde_json::Value, String> {
         let settings = SETTINGS_MUTEX.lock()
             .map_err(|_| "获取设置锁失败".to_string())?;
-        
-        match key {
-            "auto_start" => Ok(serde_json::Value::Bool(settings.auto_start)),
-            "auto_minimize" => Ok(serde_json::Value::Bool(settings.auto_minimize)),
-            "refresh_interval" => Ok(serde_json::Value::Number(settings.refresh_interval.into())),
-            "frequency_threshold" => Ok(serde_json::Value::Number(serde_json::Number::from_f64(settings.frequency_threshold)
-                .ok_or("转换频率阈值失败")?)),
-            "frequency_mode" => Ok(serde_json::Value::String(settings.frequency_mode.clone())),
-            "auto_switch_enabled" => Ok(serde_json::Value::Bool(settings.auto_switch_enabled)),
-            "auto_switch_threshold" => Ok(serde_json::Value::Number(settings.auto_switch_threshold.into())),
-            "trigger_action_enabled" => Ok(serde_json::Value::Bool(settings.trigger_action_enabled)),
-            "frequency_detection_enabled" => Ok(serde_json::Value::Bool(settings.frequency_detection_enabled)),
-            "alert_debounce_seconds" => Ok(serde_json::Value::Number(settings.alert_debounce_seconds.into())),
-            "accepted_terms_of_service" => Ok(serde_json::Value::Number(settings.accepted_terms_of_service.into())),
-            _ => Err(format!("未知的设置项: {}", key))
-        }
+        setting_value(&settings, key)
     }
 
     // 添加控制持久化的方法
@@ -462,24 +626,89 @@ impl SettingsStore {
         SKIP_FREQUENCY_MODE_PERSIST.store(skip, Ordering::SeqCst);
     }
 
-    // 添加注册钩子的方法
-    pub fn add_setting_hook<F>(&self, key: &str, hook: F) 
-    where
-        F: Fn(&str, &serde_json::Value) + Send + 'static
-    {
-        let mut hooks = SETTING_HOOKS.lock().unwrap();
-        hooks.entry(key.to_string())
-            .or_insert_with(Vec::new)
-            .push(Box::new(hook));
-    }
-
-    // 在更新设置时调用钩子
-    fn trigger_hooks(&self, key: &str, value: &serde_json::Value) {
-        if let Ok(hooks) = SETTING_HOOKS.lock() {
-            if let Some(key_hooks) = hooks.get(key) {
-                for hook in key_hooks {
-                    hook(key, value);
-                }
+}
+
+// 读取单个设置项的值
+fn setting_value(settings: &Settings, key: &str) -> Result<serde_json::Value, String> {
+    match key {
+        "auto_start" => Ok(serde_json::Value::Bool(settings.auto_start)),
+        "auto_minimize" => Ok(serde_json::Value::Bool(settings.auto_minimize)),
+        "refresh_interval" => Ok(serde_json::Value::Number(settings.refresh_interval.into())),
+        "frequency_threshold" => Ok(serde_json::Value::Number(serde_json::Number::from_f64(settings.frequency_threshold)
+            .ok_or("转换频率阈值失败")?)),
+        "frequency_mode" => Ok(serde_json::Value::String(settings.frequency_mode.clone())),
+        "auto_switch_enabled" => Ok(serde_json::Value::Bool(settings.auto_switch_enabled)),
+        "auto_switch_threshold" => Ok(serde_json::Value::Number(settings.auto_switch_threshold.into())),
+        "trigger_action_enabled" => Ok(serde_json::Value::Bool(settings.trigger_action_enabled)),
+        "frequency_detection_enabled" => Ok(serde_json::Value::Bool(settings.frequency_detection_enabled)),
+        "alert_debounce_seconds" => Ok(serde_json::Value::Number(settings.alert_debounce_seconds.into())),
+        "accepted_terms_of_service" => Ok(serde_json::Value::Number(settings.accepted_terms_of_service.into())),
+        "trigger_dispatch_policy" => Ok(serde_json::Value::String(settings.trigger_dispatch_policy.clone())),
+        "power_source_profiles" => serde_json::to_value(&settings.power_source_profiles)
+            .map_err(|e| format!("转换供电方式设置失败: {}", e)),
+        "dry_run_enabled" => Ok(serde_json::Value::Bool(settings.dry_run_enabled)),
+        _ => Err(format!("未知的设置项: {}", key))
+    }
+}
+
+// 比较两份设置，返回值不同的设置项及其新值
+fn changed_values(current: &Settings, new: &Settings) -> Result<Vec<(String, serde_json::Value)>, String> {
+    let current_value = serde_json::to_value(current)
+        .map_err(|e| format!("序列化设置失败: {}", e))?;
+    let new_value = serde_json::to_value(new)
+        .map_err(|e| format!("序列化设置失败: {}", e))?;
+
+    let mut changed = Vec::new();
+    if let (Some(new_obj), Some(current_obj)) = (new_value.as_object(), current_value.as_object()) {
+        for (key, value) in new_obj {
+            if current_obj.get(key) != Some(value) {
+                changed.push((key.clone(), value.clone()));
+            }
+        }
+    }
+    Ok(changed)
+}
+
+fn register_hook(key: &str, hook: SettingHook) -> SettingHookHandle {
+    let id = NEXT_HOOK_ID.fetch_add(1, Ordering::SeqCst);
+    let mut hooks = SETTING_HOOKS.lock().unwrap();
+    hooks.entry(key.to_string())
+        .or_default()
+        .push(RegisteredHook { id, hook });
+    SettingHookHandle {
+        key: key.to_string(),
+        id,
+    }
+}
+
+// 先复制出钩子再调用，避免钩子内部注册或注销钩子时死锁
+fn hooks_for(key: &str) -> Vec<SettingHook> {
+    match SETTING_HOOKS.lock() {
+        Ok(hooks) => hooks
+            .get(key)
+            .map(|key_hooks| key_hooks.iter().map(|h| h.hook.clone()).collect())
+            .unwrap_or_default(),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn run_validators(change: &SettingChange) -> Result<(), String> {
+    for hook in hooks_for(&change.key) {
+        if let SettingHook::Validator(validator) = hook {
+            validator(change)?;
+        }
+    }
+    Ok(())
+}
+
+// 在设置更新后调用钩子
+fn trigger_hooks(change: &SettingChange) {
+    for hook in hooks_for(&change.key) {
+        match hook {
+            SettingHook::Validator(_) => {}
+            SettingHook::Sync(hook) => hook(change),
+            SettingHook::Async(hook) => {
+                tauri::async_runtime::spawn(hook(change.clone()));
             }
         }
     }
@@ -517,10 +746,7 @@ pub fn get_settings() -> Result<Settings, String> {
 pub fn update_settings(new_settings: Settings) -> Result<(), String> {
     info!("更新设置: {:?}", new_settings);
     let store = get_store()?;
-    let mut settings = SETTINGS_MUTEX.lock()
-        .map_err(|_| "获取设置锁失败".to_string())?;
-    *settings = new_settings;
-    store.save_to_file(&settings)
+    store.update_settings(new_settings)
 }
 
 #[tauri::command]
@@ -583,6 +809,37 @@ pub fn get_frequency_detection_enabled()-> bool{
     }
 }
 
+pub fn get_trigger_dispatch_policy()-> String{
+    match get_store().and_then(|store| store.get_setting("trigger_dispatch_policy")) {
+        Ok(settings) => settings.as_str().unwrap_or("first_match").to_string(),
+        Err(_) => "first_match".to_string(),
+    }
+}
+
+pub fn get_dry_run_enabled()-> bool{
+    match get_store().and_then(|store| store.get_setting("dry_run_enabled")) {
+        Ok(settings) => settings.as_bool().unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+pub fn get_power_source_profiles()-> PowerSourceProfiles{
+    get_store()
+        .and_then(|store| store.get_setting("power_source_profiles"))
+        .ok()
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+// 按当前供电方式生效的频率阈值和刷新间隔
+pub fn get_effective_frequency_threshold()-> f64{
+    get_power_source_profiles().frequency_threshold(power_source::current_power_source(), get_frequency_threshold())
+}
+
+pub fn get_effective_refresh_interval()-> u64{
+    get_power_source_profiles().refresh_interval(power_source::current_power_source(), get_refresh_interval())
+}
+
 pub fn get_frequency_mode()-> String{
     match get_store().and_then(|store| store.get_setting("frequency_mode")) {
         Ok(settings) => settings.as_str().unwrap_or("1").to_string(),
@@ -621,12 +878,176 @@ pub fn set_skip_frequency_mode_persist(skip: bool) -> Result<(), String> {
     Ok(())
 }
 
-// 在现有的公开函数部分添加
-pub fn add_setting_hook<F>(key: &str, hook: F) -> Result<(), String>
+pub fn reload_settings_from_content(content: &str) -> Result<Vec<String>, String> {
+    let store = get_store()?;
+    store.reload_from_content(content)
+}
+
+pub fn settings_file_path() -> Result<PathBuf, String> {
+    let store = get_store()?;
+    Ok(store.settings_path().clone())
+}
+
+// 注册设置变化后的同步钩子
+pub fn add_setting_hook<F>(key: &str, hook: F) -> Result<SettingHookHandle, String>
 where
-    F: Fn(&str, &serde_json::Value) + Send + 'static
+    F: Fn(&SettingChange) + Send + Sync + 'static
 {
-    let store = get_store()?;
-    store.add_setting_hook(key, hook);
-    Ok(())
-}
\ No newline at end of file
+    get_store()?;
+    Ok(register_hook(key, SettingHook::Sync(Arc::new(hook))))
+}
+
+// 注册设置变化后的异步钩子
+pub fn add_async_setting_hook<F, Fut>(key: &str, hook: F) -> Result<SettingHookHandle, String>
+where
+    F: Fn(SettingChange) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    get_store()?;
+    Ok(register_hook(
+        key,
+        SettingHook::Async(Arc::new(move |change| Box::pin(hook(change)))),
+    ))
+}
+
+// 注册提交前的校验钩子，返回错误即否决修改
+pub fn add_setting_validator<F>(key: &str, validator: F) -> Result<SettingHookHandle, String>
+where
+    F: Fn(&SettingChange) -> Result<(), String> + Send + Sync + 'static
+{
+    get_store()?;
+    Ok(register_hook(key, SettingHook::Validator(Arc::new(validator))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    // 钩子注册表是全局的，每个测试使用不同的设置项，避免并行运行时互相影响
+
+    #[test]
+    fn test_validator_vetoes_change() {
+        let _handle = register_hook(
+            "alert_debounce_seconds",
+            SettingHook::Validator(Arc::new(|change: &SettingChange| {
+                if change.new_value.as_u64().unwrap_or(0) > 60 {
+                    return Err("不能超过 60 秒".to_string());
+                }
+                Ok(())
+            })),
+        );
+
+        let mut settings = Settings::default();
+        let err = SettingsStore::stage_change(&mut settings, "alert_debounce_seconds", json!(120)).unwrap_err();
+        assert_eq!(err, "不能超过 60 秒");
+        assert_eq!(settings.alert_debounce_seconds, Settings::default().alert_debounce_seconds);
+
+        SettingsStore::stage_change(&mut settings, "alert_debounce_seconds", json!(30)).unwrap();
+        assert_eq!(settings.alert_debounce_seconds, 30);
+    }
+
+    #[test]
+    fn test_change_carries_old_and_new_values() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_by_hook = seen.clone();
+        let _handle = register_hook(
+            "refresh_interval",
+            SettingHook::Sync(Arc::new(move |change: &SettingChange| {
+                seen_by_hook.lock().unwrap().push((change.old_value.clone(), change.new_value.clone()));
+            })),
+        );
+
+        let mut settings = Settings::default();
+        let old_interval = settings.refresh_interval;
+        for interval in [2000, 3000] {
+            let change = SettingsStore::stage_change(&mut settings, "refresh_interval", json!(interval)).unwrap();
+            trigger_hooks(&change);
+        }
+
+        assert_eq!(settings.refresh_interval, 3000);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![(json!(old_interval), json!(2000)), (json!(2000), json!(3000))]
+        );
+    }
+
+    #[test]
+    fn test_set_setting_rejects_invalid_value_and_fires_hook() {
+        // set_setting 等所有写入入口都通过 commit_values 提交
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls_by_hook = calls.clone();
+        let _hook = register_hook(
+            "auto_switch_threshold",
+            SettingHook::Sync(Arc::new(move |_: &SettingChange| {
+                calls_by_hook.fetch_add(1, Ordering::SeqCst);
+            })),
+        );
+        let _validator = register_hook(
+            "auto_switch_threshold",
+            SettingHook::Validator(Arc::new(|change: &SettingChange| {
+                if change.new_value.as_u64().unwrap_or(0) > 100 {
+                    return Err("不能超过 100".to_string());
+                }
+                Ok(())
+            })),
+        );
+
+        let settings = Mutex::new(Settings::default());
+        let saved = Mutex::new(Vec::new());
+        let commit = |value: serde_json::Value| {
+            SettingsStore::commit_values(&settings, vec![("auto_switch_threshold".to_string(), value)], |s| {
+                saved.lock().unwrap().push(s.auto_switch_threshold);
+                Ok(())
+            })
+        };
+
+        // apply_setting 和校验钩子拒绝的值都不会保存，也不会触发钩子
+        assert!(commit(json!(3)).is_err());
+        assert_eq!(commit(json!(200)).unwrap_err(), "不能超过 100");
+        assert_eq!(settings.lock().unwrap().auto_switch_threshold, Settings::default().auto_switch_threshold);
+        assert!(saved.lock().unwrap().is_empty());
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        let changes = commit(json!(50)).unwrap();
+        assert_eq!(changes[0].new_value, json!(50));
+        assert_eq!(settings.lock().unwrap().auto_switch_threshold, 50);
+        assert_eq!(*saved.lock().unwrap(), vec![50]);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_parse_settings_fills_missing_fields() {
+        let settings = SettingsStore::parse_settings(r#"{"refresh_interval": 2500}"#).unwrap();
+        assert_eq!(settings.refresh_interval, 2500);
+        assert_eq!(settings.alert_debounce_seconds, Settings::default().alert_debounce_seconds);
+        assert!(SettingsStore::parse_settings("{").is_err());
+    }
+
+    #[test]
+    fn test_dropping_handle_unregisters_hook() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let hook = |calls: &Arc<AtomicUsize>| {
+            let calls = calls.clone();
+            SettingHook::Sync(Arc::new(move |_: &SettingChange| {
+                calls.fetch_add(1, Ordering::SeqCst);
+            }))
+        };
+        let change = SettingChange {
+            key: "auto_minimize".to_string(),
+            old_value: json!(false),
+            new_value: json!(true),
+        };
+
+        let handle = register_hook("auto_minimize", hook(&calls));
+        let detached = register_hook("auto_minimize", hook(&calls));
+        trigger_hooks(&change);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // 丢弃的句柄注销钩子，detach 的钩子保留
+        drop(handle);
+        detached.detach();
+        trigger_hooks(&change);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,154 @@
+use std::fs;
+use std::path::Path;
+
+// 优先使用的 CPU 温度驱动，越靠前越优先
+const CPU_HWMON_DRIVERS: [&str; 5] = ["k10temp", "zenpower", "coretemp", "cpu_thermal", "acpitz"];
+
+// 能代表整颗 CPU 的传感器标签，找不到时取该驱动所有传感器中的最高值
+const CPU_PACKAGE_LABELS: [&str; 4] = ["Tctl", "Tdie", "Package id 0", "CPU"];
+
+// 读取某个 hwmon 目录下的温度（摄氏度），temp*_input 的单位是千分之一摄氏度
+fn read_hwmon_dir(dir: &Path) -> Option<f32> {
+    let mut package = None;
+    let mut highest: Option<f32> = None;
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(index) = file_name
+            .strip_prefix("temp")
+            .and_then(|rest| rest.strip_suffix("_input"))
+        else {
+            continue;
+        };
+        let Some(celsius) = fs::read_to_string(entry.path())
+            .ok()
+            .and_then(|v| v.trim().parse::<f32>().ok())
+            .map(|millis| millis / 1000.0)
+        else {
+            continue;
+        };
+
+        let label = fs::read_to_string(dir.join(format!("temp{}_label", index))).unwrap_or_default();
+        if CPU_PACKAGE_LABELS.contains(&label.trim()) && package.is_none() {
+            package = Some(celsius);
+        }
+        highest = Some(highest.map_or(celsius, |h: f32| h.max(celsius)));
+    }
+    package.or(highest)
+}
+
+// 从 /sys/class/hwmon 这样的目录中找出 CPU 温度
+pub fn read_hwmon_temperature(root: &Path) -> Option<f32> {
+    let mut candidates: Vec<(usize, f32)> = fs::read_dir(root)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let name = fs::read_to_string(dir.join("name")).ok()?;
+            let priority = CPU_HWMON_DRIVERS.iter().position(|d| *d == name.trim())?;
+            Some((priority, read_hwmon_dir(&dir)?))
+        })
+        .collect();
+    candidates.sort_by_key(|(priority, _)| *priority);
+    candidates.first().map(|(_, celsius)| *celsius)
+}
+
+#[cfg(target_os = "linux")]
+fn read_platform_temperature() -> Option<f32> {
+    read_hwmon_temperature(Path::new("/sys/class/hwmon"))
+}
+
+// 传感器列表只在第一次读取时枚举，之后每次只刷新读数，Windows 上不必每个周期重新连接 WMI
+#[cfg(not(target_os = "linux"))]
+static COMPONENTS: once_cell::sync::Lazy<std::sync::Mutex<sysinfo::Components>> =
+    once_cell::sync::Lazy::new(|| std::sync::Mutex::new(sysinfo::Components::new_with_refreshed_list()));
+
+// sysinfo 在 Windows 上通过 WMI 读取，部分机器需要管理员权限才有数据
+#[cfg(not(target_os = "linux"))]
+fn read_platform_temperature() -> Option<f32> {
+    let mut components = COMPONENTS.lock().ok()?;
+    components.refresh(false);
+    let readings: Vec<(String, f32)> = components
+        .iter()
+        .filter_map(|c| Some((c.label().to_lowercase(), c.temperature()?)))
+        .filter(|(_, celsius)| *celsius > 0.0)
+        .collect();
+    let is_cpu = |label: &str| ["cpu", "package", "tctl", "tdie", "core"].iter().any(|k| label.contains(k));
+
+    // 有 CPU 相关的传感器时只看这些，否则退回到所有传感器（通常只有 ACPI 热区）
+    let cpu_readings: Vec<f32> = readings.iter().filter(|(l, _)| is_cpu(l)).map(|(_, t)| *t).collect();
+    let values = if cpu_readings.is_empty() {
+        readings.iter().map(|(_, t)| *t).collect()
+    } else {
+        cpu_readings
+    };
+    values.into_iter().reduce(f32::max)
+}
+
+// 读取 CPU 温度（摄氏度），没有可用的传感器时返回 None
+pub fn read_cpu_temperature() -> Option<f32> {
+    read_platform_temperature()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_tree;
+    use std::path::PathBuf;
+
+    // hwmon 目录按 hwmon0、hwmon1 编号，驱动名写在 name 文件中
+    fn fixture(devices: &[(&str, &[(&str, &str)])]) -> PathBuf {
+        let dirs: Vec<(String, Vec<(&str, &str)>)> = devices
+            .iter()
+            .enumerate()
+            .map(|(index, (name, files))| {
+                let files = [("name", *name)].into_iter().chain(files.iter().copied()).collect();
+                (format!("hwmon{}", index), files)
+            })
+            .collect();
+        let dirs: Vec<(&str, &[(&str, &str)])> = dirs.iter().map(|(dir, files)| (dir.as_str(), files.as_slice())).collect();
+        temp_tree("hwmon", &dirs)
+    }
+
+    #[test]
+    fn test_k10temp_prefers_tctl() {
+        let root = fixture(&[
+            ("nvme", &[("temp1_input", "45850")]),
+            (
+                "k10temp",
+                &[
+                    ("temp1_input", "61250"),
+                    ("temp1_label", "Tctl"),
+                    ("temp3_input", "72000"),
+                    ("temp3_label", "Tccd1"),
+                ],
+            ),
+        ]);
+        assert_eq!(read_hwmon_temperature(&root), Some(61.25));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_coretemp_without_package_uses_highest_core() {
+        let root = fixture(&[
+            ("acpitz", &[("temp1_input", "40000")]),
+            (
+                "coretemp",
+                &[
+                    ("temp2_input", "55000"),
+                    ("temp2_label", "Core 0"),
+                    ("temp3_input", "58000"),
+                    ("temp3_label", "Core 1"),
+                ],
+            ),
+        ]);
+        assert_eq!(read_hwmon_temperature(&root), Some(58.0));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_no_cpu_sensor() {
+        let root = fixture(&[("nvme", &[("temp1_input", "45850")])]);
+        assert_eq!(read_hwmon_temperature(&root), None);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,22 @@
+use std::fs;
+use std::path::PathBuf;
+
+// 测试用的临时目录，名称带随机后缀，并行运行的测试互不影响
+pub fn temp_dir(prefix: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}_{}", prefix, uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// 按 sysfs 的结构生成测试数据：每个子目录下若干个单行文件
+pub fn temp_tree(prefix: &str, dirs: &[(&str, &[(&str, &str)])]) -> PathBuf {
+    let root = temp_dir(prefix);
+    for (name, files) in dirs {
+        let dir = root.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        for (file, content) in *files {
+            fs::write(dir.join(file), format!("{}\n", content)).unwrap();
+        }
+    }
+    root
+}
@@ -0,0 +1,536 @@
+use chrono::{Local, TimeZone};
+use log::{error, info};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+use tokio::time::Duration;
+
+use crate::action_journal::now_millis;
+use crate::notification::send_notification;
+use crate::power_source::PowerSource;
+use crate::versioned_file::exe_dir;
+
+// 正在进行的记录，为空表示未在记录
+static TRACE_SESSION: Lazy<Mutex<Option<TraceSession>>> = Lazy::new(|| Mutex::new(None));
+
+// 托盘和命令行开始记录时默认的自动停止时间
+const DEFAULT_STOP_AFTER_MINUTES: u64 = 30;
+
+// 默认单个文件的大小上限
+const DEFAULT_MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceFormat {
+    #[default]
+    Csv,
+    Jsonl,
+}
+
+impl TraceFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            TraceFormat::Csv => "csv",
+            TraceFormat::Jsonl => "jsonl",
+        }
+    }
+}
+
+// 记录选项，文件满足任一轮换条件时换新文件
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct TraceOptions {
+    pub format: TraceFormat,
+    // 单个文件超过该大小（字节）后换新文件，为空则不限制
+    pub max_file_bytes: Option<u64>,
+    // 单个文件记录超过该时长（分钟）后换新文件，为空则不限制
+    pub rotate_minutes: Option<u64>,
+    // 开始后多少分钟自动停止，为空则一直记录到手动停止
+    pub stop_after_minutes: Option<u64>,
+}
+
+impl Default for TraceOptions {
+    fn default() -> Self {
+        Self {
+            format: TraceFormat::Csv,
+            max_file_bytes: Some(DEFAULT_MAX_FILE_BYTES),
+            rotate_minutes: None,
+            stop_after_minutes: Some(DEFAULT_STOP_AFTER_MINUTES),
+        }
+    }
+}
+
+impl TraceOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_file_bytes == Some(0) {
+            return Err("文件大小上限必须大于0".to_string());
+        }
+        if self.rotate_minutes == Some(0) {
+            return Err("文件轮换时长必须大于0".to_string());
+        }
+        if self.stop_after_minutes == Some(0) {
+            return Err("自动停止时间必须大于0".to_string());
+        }
+        Ok(())
+    }
+}
+
+// 记录中的一行，JSONL 文件中每行是一个本结构体的 JSON，可以直接交给 replay.rs 回放
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TraceRecord {
+    pub timestamp_ms: u64,
+    // 频率来源：sysinfo 或 calcmhz
+    pub frequency_source: String,
+    pub active_plan: Option<String>,
+    // 指示器状态：normal、warning 或 danger
+    pub alert_state: String,
+    // 本次采样是否发出了频率提醒
+    pub alert_sent: bool,
+    pub temperature: Option<f32>,
+    pub power_source: Option<PowerSource>,
+    // 各核心频率（MHz）
+    pub frequencies: Vec<u64>,
+    #[serde(default)]
+    pub loads: Vec<f32>,
+}
+
+impl TraceRecord {
+    // CSV 的列随核心数变化，核心数变化时需要换新文件
+    fn csv_columns(&self) -> (usize, usize) {
+        (self.frequencies.len(), self.loads.len())
+    }
+
+    fn csv_header(&self) -> String {
+        let mut columns: Vec<String> = [
+            "timestamp_ms",
+            "frequency_source",
+            "active_plan",
+            "alert_state",
+            "alert_sent",
+            "temperature",
+            "power_source",
+        ]
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+        columns.extend((0..self.frequencies.len()).map(|i| format!("core{}", i)));
+        columns.extend((0..self.loads.len()).map(|i| format!("load{}", i)));
+        columns.join(",")
+    }
+
+    fn csv_row(&self) -> String {
+        let mut fields = vec![
+            self.timestamp_ms.to_string(),
+            self.frequency_source.clone(),
+            self.active_plan.clone().unwrap_or_default(),
+            self.alert_state.clone(),
+            self.alert_sent.to_string(),
+            self.temperature.map(|t| format!("{:.1}", t)).unwrap_or_default(),
+            self.power_source
+                .and_then(|s| serde_json::to_value(s).ok())
+                .and_then(|v| v.as_str().map(|s| s.to_string()))
+                .unwrap_or_default(),
+        ];
+        fields.extend(self.frequencies.iter().map(|f| f.to_string()));
+        fields.extend(self.loads.iter().map(|l| format!("{:.1}", l)));
+        fields.join(",")
+    }
+}
+
+// 当前正在写入的文件
+struct TraceFile {
+    path: PathBuf,
+    started_ms: u64,
+    bytes: u64,
+    columns: (usize, usize),
+}
+
+// 把记录追加到文件，按大小、时长和 CSV 列数换新文件
+pub struct TraceWriter {
+    dir: PathBuf,
+    options: TraceOptions,
+    current: Option<TraceFile>,
+    files: Vec<PathBuf>,
+}
+
+impl TraceWriter {
+    pub fn new(dir: PathBuf, options: TraceOptions) -> Self {
+        Self {
+            dir,
+            options,
+            current: None,
+            files: Vec::new(),
+        }
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    fn needs_new_file(&self, record: &TraceRecord) -> bool {
+        let Some(current) = &self.current else {
+            return true;
+        };
+        let too_large = self.options.max_file_bytes.is_some_and(|max| current.bytes >= max);
+        let too_old = self
+            .options
+            .rotate_minutes
+            .is_some_and(|minutes| record.timestamp_ms.saturating_sub(current.started_ms) >= minutes * 60 * 1000);
+        let columns_changed = self.options.format == TraceFormat::Csv && current.columns != record.csv_columns();
+        too_large || too_old || columns_changed
+    }
+
+    fn open_new_file(&mut self, record: &TraceRecord) -> Result<(), String> {
+        fs::create_dir_all(&self.dir).map_err(|e| format!("创建频率记录目录失败: {}", e))?;
+        let time = Local
+            .timestamp_millis_opt(record.timestamp_ms as i64)
+            .single()
+            .unwrap_or_else(Local::now);
+        // 同一秒内轮换多次时靠序号区分
+        let path = self.dir.join(format!(
+            "frequency_trace_{}_{:03}.{}",
+            time.format("%Y%m%d_%H%M%S"),
+            self.files.len() + 1,
+            self.options.format.extension()
+        ));
+
+        let mut bytes = 0;
+        if self.options.format == TraceFormat::Csv {
+            let header = format!("{}\n", record.csv_header());
+            fs::write(&path, &header).map_err(|e| format!("写入频率记录失败: {}", e))?;
+            bytes = header.len() as u64;
+        }
+        info!("频率记录写入新文件: {}", path.display());
+        self.files.push(path.clone());
+        self.current = Some(TraceFile {
+            path,
+            started_ms: record.timestamp_ms,
+            bytes,
+            columns: record.csv_columns(),
+        });
+        Ok(())
+    }
+
+    pub fn append(&mut self, record: &TraceRecord) -> Result<(), String> {
+        if self.needs_new_file(record) {
+            self.open_new_file(record)?;
+        }
+        let line = match self.options.format {
+            TraceFormat::Csv => format!("{}\n", record.csv_row()),
+            TraceFormat::Jsonl => format!(
+                "{}\n",
+                serde_json::to_string(record).map_err(|e| format!("序列化频率记录失败: {}", e))?
+            ),
+        };
+
+        let Some(current) = self.current.as_mut() else {
+            return Err("没有可写入的频率记录文件".to_string());
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&current.path)
+            .map_err(|e| format!("打开频率记录失败: {}", e))?;
+        file.write_all(line.as_bytes())
+            .map_err(|e| format!("写入频率记录失败: {}", e))?;
+        current.bytes += line.len() as u64;
+        Ok(())
+    }
+}
+
+struct TraceSession {
+    id: String,
+    started_ms: u64,
+    options: TraceOptions,
+    writer: TraceWriter,
+    samples: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TraceStatus {
+    pub recording: bool,
+    pub started_at: Option<u64>,
+    // 预计自动停止的时间（毫秒时间戳）
+    pub stops_at: Option<u64>,
+    pub options: Option<TraceOptions>,
+    pub samples: u64,
+    pub files: Vec<String>,
+}
+
+impl TraceStatus {
+    fn from_session(session: Option<&TraceSession>, recording: bool) -> Self {
+        match session {
+            Some(session) => Self {
+                recording,
+                started_at: Some(session.started_ms),
+                stops_at: session
+                    .options
+                    .stop_after_minutes
+                    .map(|minutes| session.started_ms + minutes * 60 * 1000),
+                options: Some(session.options.clone()),
+                samples: session.samples,
+                files: session
+                    .writer
+                    .files()
+                    .iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect(),
+            },
+            None => Self {
+                recording: false,
+                started_at: None,
+                stops_at: None,
+                options: None,
+                samples: 0,
+                files: Vec::new(),
+            },
+        }
+    }
+}
+
+fn get_trace_dir() -> PathBuf {
+    exe_dir().join("traces")
+}
+
+// 频率获取模式对应的来源名称
+pub fn frequency_source(frequency_mode: &str) -> &'static str {
+    if frequency_mode == "1" {
+        "sysinfo"
+    } else {
+        "calcmhz"
+    }
+}
+
+pub fn is_recording() -> bool {
+    TRACE_SESSION.lock().map(|session| session.is_some()).unwrap_or(false)
+}
+
+// 追加一次采样，写入失败时停止记录，避免每个周期重复报错
+pub fn record(record: &TraceRecord) {
+    let Ok(mut session) = TRACE_SESSION.lock() else {
+        return;
+    };
+    let Some(current) = session.as_mut() else {
+        return;
+    };
+    match current.writer.append(record) {
+        Ok(()) => current.samples += 1,
+        Err(e) => {
+            error!("{}，已停止记录频率", e);
+            *session = None;
+        }
+    }
+}
+
+fn emit_status(app: &AppHandle, status: &TraceStatus) {
+    let _ = app.emit("trace-recording-changed", status);
+}
+
+// 开始记录，已在记录时先结束当前记录
+pub fn start_recording(app: &AppHandle, options: TraceOptions) -> Result<TraceStatus, String> {
+    options.validate()?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let status = {
+        let mut session = TRACE_SESSION
+            .lock()
+            .map_err(|e| format!("获取频率记录锁失败: {}", e))?;
+        *session = Some(TraceSession {
+            id: id.clone(),
+            started_ms: now_millis(),
+            options: options.clone(),
+            writer: TraceWriter::new(get_trace_dir(), options.clone()),
+            samples: 0,
+        });
+        TraceStatus::from_session(session.as_ref(), true)
+    };
+    info!("开始记录频率: {:?}", options);
+    emit_status(app, &status);
+
+    if let Some(minutes) = options.stop_after_minutes {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(minutes * 60)).await;
+            // 期间被停止或重新开始的记录不受影响
+            let still_running = TRACE_SESSION
+                .lock()
+                .map(|session| session.as_ref().is_some_and(|s| s.id == id))
+                .unwrap_or(false);
+            if still_running {
+                if let Ok(status) = stop_recording(&app) {
+                    let _ = send_notification(
+                        "频率记录已结束",
+                        &format!("已记录 {} 分钟，共 {} 个采样", minutes, status.samples),
+                    );
+                }
+            }
+        });
+    }
+    Ok(status)
+}
+
+pub fn stop_recording(app: &AppHandle) -> Result<TraceStatus, String> {
+    let session = TRACE_SESSION
+        .lock()
+        .map_err(|e| format!("获取频率记录锁失败: {}", e))?
+        .take();
+    let status = TraceStatus::from_session(session.as_ref(), false);
+    if session.is_some() {
+        info!("停止记录频率，共 {} 个采样，文件: {:?}", status.samples, status.files);
+    }
+    emit_status(app, &status);
+    Ok(status)
+}
+
+// 命令行中与频率记录有关的参数
+#[derive(Debug, PartialEq)]
+pub enum TraceCliCommand {
+    Start(TraceOptions),
+    Stop,
+}
+
+// 解析 --record-trace[=分钟]、--trace-format=csv|jsonl 和 --stop-trace
+pub fn parse_cli_args(args: &[String]) -> Result<Option<TraceCliCommand>, String> {
+    if args.iter().any(|arg| arg == "--stop-trace") {
+        return Ok(Some(TraceCliCommand::Stop));
+    }
+
+    let mut options = TraceOptions::default();
+    let mut start = false;
+    for arg in args {
+        if arg == "--record-trace" {
+            start = true;
+        } else if let Some(minutes) = arg.strip_prefix("--record-trace=") {
+            start = true;
+            let minutes: u64 = minutes
+                .parse()
+                .map_err(|_| format!("无效的记录时长: {}", minutes))?;
+            options.stop_after_minutes = Some(minutes);
+        } else if let Some(format) = arg.strip_prefix("--trace-format=") {
+            options.format = serde_json::from_value(serde_json::Value::String(format.to_string()))
+                .map_err(|_| format!("未知的记录格式: {}", format))?;
+        }
+    }
+    if !start {
+        return Ok(None);
+    }
+    options.validate()?;
+    Ok(Some(TraceCliCommand::Start(options)))
+}
+
+// 处理启动参数，或单实例插件转发过来的新实例参数
+pub fn handle_cli_args(app: &AppHandle, args: &[String]) {
+    let result = match parse_cli_args(args) {
+        Ok(Some(TraceCliCommand::Start(options))) => start_recording(app, options).map(|_| ()),
+        Ok(Some(TraceCliCommand::Stop)) => stop_recording(app).map(|_| ()),
+        Ok(None) => Ok(()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        error!("处理频率记录参数失败: {}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn start_trace_recording(app: AppHandle, options: Option<TraceOptions>) -> Result<TraceStatus, String> {
+    start_recording(&app, options.unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn stop_trace_recording(app: AppHandle) -> Result<TraceStatus, String> {
+    stop_recording(&app)
+}
+
+#[tauri::command]
+pub async fn get_trace_recording_status() -> Result<TraceStatus, String> {
+    let session = TRACE_SESSION
+        .lock()
+        .map_err(|e| format!("获取频率记录锁失败: {}", e))?;
+    Ok(TraceStatus::from_session(session.as_ref(), session.is_some()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::parse_trace;
+
+    fn record(timestamp_ms: u64, frequencies: &[u64]) -> TraceRecord {
+        TraceRecord {
+            timestamp_ms,
+            frequency_source: "sysinfo".to_string(),
+            active_plan: Some("381B4222-F694-41F0-9685-FF5BB260DF2E".to_string()),
+            alert_state: "warning".to_string(),
+            alert_sent: true,
+            temperature: Some(61.5),
+            power_source: Some(PowerSource::Battery),
+            frequencies: frequencies.to_vec(),
+            loads: vec![12.5; frequencies.len()],
+        }
+    }
+
+    #[test]
+    fn test_csv_trace_can_be_replayed() {
+        let dir = crate::test_support::temp_dir("trace_recorder");
+        let mut writer = TraceWriter::new(dir.clone(), TraceOptions::default());
+        writer.append(&record(1000, &[3100, 2900])).unwrap();
+        writer.append(&record(2000, &[3200, 2800])).unwrap();
+        // 核心数变化（如切换到 CalcMhz）时换新文件
+        writer.append(&record(3000, &[3000])).unwrap();
+        assert_eq!(writer.files().len(), 2);
+
+        let content = fs::read_to_string(&writer.files()[0]).unwrap();
+        let samples = parse_trace(&content).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[1].frequencies, vec![3200, 2800]);
+        assert_eq!(samples[1].power_source, Some(PowerSource::Battery));
+        assert_eq!(samples[1].loads, vec![12.5, 12.5]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_jsonl_rotation_by_size_and_time() {
+        let dir = crate::test_support::temp_dir("trace_recorder");
+        let line_len = serde_json::to_string(&record(0, &[3000])).unwrap().len() as u64 + 1;
+        let options = TraceOptions {
+            format: TraceFormat::Jsonl,
+            max_file_bytes: Some(line_len * 3),
+            rotate_minutes: Some(1),
+            stop_after_minutes: None,
+        };
+        let mut writer = TraceWriter::new(dir.clone(), options);
+        for timestamp_ms in [0, 1000, 2000, 3000, 70_000] {
+            writer.append(&record(timestamp_ms, &[3000])).unwrap();
+        }
+        // 第 4 行时第一个文件已满，第 5 行距第二个文件开始已超过 1 分钟
+        let counts: Vec<usize> = writer
+            .files()
+            .iter()
+            .map(|f| fs::read_to_string(f).unwrap().lines().count())
+            .collect();
+        assert_eq!(counts, vec![3, 1, 1]);
+        let replayed = parse_trace(&fs::read_to_string(&writer.files()[2]).unwrap()).unwrap();
+        assert_eq!(replayed[0].timestamp_ms, 70_000);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_cli_args() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<String>>();
+        assert_eq!(parse_cli_args(&args(&["app.exe", "--autostart"])).unwrap(), None);
+        assert_eq!(parse_cli_args(&args(&["app.exe", "--stop-trace"])).unwrap(), Some(TraceCliCommand::Stop));
+
+        let Some(TraceCliCommand::Start(options)) =
+            parse_cli_args(&args(&["app.exe", "--record-trace=5", "--trace-format=jsonl"])).unwrap()
+        else {
+            panic!("应为开始记录");
+        };
+        assert_eq!(options.stop_after_minutes, Some(5));
+        assert_eq!(options.format, TraceFormat::Jsonl);
+
+        assert!(parse_cli_args(&args(&["app.exe", "--record-trace=0"])).is_err());
+        assert!(parse_cli_args(&args(&["app.exe", "--record-trace", "--trace-format=xml"])).is_err());
+    }
+}
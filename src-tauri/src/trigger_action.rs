@@ -1,18 +1,35 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::{fs, path::PathBuf};
 use tauri::{AppHandle, Manager};
 use std::env;
 use std::path::Path;
 use uuid;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use log;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
 
+use crate::action_executor::{CancelToken, ACTION_EXECUTOR};
+use crate::action_journal::{JournalRecorder, JournalTrigger};
+use crate::dry_run;
 use crate::notification::send_notification;
-use crate::power_plan::{check_if_scheme_is_valid, set_active_plan};
+use crate::settings_store;
+use crate::trigger_condition::{ConditionTracker, FrequencySample, TriggerCondition, CONDITION_TRACKER};
+use crate::workflow::{self, PlannedStep, WorkflowStep};
+use crate::power_plan::{active_plan_guid, check_if_scheme_is_valid, set_active_plan};
+use crate::PowerPlanUtils::GetPowerPlans::{
+    enumerate_possible_settings, get_power_setting_value, write_value_set, PowerSettingValue,
+};
 
-// 定义不同类型的执行体
+// 当前 trigger_actions.json 的文件格式版本
+// 1: 顶层为数组，类型写在动作的 version 字段，worker 不带类型标记
+// 2: 顶层为 { schema_version, actions }，类型写在 worker 的 type 字段
+pub const TRIGGER_ACTIONS_SCHEMA_VERSION: u32 = 2;
+
+// 定义不同类型的执行体，type 字段标记具体类型
 #[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(untagged)]  // 使用 untagged 让序列化时不包含类型标记
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum TriggerActionWorker {
     Simple {
         temp_plan_guid: String,
@@ -20,21 +37,57 @@ pub enum TriggerActionWorker {
         target_plan_guid: String,
     },
     SettingSwitch {
-        // 未来实现
+        plan_guid: String,
+        values: Vec<SettingSwitchValue>,
+        // 写入后是否重新激活该计划，使新值立即生效
+        #[serde(default)]
+        reapply_scheme: bool,
+        // 多少秒后恢复原值，为空则保留新值
+        restore_after_seconds: Option<u32>,
     },
     Workflow {
-        // 未来实现
+        steps: Vec<WorkflowStep>,
+    }
+}
+
+impl TriggerActionWorker {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TriggerActionWorker::Simple { .. } => "simple",
+            TriggerActionWorker::SettingSwitch { .. } => "setting_switch",
+            TriggerActionWorker::Workflow { .. } => "workflow",
+        }
     }
 }
 
+// 设置切换中单个电源设置的目标值
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SettingSwitchValue {
+    pub subgroup_guid: String,
+    pub setting_guid: String,
+    pub ac_value: u32,
+    pub dc_value: u32,
+}
+
 // 修改触发动作结构体
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct TriggerAction {
     pub id: String,
     pub name: String,
-    pub version: String,  // "simple", "setting_switch", "workflow"
     pub enabled: bool,
+    // 优先级，数值越大越先执行，相同时按文件中的顺序
+    #[serde(default)]
+    pub priority: i32,
+    // 触发条件，旧文件中没有该字段时使用全局阈值和防抖时间
+    #[serde(default)]
+    pub condition: TriggerCondition,
     pub worker: TriggerActionWorker,
+    // 单次执行的超时时间，超时后取消执行，为空则不限制
+    #[serde(default)]
+    pub timeout_seconds: Option<u32>,
+    // 只对该动作试运行：记录会执行的操作，不修改电源设置
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
 // 实现默认值
@@ -43,18 +96,182 @@ impl Default for TriggerAction {
         Self {
             id: uuid::Uuid::new_v4().to_string(),
             name: String::new(),
-            version: "simple".to_string(),
             enabled: false,
+            priority: 0,
+            condition: TriggerCondition::default(),
             worker: TriggerActionWorker::Simple {
                 temp_plan_guid: String::new(),
                 pause_seconds: 1,
                 target_plan_guid: String::new(),
             },
+            timeout_seconds: None,
+            dry_run: false,
+        }
+    }
+}
+
+impl TriggerAction {
+    // 只切换电源计划的单步工作流，规则和定时任务切换计划时同样经过执行队列和执行日志
+    pub fn switch_plan(id: String, name: String, plan_guid: &str) -> Self {
+        Self {
+            id,
+            name,
+            enabled: true,
+            worker: TriggerActionWorker::Workflow {
+                steps: vec![WorkflowStep::new(workflow::WorkflowStepKind::SwitchPlan {
+                    plan_guid: plan_guid.to_string(),
+                })],
+            },
+            ..Default::default()
+        }
+    }
+
+    // 返回该动作引用到的所有电源计划 GUID
+    pub fn referenced_plan_guids(&self) -> Vec<String> {
+        match &self.worker {
+            TriggerActionWorker::Simple { temp_plan_guid, target_plan_guid, .. } => {
+                vec![temp_plan_guid.clone(), target_plan_guid.clone()]
+            }
+            TriggerActionWorker::SettingSwitch { plan_guid, .. } => vec![plan_guid.clone()],
+            TriggerActionWorker::Workflow { steps } => {
+                let mut guids = Vec::new();
+                workflow::collect_plan_guids(steps, &mut guids);
+                guids
+            }
+        }
+    }
+
+    // 按映射表替换引用的电源计划 GUID（键为大写 GUID），导入计划后 GUID 可能发生变化
+    pub fn remap_plan_guids(&mut self, guid_map: &HashMap<String, String>) {
+        let remap = |guid: &mut String| {
+            if let Some(new_guid) = guid_map.get(&guid.to_uppercase()) {
+                *guid = new_guid.clone();
+            }
+        };
+
+        match &mut self.worker {
+            TriggerActionWorker::Simple { temp_plan_guid, target_plan_guid, .. } => {
+                remap(temp_plan_guid);
+                remap(target_plan_guid);
+            }
+            TriggerActionWorker::SettingSwitch { plan_guid, .. } => remap(plan_guid),
+            TriggerActionWorker::Workflow { steps } => workflow::remap_plan_guids(steps, guid_map),
+        }
+    }
+}
+
+// 多个动作同时可执行时的派发方式
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DispatchPolicy {
+    // 只执行优先级最高的动作
+    FirstMatch,
+    // 按优先级依次执行所有动作
+    AllInOrder,
+    // 每次触发轮流执行下一个动作
+    RoundRobin,
+}
+
+impl DispatchPolicy {
+    pub fn from_setting(value: &str) -> Result<Self, String> {
+        match value {
+            "first_match" => Ok(DispatchPolicy::FirstMatch),
+            "all_in_order" => Ok(DispatchPolicy::AllInOrder),
+            "round_robin" => Ok(DispatchPolicy::RoundRobin),
+            _ => Err(format!("未知的派发方式: {}", value)),
+        }
+    }
+}
+
+// 轮流派发时上一次执行的动作
+static LAST_ROUND_ROBIN_ACTION: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+// 可执行的动作：已启用，按优先级从高到低排列
+pub fn eligible_actions(actions: &[TriggerAction]) -> Vec<&TriggerAction> {
+    let mut eligible: Vec<&TriggerAction> = actions.iter().filter(|a| a.enabled).collect();
+    // 稳定排序，相同优先级保持文件中的顺序
+    eligible.sort_by_key(|a| std::cmp::Reverse(a.priority));
+    eligible
+}
+
+// 按派发方式从满足条件的动作（已按优先级排列）中选出本次要执行的动作
+pub fn select_actions<'a>(
+    matched: Vec<&'a TriggerAction>,
+    policy: DispatchPolicy,
+    last_round_robin: Option<&str>,
+) -> Vec<&'a TriggerAction> {
+    match policy {
+        DispatchPolicy::FirstMatch => matched.into_iter().take(1).collect(),
+        DispatchPolicy::AllInOrder => matched,
+        DispatchPolicy::RoundRobin => {
+            // 从上次执行的动作之后开始，找不到（例如本次未满足条件）时从头开始
+            let next = last_round_robin
+                .and_then(|last| matched.iter().position(|a| a.id == last))
+                .map(|index| (index + 1) % matched.len())
+                .unwrap_or(0);
+            matched.get(next).copied().into_iter().collect()
         }
     }
 }
 
-fn get_actions_path(app: &AppHandle) -> PathBuf {
+// 按各动作自己的条件检查本次采样，按派发方式选出本次要执行的动作，并记录触发时间和轮流派发的位置
+// 时间由调用方传入（秒），实时监控和离线回放共用
+pub fn select_dispatch<'a>(
+    actions: &'a [TriggerAction],
+    sample: &FrequencySample,
+    policy: DispatchPolicy,
+    tracker: &mut ConditionTracker,
+    last_round_robin: &mut Option<String>,
+    now: u64,
+) -> Vec<&'a TriggerAction> {
+    let matched: Vec<&TriggerAction> = eligible_actions(actions)
+        .into_iter()
+        .filter(|action| tracker.check(&action.id, &action.condition, sample, now))
+        .collect();
+    if matched.is_empty() {
+        return matched;
+    }
+
+    let selected = select_actions(matched, policy, last_round_robin.as_deref());
+    for action in &selected {
+        tracker.record_fired(&action.id, now);
+        if policy == DispatchPolicy::RoundRobin {
+            *last_round_robin = Some(action.id.clone());
+        }
+    }
+    selected
+}
+
+// 按各动作自己的条件检查本次采样，把要执行的动作放入执行队列，返回加入队列的动作个数
+pub async fn dispatch_trigger_actions(app: &AppHandle, sample: &FrequencySample) -> Result<usize, String> {
+    let actions = load_trigger_actions(app.clone()).await?;
+    let policy = DispatchPolicy::from_setting(&settings_store::get_trigger_dispatch_policy())?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let mut tracker = CONDITION_TRACKER
+        .lock()
+        .map_err(|_| "获取触发条件状态锁失败".to_string())?;
+    let mut last_round_robin = LAST_ROUND_ROBIN_ACTION
+        .lock()
+        .map_err(|_| "获取轮流派发状态锁失败".to_string())?;
+    let selected = select_dispatch(&actions, sample, policy, &mut tracker, &mut last_round_robin, now);
+
+    let mut submitted = 0;
+    for action in selected {
+        log::info!("派发触发动作: {}（优先级 {}）", action.name, action.priority);
+        let trigger = JournalTrigger::threshold(&sample.frequencies, action.condition.effective_threshold(sample));
+        match ACTION_EXECUTOR.submit(action.clone(), trigger) {
+            Ok(()) => submitted += 1,
+            Err(e) => log::warn!("{}", e),
+        }
+    }
+    Ok(submitted)
+}
+
+pub(crate) fn get_actions_path(_app: &AppHandle) -> PathBuf {
     let mut path = env::current_exe()
         .unwrap_or_else(|_| PathBuf::from("."))
         .parent()
@@ -94,22 +311,112 @@ pub async fn load_trigger_actions(app: AppHandle) -> Result<Vec<TriggerAction>,
     }
 
     let content =
-        fs::read_to_string(actions_path).map_err(|e| format!("读取触发动作失败: {}", e))?;
+        fs::read_to_string(&actions_path).map_err(|e| format!("读取触发动作失败: {}", e))?;
+
+    let (actions, migrated_from) = parse_trigger_actions(&content)?;
+
+    // 旧版本文件迁移后立即按新格式保存，并保留一份原文件备份
+    if let Some(old_version) = migrated_from {
+        let mut backup_path = actions_path.clone().into_os_string();
+        backup_path.push(format!(".v{}.bak", old_version));
+        fs::write(&backup_path, &content).map_err(|e| format!("备份旧版触发动作文件失败: {}", e))?;
+        save_trigger_actions(&app, &actions)?;
+        log::info!("触发动作文件已从版本 {} 迁移到版本 {}", old_version, TRIGGER_ACTIONS_SCHEMA_VERSION);
+    }
 
-    serde_json::from_str(&content).map_err(|e| format!("解析触发动作失败: {}", e))
+    Ok(actions)
+}
+
+// 解析 trigger_actions.json 的内容，返回动作列表以及迁移前的文件版本（无需迁移时为 None）
+pub fn parse_trigger_actions(content: &str) -> Result<(Vec<TriggerAction>, Option<u32>), String> {
+    let value: serde_json::Value =
+        serde_json::from_str(content).map_err(|e| format!("解析触发动作失败: {}", e))?;
+
+    match value {
+        // 版本 1：顶层为数组
+        serde_json::Value::Array(items) => Ok((parse_action_values(migrate_v1_actions(items))?, Some(1))),
+        serde_json::Value::Object(mut obj) => {
+            let version = obj
+                .get("schema_version")
+                .and_then(|v| v.as_u64())
+                .ok_or("触发动作文件缺少 schema_version 字段")?;
+            if version != TRIGGER_ACTIONS_SCHEMA_VERSION as u64 {
+                return Err(format!(
+                    "不支持的触发动作文件版本: {}（当前支持 {}）",
+                    version, TRIGGER_ACTIONS_SCHEMA_VERSION
+                ));
+            }
+            match obj.remove("actions") {
+                Some(serde_json::Value::Array(items)) => Ok((parse_action_values(items)?, None)),
+                Some(_) => Err("触发动作文件的 actions 字段必须是数组".to_string()),
+                None => Ok((Vec::new(), None)),
+            }
+        }
+        _ => Err("触发动作文件格式无效".to_string()),
+    }
+}
+
+// 版本 1 的动作把类型写在 version 字段，迁移为 worker.type
+fn migrate_v1_actions(items: Vec<serde_json::Value>) -> Vec<serde_json::Value> {
+    items
+        .into_iter()
+        .map(|mut item| {
+            if let Some(obj) = item.as_object_mut() {
+                let kind = obj.remove("version");
+                if let (Some(kind), Some(worker)) = (kind, obj.get_mut("worker").and_then(|w| w.as_object_mut())) {
+                    worker.entry("type").or_insert(kind);
+                }
+            }
+            item
+        })
+        .collect()
 }
 
-fn save_trigger_actions(app: &AppHandle, actions: &[TriggerAction]) -> Result<(), String> {
+// 逐个解析动作，出错时指出是哪一个动作
+fn parse_action_values(items: Vec<serde_json::Value>) -> Result<Vec<TriggerAction>, String> {
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let name = item
+                .get("name")
+                .and_then(|n| n.as_str())
+                .unwrap_or("未命名")
+                .to_string();
+            serde_json::from_value(item)
+                .map_err(|e| format!("第 {} 个触发动作（{}）无效: {}", index + 1, name, e))
+        })
+        .collect()
+}
+
+// 兼容旧版格式的反序列化，供配置包等嵌入了动作列表的地方使用
+pub fn deserialize_trigger_actions_compat<'de, D>(deserializer: D) -> Result<Vec<TriggerAction>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let items = Vec::<serde_json::Value>::deserialize(deserializer)?;
+    let is_v1 = items.iter().any(|item| item.get("version").is_some());
+    let items = if is_v1 { migrate_v1_actions(items) } else { items };
+    parse_action_values(items).map_err(serde::de::Error::custom)
+}
+
+pub(crate) fn save_trigger_actions(app: &AppHandle, actions: &[TriggerAction]) -> Result<(), String> {
     let actions_path = get_actions_path(app);
 
     if let Some(parent) = actions_path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
 
+    let file = serde_json::json!({
+        "schema_version": TRIGGER_ACTIONS_SCHEMA_VERSION,
+        "actions": actions,
+    });
     let json =
-        serde_json::to_string_pretty(actions).map_err(|e| format!("序列化触发动作失败: {}", e))?;
+        serde_json::to_string_pretty(&file).map_err(|e| format!("序列化触发动作失败: {}", e))?;
 
-    fs::write(actions_path, json).map_err(|e| format!("保存触发动作失败: {}", e))
+    fs::write(&actions_path, &json).map_err(|e| format!("保存触发动作失败: {}", e))?;
+    crate::config_watcher::record_app_write(&actions_path, &json);
+    Ok(())
 }
 
 // 添加新函数
@@ -153,68 +460,530 @@ pub fn get_trigger_action_enabled_count(app: &tauri::AppHandle) -> Result<usize,
     Ok(actions.iter().filter(|a| a.enabled).count())
 }
 
-pub async fn execute_trigger_action(action: &TriggerAction) {
-    log::info!("开始执行触发动作: {}", action.name);
-
-    match action.version.as_str() {
-        "simple" => {
-            if let TriggerActionWorker::Simple { temp_plan_guid, pause_seconds, target_plan_guid } = &action.worker {
-                // 执行简单模式的逻辑
-                if let Err(e) = set_active_plan(temp_plan_guid) {
-                    log::error!("切换到临时计划失败: {}", e);
-                    send_notification("触发动作执行失败", &format!("切换到临时计划失败: {}", e));
-                    return;
+//获取已启用且校验通过、可以被派发的动作的个数
+pub fn get_trigger_action_eligible_count(app: &tauri::AppHandle) -> Result<usize, String> {
+    let app = app.clone();
+    let handle = std::thread::spawn(move || {
+        tauri::async_runtime::block_on(async move {
+            let actions = load_trigger_actions(app).await?;
+            let mut count = 0;
+            for action in eligible_actions(&actions) {
+                match is_valid_trigger_action(action).await {
+                    Ok(()) => count += 1,
+                    Err(e) => log::warn!("触发动作 {} 无法执行: {}", action.name, e),
                 }
+            }
+            Ok::<usize, String>(count)
+        })
+    });
+    handle.join().map_err(|_| "独立线程执行出错".to_string())?
+}
+
+pub async fn execute_trigger_action(action: &TriggerAction, trigger: JournalTrigger, cancel: &CancelToken) -> Result<(), String> {
+    // 动作自身的试运行开关只在本次执行范围内生效
+    dry_run::scope(action.dry_run, execute_in_scope(action, trigger, cancel)).await
+}
 
-                tokio::time::sleep(Duration::from_secs(*pause_seconds as u64)).await;
+async fn execute_in_scope(action: &TriggerAction, trigger: JournalTrigger, cancel: &CancelToken) -> Result<(), String> {
+    let dry_run = dry_run::is_enabled();
+    let prefix = if dry_run { "[试运行] " } else { "" };
+    log::info!("{}开始执行触发动作: {}（{}）", prefix, action.name, action.worker.kind());
 
-                if let Err(e) = set_active_plan(target_plan_guid) {
-                    log::error!("切换到目标计划失败: {}", e);
-                    send_notification("触发动作执行失败", &format!("切换到目标计划失败: {}", e));
-                } else {
-                    send_notification("触发动作执行完成", &format!("成功执行触发动作: {}", action.name));
+    let recorder = JournalRecorder::new(&action.id, &action.name, action.worker.kind(), trigger);
+    // 超时后只发出取消，让动作在下一个检查点停下并完成必要的恢复
+    let result = {
+        let run = run_trigger_action(action, &recorder, cancel);
+        tokio::pin!(run);
+        match action.timeout_seconds {
+            Some(seconds) => {
+                tokio::select! {
+                    result = &mut run => result,
+                    _ = tokio::time::sleep(Duration::from_secs(seconds as u64)) => {
+                        log::warn!("触发动作 {} 执行超时（{} 秒），正在取消", action.name, seconds);
+                        cancel.cancel(&format!("触发动作执行超时（{} 秒）", seconds));
+                        run.await
+                    }
                 }
             }
+            None => run.await,
+        }
+    };
+
+    match &result {
+        Ok(()) => {
+            let _ = send_notification(&format!("{}触发动作执行完成", prefix), &format!("成功执行触发动作: {}", action.name));
+        }
+        Err(e) => {
+            log::error!("{}触发动作 {} 执行失败: {}", prefix, action.name, e);
+            let _ = send_notification(&format!("{}触发动作执行失败", prefix), e);
+        }
+    }
+
+    let entry = recorder.finish(&result);
+    log::info!("触发动作 {} 执行结束，耗时 {} ms", action.name, entry.duration_ms);
+    result
+}
+
+async fn run_trigger_action(action: &TriggerAction, recorder: &JournalRecorder, cancel: &CancelToken) -> Result<(), String> {
+    match &action.worker {
+        TriggerActionWorker::Simple { temp_plan_guid, pause_seconds, target_plan_guid } => {
+            // 简单模式就是一个内置的工作流，切换到目标计划是收尾步骤，被取消时也会执行
+            let (steps, cleanup) = workflow::simple_workflow(temp_plan_guid, *pause_seconds, target_plan_guid);
+            workflow::run_workflow_with_cleanup(&steps, &cleanup, recorder, cancel).await
         },
-        "setting_switch" => {
-            // 未来实现
-        },
-        "workflow" => {
-            // 未来实现
+        TriggerActionWorker::SettingSwitch { plan_guid, values, reapply_scheme, restore_after_seconds } => {
+            // 先记录原值，用于失败回滚和延时恢复
+            let (plan, keys) = (plan_guid.clone(), values.clone());
+            let previous = dry_run::spawn_blocking(move || {
+                keys.iter()
+                    .map(|v| get_power_setting_value(&plan, &v.subgroup_guid, &v.setting_guid))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .await
+            .map_err(|e| format!("读取电源设置任务异常: {}", e))?
+            .map_err(|e| format!("读取电源设置原值失败: {}", e))?;
+
+            let targets: Vec<PowerSettingValue> = values
+                .iter()
+                .map(|v| PowerSettingValue { ac_value: v.ac_value, dc_value: v.dc_value })
+                .collect();
+            write_setting_values(plan_guid, values, &targets, &previous, *reapply_scheme, recorder, write_value_set, active_plan_guid)
+                .await
+                .map_err(|e| format!("写入电源设置失败: {}", e))?;
+
+            let Some(restore_after_seconds) = restore_after_seconds else {
+                return Ok(());
+            };
+
+            // 被取消时不再等待，立即恢复原值
+            let waited = cancel.sleep(Duration::from_secs(*restore_after_seconds as u64)).await;
+
+            write_setting_values(plan_guid, values, &previous, &targets, *reapply_scheme, recorder, write_value_set, active_plan_guid)
+                .await
+                .map_err(|e| format!("恢复电源设置失败: {}", e))?;
+            waited
         },
-        _ => log::error!("未知的触发动作类型: {}", action.version)
+        TriggerActionWorker::Workflow { steps } => workflow::run_workflow(steps, recorder, cancel).await,
     }
 }
 
-pub async fn is_valid_trigger_action(action: &TriggerAction) -> Result<(), String> {
-    match action.version.as_str() {
-        "simple" => {
-            if let TriggerActionWorker::Simple { temp_plan_guid, target_plan_guid, pause_seconds } = &action.worker {
-                //逐个检查并抛出异常
-                if !check_if_scheme_is_valid(temp_plan_guid) {
-                    log::error!("临时计划不存在: {}", temp_plan_guid);
-                    return Err(format!("临时计划不存在: {}", temp_plan_guid));
+// 不执行任何操作，列出触发动作会执行的步骤
+pub fn plan_trigger_action(action: &TriggerAction) -> Vec<PlannedStep> {
+    let mut planned = Vec::new();
+    match &action.worker {
+        TriggerActionWorker::Simple { temp_plan_guid, pause_seconds, target_plan_guid } => {
+            let (steps, cleanup) = workflow::simple_workflow(temp_plan_guid, *pause_seconds, target_plan_guid);
+            workflow::plan_steps(&steps, 0, &mut planned);
+            workflow::plan_steps(&cleanup, 0, &mut planned);
+        }
+        TriggerActionWorker::SettingSwitch { plan_guid, values, reapply_scheme, restore_after_seconds } => {
+            for value in values {
+                planned.push(PlannedStep::new(
+                    0,
+                    "write_setting",
+                    Some(&value.setting_guid),
+                    format!(
+                        "写入电源计划 {} 的设置 {}/{}: AC={} DC={}",
+                        plan_guid, value.subgroup_guid, value.setting_guid, value.ac_value, value.dc_value
+                    ),
+                ));
+            }
+            let reapply = || PlannedStep::new(0, "switch_plan", Some(plan_guid), format!("重新激活电源计划 {} 使设置生效", plan_guid));
+            if *reapply_scheme {
+                planned.push(reapply());
+            }
+            if let Some(seconds) = restore_after_seconds {
+                planned.push(PlannedStep::new(0, "wait", None, format!("等待 {} 秒", seconds)));
+                for value in values {
+                    planned.push(PlannedStep::new(
+                        0,
+                        "write_setting",
+                        Some(&value.setting_guid),
+                        format!("将电源计划 {} 的设置 {}/{} 恢复为原值", plan_guid, value.subgroup_guid, value.setting_guid),
+                    ));
                 }
-                if !check_if_scheme_is_valid(target_plan_guid) {
-                    log::error!("目标计划不存在: {}", target_plan_guid);
-                    return Err(format!("目标计划不存在: {}", target_plan_guid));
+                if *reapply_scheme {
+                    planned.push(reapply());
                 }
-                //检查pause_seconds
-                if *pause_seconds < 1 {
-                    log::error!("暂停时间必须大于0，当前: {}", pause_seconds);
-                    return Err(format!("暂停时间必须大于0，当前: {}", pause_seconds));
+            }
+        }
+        TriggerActionWorker::Workflow { steps } => workflow::plan_steps(steps, 0, &mut planned),
+    }
+    planned
+}
+
+// 返回触发动作会执行的步骤，不修改任何电源设置
+#[tauri::command]
+pub async fn simulate_trigger_action(app: AppHandle, action_id: String) -> Result<Vec<PlannedStep>, String> {
+    let actions = load_trigger_actions(app).await?;
+    let action = actions
+        .iter()
+        .find(|a| a.id == action_id)
+        .ok_or_else(|| format!("触发动作不存在: {}", action_id))?;
+    Ok(plan_trigger_action(action))
+}
+
+// 依次写入一组电源设置值，中途失败时把已写入的设置回滚为 rollback 中的值
+// write 为实际写入函数，active_plan 读取当前计划，正常执行时是 write_value_set 和 active_plan_guid
+#[allow(clippy::too_many_arguments)]
+async fn write_setting_values<W, A>(
+    plan_guid: &str,
+    values: &[SettingSwitchValue],
+    targets: &[PowerSettingValue],
+    rollback: &[PowerSettingValue],
+    reapply_scheme: bool,
+    recorder: &JournalRecorder,
+    write: W,
+    active_plan: A,
+) -> Result<(), String>
+where
+    W: Fn(&str, &str, &str, u32, u32) -> Result<(), String> + Copy + Send + 'static,
+    A: FnOnce() -> Option<String> + Send + 'static,
+{
+    let write_one = |value: &SettingSwitchValue, target: &PowerSettingValue| {
+        let (plan, subgroup, setting) = (plan_guid.to_string(), value.subgroup_guid.clone(), value.setting_guid.clone());
+        let (ac_value, dc_value) = (target.ac_value, target.dc_value);
+        dry_run::spawn_blocking(move || write(&plan, &subgroup, &setting, ac_value, dc_value))
+    };
+
+    for (index, (value, target)) in values.iter().zip(targets).enumerate() {
+        let timer = recorder.start_step("write_setting", Some(&value.setting_guid), 1);
+        let result = write_one(value, target)
+            .await
+            .map_err(|e| format!("写入电源设置任务异常: {}", e))
+            .and_then(|r| r);
+        recorder.finish_step(timer, &result);
+        if let Err(e) = result {
+            for (written, previous) in values[..index].iter().zip(rollback) {
+                if let Ok(Err(rollback_error)) = write_one(written, previous).await {
+                    log::warn!("回滚电源设置 {} 失败: {}", written.setting_guid, rollback_error);
                 }
             }
-            Ok(())
+            return Err(e);
+        }
+    }
+
+    if reapply_scheme {
+        // 只有该计划正在使用时才需要重新激活，否则会把用户切换到这个计划
+        let active_plan = dry_run::spawn_blocking(active_plan)
+            .await
+            .map_err(|e| format!("读取当前电源计划任务异常: {}", e))?;
+        if !active_plan.is_some_and(|active| active.eq_ignore_ascii_case(plan_guid)) {
+            log::info!("电源计划 {} 未在使用，写入后无需重新激活", plan_guid);
+            return Ok(());
         }
-        "setting_switch" => {
-            // 未来实现
+        let timer = recorder.start_step("switch_plan", Some(plan_guid), 1);
+        let guid = plan_guid.to_string();
+        let result = dry_run::spawn_blocking(move || set_active_plan(&guid))
+            .await
+            .map_err(|e| format!("切换电源计划任务异常: {}", e))
+            .and_then(|r| r);
+        recorder.finish_step(timer, &result);
+        result?;
+    }
+    Ok(())
+}
+
+pub async fn is_valid_trigger_action(action: &TriggerAction) -> Result<(), String> {
+    if action.timeout_seconds == Some(0) {
+        return Err("超时时间必须大于0".to_string());
+    }
+    action.condition.validate()?;
+    match &action.worker {
+        TriggerActionWorker::Simple { temp_plan_guid, target_plan_guid, pause_seconds } => {
+            //逐个检查并抛出异常
+            if !check_if_scheme_is_valid(temp_plan_guid) {
+                log::error!("临时计划不存在: {}", temp_plan_guid);
+                return Err(format!("临时计划不存在: {}", temp_plan_guid));
+            }
+            if !check_if_scheme_is_valid(target_plan_guid) {
+                log::error!("目标计划不存在: {}", target_plan_guid);
+                return Err(format!("目标计划不存在: {}", target_plan_guid));
+            }
+            //检查pause_seconds
+            if *pause_seconds < 1 {
+                log::error!("暂停时间必须大于0，当前: {}", pause_seconds);
+                return Err(format!("暂停时间必须大于0，当前: {}", pause_seconds));
+            }
             Ok(())
         }
-        "workflow" => {
-            // 未来实现
+        TriggerActionWorker::SettingSwitch { plan_guid, values, restore_after_seconds, .. } => {
+            if !check_if_scheme_is_valid(plan_guid) {
+                log::error!("电源计划不存在: {}", plan_guid);
+                return Err(format!("电源计划不存在: {}", plan_guid));
+            }
+            if values.is_empty() {
+                return Err("至少需要设置一个电源设置".to_string());
+            }
+            // 用设置自身的取值信息校验 AC/DC 值
+            for value in values {
+                let possible = enumerate_possible_settings(&value.subgroup_guid, &value.setting_guid)
+                    .map_err(|e| format!("读取电源设置 {} 的可选值失败: {}", value.setting_guid, e))?;
+                possible.validate_value(value.ac_value)
+                    .map_err(|e| format!("电源设置 {} 的 AC 值无效: {}", value.setting_guid, e))?;
+                possible.validate_value(value.dc_value)
+                    .map_err(|e| format!("电源设置 {} 的 DC 值无效: {}", value.setting_guid, e))?;
+            }
+            if *restore_after_seconds == Some(0) {
+                return Err("恢复等待时间必须大于0".to_string());
+            }
             Ok(())
         }
-        _ => Err(format!("未知的触发动作类型: {}", action.version))
+        TriggerActionWorker::Workflow { steps } => {
+            if steps.is_empty() {
+                return Err("工作流至少需要一个步骤".to_string());
+            }
+            workflow::validate_steps(steps)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_v1_trigger_actions_migrates_version_field() {
+        let content = r#"[
+            {
+                "id": "a1",
+                "name": "降频",
+                "version": "simple",
+                "enabled": true,
+                "worker": {
+                    "temp_plan_guid": "381B4222-F694-41F0-9685-FF5BB260DF2E",
+                    "pause_seconds": 3,
+                    "target_plan_guid": "8BD00ADD-ABF1-47CF-98B4-0E38E7999415"
+                }
+            }
+        ]"#;
+
+        let (actions, migrated_from) = parse_trigger_actions(content).unwrap();
+        assert_eq!(migrated_from, Some(1));
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].worker.kind(), "simple");
+    }
+
+    #[test]
+    fn test_parse_v2_trigger_actions() {
+        let content = r#"{
+            "schema_version": 2,
+            "actions": [
+                {
+                    "id": "a2",
+                    "name": "工作流",
+                    "enabled": false,
+                    "worker": {
+                        "type": "workflow",
+                        "steps": [
+                            { "type": "wait", "seconds": 2 },
+                            { "type": "refresh_monitor", "on_error": { "policy": "continue" } }
+                        ]
+                    }
+                }
+            ]
+        }"#;
+
+        let (actions, migrated_from) = parse_trigger_actions(content).unwrap();
+        assert_eq!(migrated_from, None);
+        assert_eq!(actions[0].worker.kind(), "workflow");
+    }
+
+    #[test]
+    fn test_parse_trigger_actions_rejects_unknown_type() {
+        // 旧格式下空结构体会把任意对象解析成错误的类型，新格式必须明确报错
+        let content = r#"{
+            "schema_version": 2,
+            "actions": [
+                { "id": "a3", "name": "未知", "enabled": false, "worker": { "type": "teleport" } }
+            ]
+        }"#;
+
+        let err = parse_trigger_actions(content).unwrap_err();
+        assert!(err.contains("未知"));
+        assert!(err.contains("teleport"));
+    }
+
+    #[test]
+    fn test_parse_trigger_actions_rejects_newer_schema() {
+        let content = r#"{ "schema_version": 99, "actions": [] }"#;
+        assert!(parse_trigger_actions(content).is_err());
+    }
+
+    fn action(id: &str, enabled: bool, priority: i32) -> TriggerAction {
+        TriggerAction {
+            id: id.to_string(),
+            name: id.to_string(),
+            enabled,
+            priority,
+            ..Default::default()
+        }
+    }
+
+    fn ids(actions: Vec<&TriggerAction>) -> Vec<&str> {
+        actions.iter().map(|a| a.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_select_actions_by_policy() {
+        let actions = vec![
+            action("low", true, 0),
+            action("disabled", false, 9),
+            action("high", true, 5),
+            action("low2", true, 0),
+        ];
+
+        let select = |policy, last| ids(select_actions(eligible_actions(&actions), policy, last));
+
+        assert_eq!(select(DispatchPolicy::FirstMatch, None), vec!["high"]);
+        assert_eq!(select(DispatchPolicy::AllInOrder, None), vec!["high", "low", "low2"]);
+
+        assert_eq!(select(DispatchPolicy::RoundRobin, None), vec!["high"]);
+        assert_eq!(select(DispatchPolicy::RoundRobin, Some("high")), vec!["low"]);
+        assert_eq!(select(DispatchPolicy::RoundRobin, Some("low2")), vec!["high"]);
+        // 上次执行的动作已被禁用时从头开始
+        assert_eq!(select(DispatchPolicy::RoundRobin, Some("disabled")), vec!["high"]);
+    }
+
+    #[test]
+    fn test_plan_trigger_action_lists_branches_and_restore() {
+        let content = r#"{
+            "schema_version": 2,
+            "actions": [
+                {
+                    "id": "a4",
+                    "name": "分支",
+                    "enabled": true,
+                    "worker": {
+                        "type": "workflow",
+                        "steps": [
+                            {
+                                "type": "branch",
+                                "condition": { "type": "still_above_threshold", "threshold": 3.5 },
+                                "then_steps": [{ "type": "switch_plan", "plan_guid": "P1" }]
+                            },
+                            { "type": "wait", "seconds": 2, "on_error": { "policy": "continue" } }
+                        ]
+                    }
+                }
+            ]
+        }"#;
+        let (actions, _) = parse_trigger_actions(content).unwrap();
+        let planned = plan_trigger_action(&actions[0]);
+        let outline: Vec<(usize, &str)> = planned.iter().map(|p| (p.depth, p.kind.as_str())).collect();
+        assert_eq!(outline, vec![(0, "branch"), (1, "then"), (2, "switch_plan"), (1, "else"), (0, "wait")]);
+        assert_eq!(planned[0].description, "判断频率是否仍高于 3.50 GHz");
+        assert_eq!(planned[4].description, "等待 2 秒（失败后继续）");
+
+        let switch = TriggerAction {
+            worker: TriggerActionWorker::SettingSwitch {
+                plan_guid: "P".to_string(),
+                values: vec![SettingSwitchValue {
+                    subgroup_guid: "S".to_string(),
+                    setting_guid: "V".to_string(),
+                    ac_value: 1,
+                    dc_value: 0,
+                }],
+                reapply_scheme: true,
+                restore_after_seconds: Some(30),
+            },
+            ..Default::default()
+        };
+        let kinds: Vec<String> = plan_trigger_action(&switch).into_iter().map(|p| p.kind).collect();
+        assert_eq!(kinds, vec!["write_setting", "switch_plan", "wait", "write_setting", "switch_plan"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_simple_action_still_switches_to_target_plan() {
+        let action = TriggerAction {
+            worker: TriggerActionWorker::Simple {
+                temp_plan_guid: "TEMP".to_string(),
+                pause_seconds: 30,
+                target_plan_guid: "TARGET".to_string(),
+            },
+            ..Default::default()
+        };
+        let recorder = JournalRecorder::new(&action.id, &action.name, action.worker.kind(), JournalTrigger::threshold(&[4500], 4.0));
+        let cancel = CancelToken::new();
+
+        // 试运行范围内切换计划只记录不执行，可以从步骤中看到实际切换到了哪个计划
+        let (result, ()) = dry_run::scope(true, async {
+            tokio::join!(run_trigger_action(&action, &recorder, &cancel), async {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                cancel.cancel("触发动作执行超时（1 秒）");
+            })
+        })
+        .await;
+
+        assert_eq!(result.unwrap_err(), "触发动作执行超时（1 秒）");
+        let steps = recorder.steps();
+        let outline: Vec<(&str, Option<&str>, bool)> = steps
+            .iter()
+            .map(|s| (s.kind.as_str(), s.target.as_deref(), s.success))
+            .collect();
+        assert_eq!(
+            outline,
+            vec![("switch_plan", Some("TEMP"), true), ("wait", None, false), ("switch_plan", Some("TARGET"), true)]
+        );
+        assert_eq!(steps[2].planned, vec!["powercfg /setactive TARGET"]);
+    }
+
+    static WRITES: Lazy<Mutex<Vec<(String, u32)>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+    // 写入 S3 时失败，其余写入记录下来
+    fn failing_write(_plan: &str, _subgroup: &str, setting: &str, ac_value: u32, _dc_value: u32) -> Result<(), String> {
+        if setting == "S3" {
+            return Err("拒绝访问".to_string());
+        }
+        WRITES.lock().unwrap().push((setting.to_string(), ac_value));
+        Ok(())
+    }
+
+    // 正在使用的是另一个计划，不依赖本机的电源计划
+    fn other_plan_active() -> Option<String> {
+        Some("OTHER".to_string())
+    }
+
+    #[tokio::test]
+    async fn test_write_setting_values_rolls_back_on_failure() {
+        let values: Vec<SettingSwitchValue> = ["S1", "S2", "S3"]
+            .iter()
+            .map(|setting| SettingSwitchValue {
+                subgroup_guid: "SUB".to_string(),
+                setting_guid: setting.to_string(),
+                ac_value: 0,
+                dc_value: 0,
+            })
+            .collect();
+        let values_of = |ac: [u32; 3]| ac.iter().map(|&ac_value| PowerSettingValue { ac_value, dc_value: 0 }).collect::<Vec<_>>();
+        let (targets, previous) = (values_of([10, 20, 30]), values_of([1, 2, 3]));
+        let recorder = JournalRecorder::new("a", "a", "setting_switch", JournalTrigger::threshold(&[4500], 4.0));
+
+        let result =
+            write_setting_values("PLAN", &values, &targets, &previous, true, &recorder, failing_write, other_plan_active).await;
+        assert_eq!(result.unwrap_err(), "拒绝访问");
+        // 已写入的 S1、S2 按原值回滚，失败后不再重新激活计划
+        assert_eq!(
+            *WRITES.lock().unwrap(),
+            vec![("S1".to_string(), 10), ("S2".to_string(), 20), ("S1".to_string(), 1), ("S2".to_string(), 2)]
+        );
+        let outline: Vec<(String, bool)> = recorder.steps().into_iter().map(|s| (s.target.unwrap(), s.success)).collect();
+        assert_eq!(outline, vec![("S1".to_string(), true), ("S2".to_string(), true), ("S3".to_string(), false)]);
+
+        // 全部写入成功，但计划没有在使用，不应切换过去
+        WRITES.lock().unwrap().clear();
+        let recorder = JournalRecorder::new("a", "a", "setting_switch", JournalTrigger::threshold(&[4500], 4.0));
+        write_setting_values("PLAN", &values[..2], &targets, &previous, true, &recorder, failing_write, other_plan_active)
+            .await
+            .unwrap();
+        assert_eq!(WRITES.lock().unwrap().len(), 2);
+        assert!(recorder.steps().iter().all(|s| s.kind == "write_setting"));
+    }
+
+    #[test]
+    fn test_select_actions_without_eligible_actions() {
+        let actions = vec![action("a", false, 0)];
+        assert!(select_actions(eligible_actions(&actions), DispatchPolicy::RoundRobin, Some("a")).is_empty());
+        assert!(DispatchPolicy::from_setting("random").is_err());
     }
 }
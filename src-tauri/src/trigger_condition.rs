@@ -0,0 +1,325 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::power_source::PowerSource;
+
+// 所有动作共用的条件状态，记录持续超限的开始时间和上次触发时间
+pub static CONDITION_TRACKER: Lazy<Mutex<ConditionTracker>> = Lazy::new(|| Mutex::new(ConditionTracker::default()));
+
+fn default_min_cores() -> usize {
+    1
+}
+
+// 单个触发动作的触发条件，未填写的项使用全局设置
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TriggerCondition {
+    // 频率阈值（GHz），为空则使用全局 frequency_threshold
+    #[serde(default)]
+    pub threshold: Option<f64>,
+    // 至少多少个核心超过阈值才算满足
+    #[serde(default = "default_min_cores")]
+    pub min_cores: usize,
+    // 需要持续满足多少秒才触发，0 表示立即触发
+    #[serde(default)]
+    pub sustain_seconds: u64,
+    // 触发后多少秒内不再触发，为空则使用全局 alert_debounce_seconds
+    #[serde(default)]
+    pub cooldown_seconds: Option<u64>,
+    // 只在指定的频率获取模式下生效，为空则不限制
+    #[serde(default)]
+    pub frequency_mode: Option<String>,
+    // 只在指定的供电方式下生效，为空则不限制
+    #[serde(default)]
+    pub power_source: Option<PowerSource>,
+    // CPU 温度阈值（摄氏度），设置后温度也需高于该值才算满足；读不到温度时视为不满足
+    #[serde(default)]
+    pub temperature_threshold: Option<f32>,
+    // 占用率上限（%），设置后只统计占用率低于该值的超频核心，用于识别空闲时仍在高频运行的情况
+    #[serde(default)]
+    pub max_load: Option<f32>,
+}
+
+impl Default for TriggerCondition {
+    fn default() -> Self {
+        Self {
+            threshold: None,
+            min_cores: default_min_cores(),
+            sustain_seconds: 0,
+            cooldown_seconds: None,
+            frequency_mode: None,
+            power_source: None,
+            temperature_threshold: None,
+            max_load: None,
+        }
+    }
+}
+
+impl TriggerCondition {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(threshold) = self.threshold {
+            if threshold <= 0.0 {
+                return Err(format!("触发阈值必须大于0，当前: {}", threshold));
+            }
+        }
+        if self.temperature_threshold.is_some_and(|t| t <= 0.0) {
+            return Err("温度阈值必须大于0".to_string());
+        }
+        if let Some(max_load) = self.max_load {
+            if max_load <= 0.0 || max_load > 100.0 {
+                return Err(format!("占用率上限必须在 0-100 之间，当前: {}", max_load));
+            }
+        }
+        if self.min_cores < 1 {
+            return Err("最少超限核心数必须大于0".to_string());
+        }
+        if let Some(mode) = &self.frequency_mode {
+            if mode != "1" && mode != "2" {
+                return Err(format!("未知的频率获取模式: {}", mode));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn effective_threshold(&self, sample: &FrequencySample) -> f64 {
+        self.threshold.unwrap_or(sample.global_threshold)
+    }
+}
+
+// 一次频率采样及当时的全局设置
+#[derive(Clone, Debug)]
+pub struct FrequencySample {
+    pub frequencies: Vec<u64>,
+    pub frequency_mode: String,
+    pub global_threshold: f64,
+    pub global_cooldown_seconds: u64,
+    // 采样时的供电方式，无法判断时为空
+    pub power_source: Option<PowerSource>,
+    // 采样时的 CPU 温度（摄氏度），没有传感器时为空
+    pub temperature: Option<f32>,
+    // 各核心占用率（%）
+    pub loads: Vec<f32>,
+}
+
+// 某个频率对应的占用率：核心数一致时取对应核心，否则（如 CalcMhz 模式只有一个频率）取平均占用率
+pub fn core_load(frequencies: &[u64], loads: &[f32], index: usize) -> Option<f32> {
+    if loads.len() == frequencies.len() {
+        return loads.get(index).copied();
+    }
+    if loads.is_empty() {
+        return None;
+    }
+    Some(loads.iter().sum::<f32>() / loads.len() as f32)
+}
+
+#[derive(Default, Debug)]
+struct ConditionState {
+    exceeding_since: Option<u64>,
+    last_fired: Option<u64>,
+}
+
+// 按动作记录条件状态，时间由调用方传入（秒），便于测试
+#[derive(Default, Debug)]
+pub struct ConditionTracker {
+    states: HashMap<String, ConditionState>,
+}
+
+impl ConditionTracker {
+    // 根据本次采样更新动作的条件状态，返回条件是否满足（已考虑持续时间和冷却时间）
+    pub fn check(&mut self, action_id: &str, condition: &TriggerCondition, sample: &FrequencySample, now: u64) -> bool {
+        let state = self.states.entry(action_id.to_string()).or_default();
+
+        let mode_matches = condition
+            .frequency_mode
+            .as_ref()
+            .is_none_or(|mode| mode == &sample.frequency_mode);
+        // 限定了供电方式时，供电方式未知也视为不满足
+        let source_matches = condition
+            .power_source
+            .is_none_or(|source| sample.power_source == Some(source));
+        let temperature_matches = condition
+            .temperature_threshold
+            .is_none_or(|limit| sample.temperature.is_some_and(|t| t > limit));
+        let threshold = condition.effective_threshold(sample);
+        // 设置了占用率上限时，高负载下的高频属于正常情况，不计入
+        let exceeded = sample
+            .frequencies
+            .iter()
+            .enumerate()
+            .filter(|(_, &freq)| freq as f64 / 1000.0 > threshold)
+            .filter(|(index, _)| {
+                condition.max_load.is_none_or(|max_load| {
+                    core_load(&sample.frequencies, &sample.loads, *index).is_some_and(|load| load < max_load)
+                })
+            })
+            .count();
+
+        // 条件中断后重新计算持续时间
+        if !mode_matches || !source_matches || !temperature_matches || exceeded < condition.min_cores {
+            state.exceeding_since = None;
+            return false;
+        }
+
+        let since = *state.exceeding_since.get_or_insert(now);
+        if now.saturating_sub(since) < condition.sustain_seconds {
+            return false;
+        }
+
+        let cooldown = condition.cooldown_seconds.unwrap_or(sample.global_cooldown_seconds);
+        match state.last_fired {
+            Some(last_fired) => now.saturating_sub(last_fired) >= cooldown,
+            None => true,
+        }
+    }
+
+    // 动作被派发后记录触发时间，开始计算冷却
+    pub fn record_fired(&mut self, action_id: &str, now: u64) {
+        self.states.entry(action_id.to_string()).or_default().last_fired = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(frequencies: &[u64]) -> FrequencySample {
+        FrequencySample {
+            frequencies: frequencies.to_vec(),
+            frequency_mode: "1".to_string(),
+            global_threshold: 3.0,
+            global_cooldown_seconds: 10,
+            power_source: Some(PowerSource::Ac),
+            temperature: Some(60.0),
+            loads: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_default_condition_uses_global_settings() {
+        let mut tracker = ConditionTracker::default();
+        let condition = TriggerCondition::default();
+
+        assert!(!tracker.check("a", &condition, &sample(&[2900, 2800]), 0));
+        assert!(tracker.check("a", &condition, &sample(&[3100, 2800]), 1));
+        tracker.record_fired("a", 1);
+        // 全局冷却时间 10 秒
+        assert!(!tracker.check("a", &condition, &sample(&[3100, 2800]), 5));
+        assert!(tracker.check("a", &condition, &sample(&[3100, 2800]), 11));
+    }
+
+    #[test]
+    fn test_per_action_threshold_and_min_cores() {
+        let mut tracker = ConditionTracker::default();
+        let mild = TriggerCondition {
+            threshold: Some(3.2),
+            ..Default::default()
+        };
+        let harsh = TriggerCondition {
+            threshold: Some(3.8),
+            min_cores: 2,
+            ..Default::default()
+        };
+
+        let frequencies = sample(&[3500, 3900, 3000]);
+        assert!(tracker.check("mild", &mild, &frequencies, 0));
+        assert!(!tracker.check("harsh", &harsh, &frequencies, 0));
+        assert!(tracker.check("harsh", &harsh, &sample(&[3900, 3900, 3000]), 1));
+    }
+
+    #[test]
+    fn test_sustain_resets_when_condition_breaks() {
+        let mut tracker = ConditionTracker::default();
+        let condition = TriggerCondition {
+            sustain_seconds: 5,
+            cooldown_seconds: Some(0),
+            ..Default::default()
+        };
+
+        assert!(!tracker.check("a", &condition, &sample(&[3500]), 0));
+        assert!(!tracker.check("a", &condition, &sample(&[3500]), 4));
+        assert!(!tracker.check("a", &condition, &sample(&[2500]), 5));
+        assert!(!tracker.check("a", &condition, &sample(&[3500]), 6));
+        assert!(tracker.check("a", &condition, &sample(&[3500]), 11));
+    }
+
+    #[test]
+    fn test_frequency_mode_requirement() {
+        let mut tracker = ConditionTracker::default();
+        let condition = TriggerCondition {
+            frequency_mode: Some("2".to_string()),
+            ..Default::default()
+        };
+        assert!(!tracker.check("a", &condition, &sample(&[3500]), 0));
+
+        let mut calcmhz = sample(&[3500]);
+        calcmhz.frequency_mode = "2".to_string();
+        assert!(tracker.check("a", &condition, &calcmhz, 1));
+
+        assert!(TriggerCondition { frequency_mode: Some("3".to_string()), ..Default::default() }.validate().is_err());
+        assert!(TriggerCondition { min_cores: 0, ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_power_source_requirement() {
+        let mut tracker = ConditionTracker::default();
+        let condition = TriggerCondition {
+            power_source: Some(PowerSource::Battery),
+            ..Default::default()
+        };
+        assert!(!tracker.check("a", &condition, &sample(&[3500]), 0));
+
+        let mut on_battery = sample(&[3500]);
+        on_battery.power_source = Some(PowerSource::Battery);
+        assert!(tracker.check("a", &condition, &on_battery, 1));
+
+        let mut unknown = sample(&[3500]);
+        unknown.power_source = None;
+        assert!(!tracker.check("b", &condition, &unknown, 2));
+    }
+
+    #[test]
+    fn test_temperature_requirement() {
+        let mut tracker = ConditionTracker::default();
+        let condition = TriggerCondition {
+            temperature_threshold: Some(80.0),
+            ..Default::default()
+        };
+        assert!(!tracker.check("a", &condition, &sample(&[3500]), 0));
+
+        let mut hot = sample(&[3500]);
+        hot.temperature = Some(85.5);
+        assert!(tracker.check("a", &condition, &hot, 1));
+
+        let mut no_sensor = sample(&[3500]);
+        no_sensor.temperature = None;
+        assert!(!tracker.check("b", &condition, &no_sensor, 2));
+    }
+
+    #[test]
+    fn test_high_clock_at_low_load() {
+        let mut tracker = ConditionTracker::default();
+        let condition = TriggerCondition {
+            max_load: Some(20.0),
+            ..Default::default()
+        };
+
+        // 两个核心都在高频，但只有空闲的核心计入
+        let mut busy = sample(&[3500, 3600]);
+        busy.loads = vec![95.0, 80.0];
+        assert!(!tracker.check("a", &condition, &busy, 0));
+
+        let mut idle = sample(&[3500, 3600]);
+        idle.loads = vec![95.0, 5.0];
+        assert!(tracker.check("a", &condition, &idle, 1));
+
+        // CalcMhz 模式只有一个频率，使用平均占用率
+        let mut single = sample(&[3500]);
+        single.loads = vec![10.0, 15.0, 20.0];
+        assert!(tracker.check("b", &condition, &single, 2));
+
+        // 没有占用率数据时视为不满足
+        assert!(!tracker.check("c", &condition, &sample(&[3500]), 3));
+        assert!(TriggerCondition { max_load: Some(120.0), ..Default::default() }.validate().is_err());
+    }
+}
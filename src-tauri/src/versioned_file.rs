@@ -0,0 +1,125 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 程序所在目录，规则、定时任务等配置文件都保存在这里
+pub fn exe_dir() -> PathBuf {
+    env::current_exe()
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .to_path_buf()
+}
+
+// 程序目录下带 schema_version 的 JSON 配置文件
+// label 用于错误信息，例如 "规则" 会得到 "读取规则文件失败"
+pub struct VersionedFile {
+    pub file_name: &'static str,
+    pub label: &'static str,
+    pub schema_version: u32,
+}
+
+impl VersionedFile {
+    pub const fn new(file_name: &'static str, label: &'static str, schema_version: u32) -> Self {
+        Self {
+            file_name,
+            label,
+            schema_version,
+        }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        exe_dir().join(self.file_name)
+    }
+
+    // 解析文件内容并检查版本，返回顶层对象
+    pub fn parse_value(&self, content: &str) -> Result<Value, String> {
+        let value: Value =
+            serde_json::from_str(content).map_err(|e| format!("解析{}文件失败: {}", self.label, e))?;
+        let version = value
+            .get("schema_version")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| format!("{}文件缺少 schema_version 字段", self.label))?;
+        if version != self.schema_version as u64 {
+            return Err(format!(
+                "不支持的{}文件版本: {}（当前支持 {}）",
+                self.label, version, self.schema_version
+            ));
+        }
+        Ok(value)
+    }
+
+    // 整个顶层对象就是配置内容
+    pub fn parse<T: DeserializeOwned>(&self, content: &str) -> Result<T, String> {
+        serde_json::from_value(self.parse_value(content)?).map_err(|e| format!("解析{}失败: {}", self.label, e))
+    }
+
+    // 配置内容保存在顶层的 key 字段中，缺少该字段时使用默认值
+    pub fn parse_field<T: DeserializeOwned + Default>(&self, content: &str, key: &str) -> Result<T, String> {
+        match self.parse_value(content)?.get_mut(key).map(Value::take) {
+            Some(field) => serde_json::from_value(field).map_err(|e| format!("解析{}失败: {}", self.label, e)),
+            None => Ok(T::default()),
+        }
+    }
+
+    // 读取文件内容，文件不存在时返回 None
+    fn read_content(&self) -> Result<Option<String>, String> {
+        let path = self.path();
+        if !path.exists() {
+            return Ok(None);
+        }
+        fs::read_to_string(&path)
+            .map(Some)
+            .map_err(|e| format!("读取{}文件失败: {}", self.label, e))
+    }
+
+    pub fn read<T: DeserializeOwned + Default>(&self) -> Result<T, String> {
+        match self.read_content()? {
+            Some(content) => self.parse(&content),
+            None => Ok(T::default()),
+        }
+    }
+
+    pub fn read_field<T: DeserializeOwned + Default>(&self, key: &str) -> Result<T, String> {
+        match self.read_content()? {
+            Some(content) => self.parse_field(&content, key),
+            None => Ok(T::default()),
+        }
+    }
+
+    // body 必须是对象，写入时加上 schema_version 字段
+    pub fn write(&self, body: Value) -> Result<(), String> {
+        let mut file = serde_json::Map::new();
+        file.insert("schema_version".to_string(), Value::from(self.schema_version));
+        match body {
+            Value::Object(fields) => file.extend(fields),
+            _ => return Err(format!("序列化{}失败: 内容不是对象", self.label)),
+        }
+        let content =
+            serde_json::to_string_pretty(&file).map_err(|e| format!("序列化{}失败: {}", self.label, e))?;
+        fs::write(self.path(), content).map_err(|e| format!("保存{}失败: {}", self.label, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: VersionedFile = VersionedFile::new("test.json", "测试", 2);
+
+    #[test]
+    fn test_parse_checks_version() {
+        let items: Vec<u32> = FILE.parse_field(r#"{"schema_version":2,"items":[1,2]}"#, "items").unwrap();
+        assert_eq!(items, vec![1, 2]);
+        let missing: Vec<u32> = FILE.parse_field(r#"{"schema_version":2}"#, "items").unwrap();
+        assert!(missing.is_empty());
+
+        let err = FILE.parse_field::<Vec<u32>>(r#"{"schema_version":1,"items":[]}"#, "items").unwrap_err();
+        assert!(err.contains("不支持的测试文件版本"));
+        let err = FILE.parse_field::<Vec<u32>>(r#"{"items":[]}"#, "items").unwrap_err();
+        assert_eq!(err, "测试文件缺少 schema_version 字段");
+        assert!(FILE.parse::<Value>("not json").unwrap_err().starts_with("解析测试文件失败"));
+    }
+}
@@ -0,0 +1,558 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use tokio::time::Duration;
+
+use crate::action_executor::CancelToken;
+use crate::action_journal::JournalRecorder;
+use crate::dry_run;
+use crate::monitor::{Monitor, MONITOR};
+use crate::notification::send_notification;
+use crate::power_plan::{check_if_scheme_is_valid, get_power_plans, set_active_plan};
+use crate::settings_store;
+use crate::PowerPlanUtils::GetPowerPlans::{enumerate_possible_settings, write_value_set};
+
+// 工作流中单个步骤要做的事
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkflowStepKind {
+    SwitchPlan {
+        plan_guid: String,
+    },
+    Wait {
+        seconds: u32,
+    },
+    WriteSetting {
+        plan_guid: String,
+        subgroup_guid: String,
+        setting_guid: String,
+        ac_value: u32,
+        dc_value: u32,
+    },
+    Notify {
+        title: String,
+        body: String,
+    },
+    RefreshMonitor,
+    Branch {
+        condition: WorkflowCondition,
+        #[serde(default)]
+        then_steps: Vec<WorkflowStep>,
+        #[serde(default)]
+        else_steps: Vec<WorkflowStep>,
+    },
+}
+
+impl WorkflowStepKind {
+    // 与序列化时的 type 字段一致，用于执行日志
+    pub fn name(&self) -> &'static str {
+        match self {
+            WorkflowStepKind::SwitchPlan { .. } => "switch_plan",
+            WorkflowStepKind::Wait { .. } => "wait",
+            WorkflowStepKind::WriteSetting { .. } => "write_setting",
+            WorkflowStepKind::Notify { .. } => "notify",
+            WorkflowStepKind::RefreshMonitor => "refresh_monitor",
+            WorkflowStepKind::Branch { .. } => "branch",
+        }
+    }
+
+    // 步骤操作的对象，用于执行日志
+    pub fn target(&self) -> Option<&str> {
+        match self {
+            WorkflowStepKind::SwitchPlan { plan_guid } => Some(plan_guid),
+            WorkflowStepKind::WriteSetting { setting_guid, .. } => Some(setting_guid),
+            _ => None,
+        }
+    }
+
+    // 步骤的文字说明，用于试运行模拟
+    pub fn describe(&self) -> String {
+        match self {
+            WorkflowStepKind::SwitchPlan { plan_guid } => format!("切换到电源计划 {}", plan_guid),
+            WorkflowStepKind::Wait { seconds } => format!("等待 {} 秒", seconds),
+            WorkflowStepKind::WriteSetting { plan_guid, subgroup_guid, setting_guid, ac_value, dc_value } => format!(
+                "写入电源计划 {} 的设置 {}/{}: AC={} DC={}",
+                plan_guid, subgroup_guid, setting_guid, ac_value, dc_value
+            ),
+            WorkflowStepKind::Notify { title, body } => format!("发送通知: {} - {}", title, body),
+            WorkflowStepKind::RefreshMonitor => "立即刷新频率监控".to_string(),
+            WorkflowStepKind::Branch { condition, .. } => format!("判断{}", condition.describe()),
+        }
+    }
+}
+
+// 分支步骤的判断条件
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkflowCondition {
+    // 重新采样一次频率，判断是否仍有核心高于阈值（为空则使用全局阈值）
+    StillAboveThreshold { threshold: Option<f64> },
+    ActivePlanIs { plan_guid: String },
+}
+
+impl WorkflowCondition {
+    pub fn describe(&self) -> String {
+        match self {
+            WorkflowCondition::StillAboveThreshold { threshold: Some(threshold) } => {
+                format!("频率是否仍高于 {:.2} GHz", threshold)
+            }
+            WorkflowCondition::StillAboveThreshold { threshold: None } => "频率是否仍高于全局阈值".to_string(),
+            WorkflowCondition::ActivePlanIs { plan_guid } => format!("当前电源计划是否为 {}", plan_guid),
+        }
+    }
+}
+
+// 步骤失败后的处理方式
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum ErrorPolicy {
+    #[default]
+    Abort,
+    Continue,
+    Retry {
+        max_attempts: u32,
+        #[serde(default)]
+        delay_seconds: u32,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WorkflowStep {
+    #[serde(flatten)]
+    pub kind: WorkflowStepKind,
+    // 单个步骤的超时时间，为空则不限制
+    #[serde(default)]
+    pub timeout_seconds: Option<u32>,
+    #[serde(default)]
+    pub on_error: ErrorPolicy,
+}
+
+impl WorkflowStep {
+    pub fn new(kind: WorkflowStepKind) -> Self {
+        Self {
+            kind,
+            timeout_seconds: None,
+            on_error: ErrorPolicy::Abort,
+        }
+    }
+}
+
+// 内置工作流：切换到临时计划，等待，再切换到目标计划
+// 返回 (主体步骤, 收尾步骤)，切换到目标计划放在收尾步骤中，被取消或超时也会执行
+pub fn simple_workflow(temp_plan_guid: &str, pause_seconds: u32, target_plan_guid: &str) -> (Vec<WorkflowStep>, Vec<WorkflowStep>) {
+    let steps = vec![
+        WorkflowStep::new(WorkflowStepKind::SwitchPlan {
+            plan_guid: temp_plan_guid.to_string(),
+        }),
+        WorkflowStep::new(WorkflowStepKind::Wait {
+            seconds: pause_seconds,
+        }),
+    ];
+    let cleanup = vec![WorkflowStep::new(WorkflowStepKind::SwitchPlan {
+        plan_guid: target_plan_guid.to_string(),
+    })];
+    (steps, cleanup)
+}
+
+// 收集工作流中引用到的所有电源计划 GUID
+pub fn collect_plan_guids(steps: &[WorkflowStep], guids: &mut Vec<String>) {
+    for step in steps {
+        match &step.kind {
+            WorkflowStepKind::SwitchPlan { plan_guid } | WorkflowStepKind::WriteSetting { plan_guid, .. } => {
+                guids.push(plan_guid.clone());
+            }
+            WorkflowStepKind::Branch { condition, then_steps, else_steps } => {
+                if let WorkflowCondition::ActivePlanIs { plan_guid } = condition {
+                    guids.push(plan_guid.clone());
+                }
+                collect_plan_guids(then_steps, guids);
+                collect_plan_guids(else_steps, guids);
+            }
+            _ => {}
+        }
+    }
+}
+
+// 按映射表替换工作流中的电源计划 GUID（键为大写 GUID）
+pub fn remap_plan_guids(steps: &mut [WorkflowStep], guid_map: &HashMap<String, String>) {
+    let remap = |guid: &mut String| {
+        if let Some(new_guid) = guid_map.get(&guid.to_uppercase()) {
+            *guid = new_guid.clone();
+        }
+    };
+
+    for step in steps {
+        match &mut step.kind {
+            WorkflowStepKind::SwitchPlan { plan_guid } | WorkflowStepKind::WriteSetting { plan_guid, .. } => {
+                remap(plan_guid);
+            }
+            WorkflowStepKind::Branch { condition, then_steps, else_steps } => {
+                if let WorkflowCondition::ActivePlanIs { plan_guid } = condition {
+                    remap(plan_guid);
+                }
+                remap_plan_guids(then_steps, guid_map);
+                remap_plan_guids(else_steps, guid_map);
+            }
+            _ => {}
+        }
+    }
+}
+
+// 校验工作流，包括嵌套分支中的步骤
+pub fn validate_steps(steps: &[WorkflowStep]) -> Result<(), String> {
+    for (index, step) in steps.iter().enumerate() {
+        let position = index + 1;
+        if step.timeout_seconds == Some(0) {
+            return Err(format!("第 {} 步的超时时间必须大于0", position));
+        }
+        if let ErrorPolicy::Retry { max_attempts, .. } = step.on_error {
+            if max_attempts < 1 {
+                return Err(format!("第 {} 步的重试次数必须大于0", position));
+            }
+            // 重试分支会把已经成功的子步骤再执行一遍，重试应设置在分支内的步骤上
+            if let WorkflowStepKind::Branch { .. } = step.kind {
+                return Err(format!("第 {} 步是分支，不能设置失败重试，请在分支内的步骤上设置", position));
+            }
+        }
+
+        match &step.kind {
+            WorkflowStepKind::SwitchPlan { plan_guid } => {
+                if !check_if_scheme_is_valid(plan_guid) {
+                    return Err(format!("第 {} 步的电源计划不存在: {}", position, plan_guid));
+                }
+            }
+            WorkflowStepKind::WriteSetting { plan_guid, subgroup_guid, setting_guid, ac_value, dc_value } => {
+                if !check_if_scheme_is_valid(plan_guid) {
+                    return Err(format!("第 {} 步的电源计划不存在: {}", position, plan_guid));
+                }
+                let possible = enumerate_possible_settings(subgroup_guid, setting_guid)
+                    .map_err(|e| format!("第 {} 步读取电源设置可选值失败: {}", position, e))?;
+                possible
+                    .validate_value(*ac_value)
+                    .map_err(|e| format!("第 {} 步的 AC 值无效: {}", position, e))?;
+                possible
+                    .validate_value(*dc_value)
+                    .map_err(|e| format!("第 {} 步的 DC 值无效: {}", position, e))?;
+            }
+            WorkflowStepKind::Branch { condition, then_steps, else_steps } => {
+                if let WorkflowCondition::ActivePlanIs { plan_guid } = condition {
+                    if !check_if_scheme_is_valid(plan_guid) {
+                        return Err(format!("第 {} 步判断的电源计划不存在: {}", position, plan_guid));
+                    }
+                }
+                validate_steps(then_steps).map_err(|e| format!("第 {} 步的满足分支中: {}", position, e))?;
+                validate_steps(else_steps).map_err(|e| format!("第 {} 步的不满足分支中: {}", position, e))?;
+            }
+            WorkflowStepKind::Wait { .. } | WorkflowStepKind::Notify { .. } | WorkflowStepKind::RefreshMonitor => {}
+        }
+    }
+    Ok(())
+}
+
+// 试运行模拟出的单个步骤，depth 为所在分支的嵌套层级
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct PlannedStep {
+    pub depth: usize,
+    pub kind: String,
+    pub target: Option<String>,
+    pub description: String,
+}
+
+impl PlannedStep {
+    pub fn new(depth: usize, kind: &str, target: Option<&str>, description: String) -> Self {
+        Self {
+            depth,
+            kind: kind.to_string(),
+            target: target.map(|t| t.to_string()),
+            description,
+        }
+    }
+}
+
+// 不执行任何操作，列出工作流会执行的步骤，分支的两侧都会列出
+pub fn plan_steps(steps: &[WorkflowStep], depth: usize, planned: &mut Vec<PlannedStep>) {
+    for step in steps {
+        let on_error = match step.on_error {
+            ErrorPolicy::Abort => String::new(),
+            ErrorPolicy::Continue => "（失败后继续）".to_string(),
+            ErrorPolicy::Retry { max_attempts, .. } => format!("（失败时最多尝试 {} 次）", max_attempts),
+        };
+        planned.push(PlannedStep::new(
+            depth,
+            step.kind.name(),
+            step.kind.target(),
+            format!("{}{}", step.kind.describe(), on_error),
+        ));
+
+        if let WorkflowStepKind::Branch { then_steps, else_steps, .. } = &step.kind {
+            planned.push(PlannedStep::new(depth + 1, "then", None, "条件满足时:".to_string()));
+            plan_steps(then_steps, depth + 2, planned);
+            planned.push(PlannedStep::new(depth + 1, "else", None, "条件不满足时:".to_string()));
+            plan_steps(else_steps, depth + 2, planned);
+        }
+    }
+}
+
+// 按顺序执行工作流，每次步骤尝试都记录到执行日志，被取消时在下一个步骤前停止
+pub async fn run_workflow(steps: &[WorkflowStep], recorder: &JournalRecorder, cancel: &CancelToken) -> Result<(), String> {
+    run_steps(steps, recorder, cancel).await
+}
+
+// 执行工作流，之后无论成功、失败还是被取消都执行收尾步骤
+// 收尾步骤使用新的取消标记，不会因为动作已被取消或超时而跳过
+pub async fn run_workflow_with_cleanup(
+    steps: &[WorkflowStep],
+    cleanup: &[WorkflowStep],
+    recorder: &JournalRecorder,
+    cancel: &CancelToken,
+) -> Result<(), String> {
+    let result = run_steps(steps, recorder, cancel).await;
+    if cancel.is_cancelled() {
+        info!("工作流已取消，继续执行收尾步骤");
+    }
+    let cleanup_result = run_steps(cleanup, recorder, &CancelToken::new()).await;
+    result.and(cleanup_result)
+}
+
+// 分支会递归执行子步骤，需要装箱
+fn run_steps<'a>(
+    steps: &'a [WorkflowStep],
+    recorder: &'a JournalRecorder,
+    cancel: &'a CancelToken,
+) -> Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>> {
+    Box::pin(async move {
+        for step in steps {
+            cancel.check()?;
+            run_step(step, recorder, cancel).await?;
+        }
+        Ok(())
+    })
+}
+
+async fn run_step(step: &WorkflowStep, recorder: &JournalRecorder, cancel: &CancelToken) -> Result<(), String> {
+    run_step_with(step, recorder, cancel, || run_step_kind(&step.kind, recorder, cancel)).await
+}
+
+// 按步骤的超时和错误处理方式执行，execute 负责执行一次步骤本身，测试时可以替换
+async fn run_step_with<F, Fut>(
+    step: &WorkflowStep,
+    recorder: &JournalRecorder,
+    cancel: &CancelToken,
+    mut execute: F,
+) -> Result<(), String>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    let (max_attempts, delay_seconds) = match step.on_error {
+        ErrorPolicy::Retry { max_attempts, delay_seconds } => (max_attempts.max(1), delay_seconds),
+        _ => (1, 0),
+    };
+
+    let mut attempt = 1;
+    let result = loop {
+        // 分支本身不记录，其子步骤会各自记录
+        let timer = match step.kind {
+            WorkflowStepKind::Branch { .. } => None,
+            _ => Some(recorder.start_step(step.kind.name(), step.kind.target(), attempt)),
+        };
+        let result = match step.timeout_seconds {
+            Some(seconds) => tokio::time::timeout(Duration::from_secs(seconds as u64), execute())
+                .await
+                .unwrap_or_else(|_| Err(format!("步骤超时（{} 秒）", seconds))),
+            None => execute().await,
+        };
+        if let Some(timer) = timer {
+            recorder.finish_step(timer, &result);
+        }
+
+        match result {
+            Err(e) if attempt < max_attempts && !cancel.is_cancelled() => {
+                warn!("工作流步骤失败，第 {}/{} 次尝试: {}", attempt, max_attempts, e);
+                attempt += 1;
+                cancel.sleep(Duration::from_secs(delay_seconds as u64)).await?;
+            }
+            result => break result,
+        }
+    };
+
+    // 因取消而失败的步骤不受错误处理方式影响，直接结束整个工作流
+    if cancel.is_cancelled() {
+        return result.map_err(|_| cancel.reason());
+    }
+
+    match (result, &step.on_error) {
+        (Err(e), ErrorPolicy::Continue) => {
+            warn!("工作流步骤失败，继续执行后续步骤: {}", e);
+            Ok(())
+        }
+        (result, _) => result,
+    }
+}
+
+async fn run_step_kind(kind: &WorkflowStepKind, recorder: &JournalRecorder, cancel: &CancelToken) -> Result<(), String> {
+    match kind {
+        WorkflowStepKind::SwitchPlan { plan_guid } => {
+            info!("工作流步骤: 切换电源计划 {}", plan_guid);
+            let guid = plan_guid.clone();
+            dry_run::spawn_blocking(move || set_active_plan(&guid))
+                .await
+                .map_err(|e| format!("切换电源计划任务异常: {}", e))?
+                .map_err(|e| format!("切换到计划 {} 失败: {}", plan_guid, e))
+        }
+        WorkflowStepKind::Wait { seconds } => cancel.sleep(Duration::from_secs(*seconds as u64)).await,
+        WorkflowStepKind::WriteSetting { plan_guid, subgroup_guid, setting_guid, ac_value, dc_value } => {
+            info!("工作流步骤: 写入电源设置 {} = AC {} / DC {}", setting_guid, ac_value, dc_value);
+            let (plan, subgroup, setting) = (plan_guid.clone(), subgroup_guid.clone(), setting_guid.clone());
+            let (ac_value, dc_value) = (*ac_value, *dc_value);
+            dry_run::spawn_blocking(move || write_value_set(&plan, &subgroup, &setting, ac_value, dc_value))
+                .await
+                .map_err(|e| format!("写入电源设置任务异常: {}", e))?
+                .map_err(|e| format!("写入电源设置 {} 失败: {}", setting_guid, e))
+        }
+        WorkflowStepKind::Notify { title, body } => send_notification(title, body),
+        WorkflowStepKind::RefreshMonitor => {
+            MONITOR.refresh_now().await;
+            Ok(())
+        }
+        WorkflowStepKind::Branch { condition, then_steps, else_steps } => {
+            if evaluate_condition(condition).await? {
+                run_steps(then_steps, recorder, cancel).await
+            } else {
+                run_steps(else_steps, recorder, cancel).await
+            }
+        }
+    }
+}
+
+async fn evaluate_condition(condition: &WorkflowCondition) -> Result<bool, String> {
+    match condition {
+        WorkflowCondition::StillAboveThreshold { threshold } => {
+            let threshold = threshold.unwrap_or_else(settings_store::get_effective_frequency_threshold);
+            let frequencies = Monitor::get_frequencies(&settings_store::get_frequency_mode()).await;
+            let above = frequencies.iter().any(|&freq| freq as f64 / 1000.0 > threshold);
+            info!("工作流条件: 频率是否仍高于 {:.2} GHz: {}", threshold, above);
+            Ok(above)
+        }
+        WorkflowCondition::ActivePlanIs { plan_guid } => {
+            let plans = get_power_plans()?;
+            Ok(plans
+                .iter()
+                .any(|p| p.is_active && p.guid.eq_ignore_ascii_case(plan_guid)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action_journal::JournalTrigger;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn step_with(on_error: ErrorPolicy) -> WorkflowStep {
+        WorkflowStep {
+            on_error,
+            ..WorkflowStep::new(WorkflowStepKind::Wait { seconds: 0 })
+        }
+    }
+
+    fn recorder() -> JournalRecorder {
+        JournalRecorder::new("a", "a", "workflow", JournalTrigger::threshold(&[4500], 4.0))
+    }
+
+    // 前 succeed_on - 1 次执行失败，之后成功
+    fn fail_until(calls: &AtomicU32, succeed_on: u32) -> impl FnMut() -> std::future::Ready<Result<(), String>> + '_ {
+        move || {
+            let attempt = calls.fetch_add(1, Ordering::SeqCst) + 1;
+            std::future::ready(if attempt < succeed_on {
+                Err(format!("第 {} 次失败", attempt))
+            } else {
+                Ok(())
+            })
+        }
+    }
+
+    fn outline(recorder: &JournalRecorder) -> Vec<(u32, bool)> {
+        recorder.steps().iter().map(|s| (s.attempt, s.success)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_retry_until_success() {
+        let step = step_with(ErrorPolicy::Retry { max_attempts: 3, delay_seconds: 0 });
+        let (recorder, calls) = (recorder(), AtomicU32::new(0));
+
+        run_step_with(&step, &recorder, &CancelToken::new(), fail_until(&calls, 3)).await.unwrap();
+        assert_eq!(outline(&recorder), vec![(1, false), (2, false), (3, true)]);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_max_attempts() {
+        let step = step_with(ErrorPolicy::Retry { max_attempts: 2, delay_seconds: 0 });
+        let (recorder, calls) = (recorder(), AtomicU32::new(0));
+
+        let result = run_step_with(&step, &recorder, &CancelToken::new(), fail_until(&calls, u32::MAX)).await;
+        assert_eq!(result.unwrap_err(), "第 2 次失败");
+        assert_eq!(outline(&recorder), vec![(1, false), (2, false)]);
+    }
+
+    #[tokio::test]
+    async fn test_continue_and_abort() {
+        let (recorder, calls) = (recorder(), AtomicU32::new(0));
+        let cancel = CancelToken::new();
+
+        let result = run_step_with(&step_with(ErrorPolicy::Continue), &recorder, &cancel, fail_until(&calls, u32::MAX)).await;
+        assert!(result.is_ok());
+        let result = run_step_with(&step_with(ErrorPolicy::Abort), &recorder, &cancel, fail_until(&calls, u32::MAX)).await;
+        assert_eq!(result.unwrap_err(), "第 2 次失败");
+        // 两种方式都只执行一次，失败都会记录
+        assert_eq!(outline(&recorder), vec![(1, false), (1, false)]);
+    }
+
+    #[tokio::test]
+    async fn test_step_timeout() {
+        let step = WorkflowStep {
+            timeout_seconds: Some(1),
+            ..step_with(ErrorPolicy::Abort)
+        };
+        let recorder = recorder();
+
+        let result = run_step_with(&step, &recorder, &CancelToken::new(), || async {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(())
+        })
+        .await;
+        assert_eq!(result.unwrap_err(), "步骤超时（1 秒）");
+        assert_eq!(outline(&recorder), vec![(1, false)]);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_during_retry_delay() {
+        let step = step_with(ErrorPolicy::Retry { max_attempts: 3, delay_seconds: 30 });
+        let (recorder, calls) = (recorder(), AtomicU32::new(0));
+        let cancel = CancelToken::new();
+
+        let (result, ()) = tokio::join!(run_step_with(&step, &recorder, &cancel, fail_until(&calls, u32::MAX)), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            cancel.cancel("触发动作已被用户取消");
+        });
+        // 等待重试时被取消，不再尝试，返回取消原因
+        assert_eq!(result.unwrap_err(), "触发动作已被用户取消");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_retry_on_branch_is_rejected() {
+        let branch = WorkflowStep {
+            on_error: ErrorPolicy::Retry { max_attempts: 2, delay_seconds: 0 },
+            ..WorkflowStep::new(WorkflowStepKind::Branch {
+                condition: WorkflowCondition::StillAboveThreshold { threshold: None },
+                then_steps: Vec::new(),
+                else_steps: Vec::new(),
+            })
+        };
+        let err = validate_steps(&[branch]).unwrap_err();
+        assert!(err.contains("不能设置失败重试"), "{}", err);
+    }
+}
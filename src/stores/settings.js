@@ -61,6 +61,12 @@ export const useSettingsStore = defineStore('settings', {
     frequency_detection_enabled: true,
     alert_debounce_seconds: 15,
     accepted_terms_of_service: -1,
+    trigger_dispatch_policy: 'first_match',
+    power_source_profiles: {
+      ac: { frequency_threshold: null, refresh_interval: null },
+      battery: { frequency_threshold: null, refresh_interval: null }
+    },
+    dry_run_enabled: false,
   }),
 
   actions: {